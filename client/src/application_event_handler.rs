@@ -54,7 +54,9 @@ impl EventHandler for ApplicationEventHandler {
         (continue_render, cursor)
     }
 
-    fn on_close(&mut self) {}
+    fn on_close(&mut self) {
+        self.game_manager.quit_game();
+    }
 
     fn focus_gained(&mut self) {}
 
@@ -0,0 +1,410 @@
+use crate::chunk::ChunkMesh;
+use block_chunk::mesh::{ChunkNeighbours, FaceDirection};
+use cgmath::{vec3, Deg, Euler, InnerSpace, Quaternion, Rotation, Vector3};
+use face_texture_map::FaceTextureMap;
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::sync::atomic::{AtomicBool, Ordering as AtomicOrdering};
+use std::sync::Arc;
+use tokio::sync::{Mutex, Notify};
+use voxelcraft_core::block::BlockPosition;
+use voxelcraft_core::chunk::ChunkPosition;
+use voxelcraft_id::BlockId;
+use voxelcraft_mod::Block;
+use voxelcraft_server::client::Client;
+use voxelcraft_server::CHUNK_SIZE;
+use wgpu::Device;
+
+/// Keeps the chunks around the player meshed as the player moves.
+///
+/// Chunks that come into view are meshed on worker tasks, nearest first, with the chunks in front
+/// of the camera counting as nearer than those behind it. Meshes are kept until their chunk is more
+/// than a chunk out of view, so that walking back and forth over a chunk border does not mesh the
+/// same chunks over and over again. Chunks are meshed again when their blocks change, and when a
+/// neighbour that was out of view when they were meshed comes into view
+#[derive(Debug)]
+pub struct ChunkManager {
+    view_distance: usize,
+    workers: usize,
+    device: Arc<Device>,
+    face_texture_map: Arc<FaceTextureMap>,
+    blocks: Arc<HashMap<BlockId, Arc<dyn Block>>>,
+    meshes: Mutex<HashMap<ChunkPosition, ChunkMesh>>,
+    /// Meshes are only added or dropped while holding this as well, so that a mesh can not be
+    /// kept for a chunk that just went out of view
+    state: std::sync::Mutex<State>,
+    /// Wakes up a worker when there are chunks to mesh, or all of them when they should stop
+    queue_notify: Notify,
+    is_stopped: AtomicBool,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    /// The chunk the player is in, once it is known
+    center: Option<ChunkPosition>,
+    /// Where the camera is looking, in degrees
+    yaw: f32,
+    /// The chunks in view that are waiting for a worker
+    queued: HashSet<ChunkPosition>,
+    /// The chunks a worker is meshing right now
+    meshing: HashSet<ChunkPosition>,
+    /// The neighbours that were out of view when a chunk was meshed, so that it can be meshed
+    /// again once they are, to cull its border and shade its corners against them
+    missing_neighbours: HashMap<ChunkPosition, Vec<ChunkPosition>>,
+}
+
+impl ChunkManager {
+    pub fn new(
+        view_distance: usize,
+        device: Arc<Device>,
+        face_texture_map: Arc<FaceTextureMap>,
+        blocks: Arc<HashMap<BlockId, Arc<dyn Block>>>,
+    ) -> Self {
+        Self {
+            view_distance,
+            workers: num_cpus::get(),
+            device,
+            face_texture_map,
+            blocks,
+            meshes: Mutex::new(HashMap::new()),
+            state: std::sync::Mutex::new(State::default()),
+            queue_notify: Notify::new(),
+            is_stopped: AtomicBool::new(false),
+        }
+    }
+
+    /// Starts the workers that mesh the chunks, which they read from the client
+    pub fn start<C: Client + Send + Sync + 'static>(self: &Arc<Self>, client: &Arc<C>) {
+        for _ in 0..self.workers {
+            tokio::spawn(Arc::clone(self).work(Arc::clone(client)));
+        }
+    }
+
+    /// Stops the workers once they are done with the chunk they are meshing
+    pub fn stop(&self) {
+        self.is_stopped.store(true, AtomicOrdering::Relaxed);
+        self.queue_notify.notify_waiters();
+    }
+
+    pub fn meshes(&self) -> &Mutex<HashMap<ChunkPosition, ChunkMesh>> {
+        &self.meshes
+    }
+
+    /// Centers the view on the chunk the player is in. Chunks that came into view are queued, and
+    /// the meshes of chunks that are too far away are dropped
+    pub async fn move_to(&self, center: ChunkPosition) {
+        let mut meshes = self.meshes.lock().await;
+        let queued_count = {
+            let mut state = self.state.lock().unwrap();
+            if state.center == Some(center) {
+                return;
+            }
+            state.center = Some(center);
+
+            meshes.retain(|position, _| is_in_range(&center, position, self.view_distance + 1));
+            state
+                .missing_neighbours
+                .retain(|position, _| meshes.contains_key(position));
+            let queued = center
+                .surrounding_chunks(self.view_distance)
+                .into_iter()
+                .filter(|position| {
+                    !meshes.contains_key(position) && !state.meshing.contains(position)
+                })
+                .collect::<Vec<_>>();
+            // Chunks that are queued to be meshed again stay queued while they are in view
+            state
+                .queued
+                .retain(|position| is_in_range(&center, position, self.view_distance));
+            state.queued.extend(queued);
+            state.queued.len()
+        };
+
+        // Busy workers look for more chunks on their own once they are done
+        for _ in 0..queued_count.min(self.workers) {
+            self.queue_notify.notify_one();
+        }
+    }
+
+    /// Meshes the chunk of the block again, and the chunks next to it when the block is on their
+    /// border, as the block can cover their faces and shade their corners
+    pub fn block_changed(&self, position: &BlockPosition<CHUNK_SIZE>) {
+        let offset = &position.offset;
+        let last = CHUNK_SIZE - 1;
+        let mut chunks = vec![position.chunk_position];
+        for (is_on_border, direction) in [
+            (offset.x == 0, FaceDirection::West),
+            (offset.x == last, FaceDirection::East),
+            (offset.y == 0, FaceDirection::Down),
+            (offset.y == last, FaceDirection::Up),
+            (offset.z == 0, FaceDirection::North),
+            (offset.z == last, FaceDirection::South),
+        ] {
+            if is_on_border {
+                chunks.push(neighbour(&position.chunk_position, direction));
+            }
+        }
+        self.queue(&chunks);
+    }
+
+    /// Turns the camera, which changes the chunks that are meshed first
+    pub fn set_yaw(&self, yaw: Deg<f32>) {
+        self.state.lock().unwrap().yaw = yaw.0;
+    }
+
+    /// How far along meshing the chunks in view is, in percent
+    pub fn progress(&self) -> f32 {
+        let state = self.state.lock().unwrap();
+        let size = self.view_distance * 2 + 1;
+        let total = (size * size * size) as f32;
+        let remaining = state.queued.union(&state.meshing).count() as f32;
+        100.0 * (total - remaining) / total
+    }
+
+    /// Queues the chunks that are in view to be meshed, even when they have a mesh already
+    fn queue(&self, positions: &[ChunkPosition]) {
+        let queued_count = {
+            let mut state = self.state.lock().unwrap();
+            let center = match state.center {
+                Some(center) => center,
+                None => return,
+            };
+            positions
+                .iter()
+                .filter(|position| is_in_range(&center, position, self.view_distance))
+                .filter(|position| state.queued.insert(**position))
+                .count()
+        };
+
+        for _ in 0..queued_count.min(self.workers) {
+            self.queue_notify.notify_one();
+        }
+    }
+
+    async fn work<C: Client + Send + Sync>(self: Arc<Self>, client: Arc<C>) {
+        loop {
+            // Listening before looking, so that a stop in between is not missed
+            let notified = self.queue_notify.notified();
+            if self.is_stopped.load(AtomicOrdering::Relaxed) {
+                return;
+            }
+            let position = match self.take_next_chunk() {
+                Some(position) => position,
+                None => {
+                    notified.await;
+                    continue;
+                }
+            };
+
+            let mesh = self.mesh(client.as_ref(), position).await;
+
+            let mut meshes = self.meshes.lock().await;
+            let waiting_neighbours = {
+                let mut state = self.state.lock().unwrap();
+                state.meshing.remove(&position);
+                match mesh {
+                    Ok((mesh, missing_neighbours)) => {
+                        let is_in_view = state.center.map_or(false, |center| {
+                            is_in_range(&center, &position, self.view_distance)
+                        });
+                        if is_in_view {
+                            meshes.insert(position, mesh);
+                            state
+                                .missing_neighbours
+                                .insert(position, missing_neighbours);
+                        }
+                        // The neighbours that were meshed while this chunk was out of view
+                        FaceDirection::ALL
+                            .iter()
+                            .map(|direction| neighbour(&position, *direction))
+                            .filter(|neighbour| {
+                                state
+                                    .missing_neighbours
+                                    .get(neighbour)
+                                    .map_or(false, |missing| missing.contains(&position))
+                            })
+                            .collect::<Vec<_>>()
+                    }
+                    Err(error) => {
+                        log::error!("Failed to mesh chunk {}: {}", position, error);
+                        Vec::new()
+                    }
+                }
+            };
+            drop(meshes);
+            self.queue(&waiting_neighbours);
+        }
+    }
+
+    /// Takes the queued chunk with the highest priority, if there is one. Chunks that are being
+    /// meshed already wait until that is done, so that an older mesh can not replace a newer one
+    fn take_next_chunk(&self) -> Option<ChunkPosition> {
+        let mut state = self.state.lock().unwrap();
+        let state = &mut *state;
+        let center = state.center?;
+        let forward = forward(Deg(state.yaw));
+        let meshing = &state.meshing;
+        let position = state
+            .queued
+            .iter()
+            .filter(|position| !meshing.contains(position))
+            .copied()
+            .min_by(|a, b| {
+                priority(&center, a, forward)
+                    .partial_cmp(&priority(&center, b, forward))
+                    .unwrap_or(Ordering::Equal)
+            })?;
+        state.queued.remove(&position);
+        state.meshing.insert(position);
+        Some(position)
+    }
+
+    /// Meshes the chunk, and returns the neighbours it was meshed without
+    async fn mesh<C: Client + Send + Sync>(
+        &self,
+        client: &C,
+        position: ChunkPosition,
+    ) -> Result<(ChunkMesh, Vec<ChunkPosition>), Box<dyn Error + Send + Sync>> {
+        let (neighbours, missing_neighbours) = self.collect_neighbours(client, &position).await?;
+        let device = Arc::clone(&self.device);
+        let face_texture_map = Arc::clone(&self.face_texture_map);
+        let blocks = Arc::clone(&self.blocks);
+        let mesh = client
+            .get_chunk(position, |chunk| async move {
+                ChunkMesh::new(
+                    &device,
+                    &chunk,
+                    &neighbours,
+                    &position,
+                    &face_texture_map,
+                    &blocks,
+                )
+                .await
+            })
+            .await??;
+        Ok((mesh, missing_neighbours))
+    }
+
+    /// Copies the edges of the chunks next to the given chunk, so that the faces they cover can be
+    /// culled. Only chunks that are in view are used, so that no extra chunks have to be generated.
+    /// The neighbours that were out of view are returned as well
+    async fn collect_neighbours<C: Client + Send + Sync>(
+        &self,
+        client: &C,
+        position: &ChunkPosition,
+    ) -> Result<
+        (ChunkNeighbours<BlockId, CHUNK_SIZE>, Vec<ChunkPosition>),
+        Box<dyn Error + Send + Sync>,
+    > {
+        let center = self.state.lock().unwrap().center;
+        let mut neighbours = ChunkNeighbours::new();
+        let mut missing_neighbours = Vec::new();
+
+        for direction in FaceDirection::ALL {
+            let neighbour_position = neighbour(position, direction);
+            let is_in_view = center.map_or(false, |center| {
+                is_in_range(&center, &neighbour_position, self.view_distance)
+            });
+            if !is_in_view {
+                missing_neighbours.push(neighbour_position);
+                continue;
+            }
+
+            neighbours = client
+                .get_chunk(neighbour_position, |chunk| async move {
+                    let mut neighbours = neighbours;
+                    neighbours.insert(direction, &chunk);
+                    neighbours
+                })
+                .await?;
+        }
+
+        Ok((neighbours, missing_neighbours))
+    }
+}
+
+/// The chunk next to the given chunk in the direction
+fn neighbour(position: &ChunkPosition, direction: FaceDirection) -> ChunkPosition {
+    let (x, y, z) = match direction {
+        FaceDirection::North => (0, 0, -1),
+        FaceDirection::South => (0, 0, 1),
+        FaceDirection::West => (-1, 0, 0),
+        FaceDirection::East => (1, 0, 0),
+        FaceDirection::Up => (0, 1, 0),
+        FaceDirection::Down => (0, -1, 0),
+    };
+    ChunkPosition {
+        x: position.x + x,
+        y: position.y + y,
+        z: position.z + z,
+        dimension: position.dimension,
+    }
+}
+
+/// Whether the chunk is in the same dimension, and at most `range` chunks away on every axis
+fn is_in_range(center: &ChunkPosition, position: &ChunkPosition, range: usize) -> bool {
+    let range = range as i32;
+    position.dimension == center.dimension
+        && (position.x - center.x).abs() <= range
+        && (position.y - center.y).abs() <= range
+        && (position.z - center.z).abs() <= range
+}
+
+/// The direction the player walks in when moving forward, the same way the server turns it
+fn forward(yaw: Deg<f32>) -> Vector3<f32> {
+    let angle = Quaternion::from(Euler::<Deg<f32>> {
+        x: Deg(180.0),
+        y: yaw + Deg(180.0),
+        z: Deg(0.0),
+    });
+    angle.rotate_vector(vec3(0.0, 0.0, 1.0))
+}
+
+/// Lower is meshed sooner. This is the distance to the player, where chunks behind the camera
+/// count as up to three times as far away as chunks in front of it
+fn priority(center: &ChunkPosition, position: &ChunkPosition, forward: Vector3<f32>) -> f32 {
+    let offset = vec3(
+        (position.x - center.x) as f32,
+        (position.y - center.y) as f32,
+        (position.z - center.z) as f32,
+    );
+    let distance = offset.magnitude();
+    if distance == 0.0 {
+        return 0.0;
+    }
+
+    let alignment = offset.dot(forward) / distance;
+    distance * (2.0 - alignment)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::chunk::chunk_manager::{forward, priority};
+    use cgmath::Deg;
+    use voxelcraft_core::chunk::ChunkPosition;
+
+    fn chunk(x: i32, y: i32, z: i32) -> ChunkPosition {
+        ChunkPosition {
+            x,
+            y,
+            z,
+            dimension: Default::default(),
+        }
+    }
+
+    #[test]
+    fn it_should_prioritize_chunks_in_front_of_the_camera() {
+        let center = chunk(0, 0, 0);
+        let forward = forward(Deg(0.0));
+
+        let in_front = priority(&center, &chunk(0, 0, 2), forward);
+        let behind = priority(&center, &chunk(0, 0, -1), forward);
+        let beside = priority(&center, &chunk(1, 0, 0), forward);
+
+        assert_eq!(priority(&center, &center, forward), 0.0);
+        assert!(in_front < behind);
+        assert!(beside < behind);
+        assert!(priority(&center, &chunk(0, 0, 1), forward) < beside);
+    }
+}
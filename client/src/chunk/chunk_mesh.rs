@@ -1,12 +1,15 @@
 use crate::chunk::create_down_faces::create_down_faces;
 use crate::chunk::create_east_faces::create_east_faces;
 use crate::chunk::create_north_faces::create_north_faces;
+use crate::chunk::create_shape_faces::create_shape_faces;
 use crate::chunk::create_south_faces::create_south_faces;
 use crate::chunk::create_up_faces::create_up_faces;
 use crate::chunk::create_west_faces::create_west_faces;
 use crate::gpu::primitives::{SmallTexturedArrayVertex, TexturedArrayVertex};
 use crate::gpu::RenderContext;
-use block_chunk::mesh::{BlockDescriptor, Face, FaceDirection, MeshableChunk};
+use block_chunk::mesh::{
+    BlockDescriptor, BlockShape, ChunkNeighbours, Face, FaceDirection, MeshableChunk, ShapeFace,
+};
 use face_texture_map::FaceTextureMap;
 use smallvec::SmallVec;
 use std::collections::HashMap;
@@ -34,6 +37,7 @@ impl ChunkMesh {
     pub async fn new(
         device: &Arc<Device>,
         chunk: &Chunk,
+        neighbours: &ChunkNeighbours<BlockId, CHUNK_SIZE>,
         position: &ChunkPosition,
         face_texture_map: &Arc<FaceTextureMap>,
         blocks: &Arc<HashMap<BlockId, Arc<dyn Block>>>,
@@ -41,16 +45,17 @@ impl ChunkMesh {
         log::debug!("Building mesh for chunk at: {}", position);
         let start_time = Instant::now();
         let meshes = chunk
-            .greedy_mesh(
+            .greedy_mesh_with_neighbours(
+                neighbours,
                 |id| {
                     if let Some(block) = blocks.get(id) {
                         Some(BlockDescriptor {
-                            is_standard_square: block.is_standard_square(),
+                            shape: block.shape(),
                             is_transparent: block.is_transparent(),
                         })
                     } else if id == &BlockId::DEBUG {
                         Some(BlockDescriptor {
-                            is_standard_square: true,
+                            shape: BlockShape::Cube,
                             is_transparent: false,
                         })
                     } else {
@@ -72,7 +77,12 @@ impl ChunkMesh {
         let device = Arc::clone(&device);
         let position = *position;
         let handle = tokio::task::spawn_blocking(move || {
-            let (mesh, mesh_indices) = Self::convert_mesh(&position, meshes.mesh);
+            let (mesh, mesh_indices) = Self::convert_mesh(
+                &position,
+                meshes.mesh,
+                meshes.shape_mesh,
+                meshes.transparent_shape_mesh,
+            );
 
             let vertex_buffer = device.create_buffer_init(&BufferInitDescriptor {
                 label: Some(&format!("Chunk vertex buffer at: {}", position)),
@@ -114,6 +124,8 @@ impl ChunkMesh {
     fn convert_mesh(
         position: &ChunkPosition,
         faces: Vec<Face<i32, CHUNK_SIZE>>,
+        shape_faces: Vec<ShapeFace<i32, CHUNK_SIZE>>,
+        transparent_shape_faces: Vec<ShapeFace<i32, CHUNK_SIZE>>,
     ) -> (Vec<SmallTexturedArrayVertex>, Vec<u32>) {
         let mut indices = vec![];
         let mut vertices = vec![];
@@ -195,6 +207,22 @@ impl ChunkMesh {
             }
         }
 
+        // The transparent faces go last, so that they are drawn after everything behind them
+        for face in shape_faces.iter().chain(&transparent_shape_faces) {
+            let offset = vertices.len() as u32;
+            vertices.extend_from_slice(&create_shape_faces(position, face));
+
+            // Shape faces are always wound counter clockwise
+            indices.extend_from_slice(&[
+                offset,
+                offset + 1,
+                offset + 2,
+                offset,
+                offset + 2,
+                offset + 3,
+            ]);
+        }
+
         (vertices, indices)
     }
 }
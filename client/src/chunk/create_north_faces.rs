@@ -21,6 +21,7 @@ pub fn create_north_faces(
             ],
             tex_coords: [0.0, 0.0],
             tex_index: face.texture as i32,
+            ambient_occlusion: f32::from(face.ambient_occlusion[0]) / 3.0,
         },
         SmallTexturedArrayVertex {
             position: [
@@ -30,6 +31,7 @@ pub fn create_north_faces(
             ],
             tex_coords: [0.0, face.height as f32],
             tex_index: face.texture as i32,
+            ambient_occlusion: f32::from(face.ambient_occlusion[1]) / 3.0,
         },
         SmallTexturedArrayVertex {
             position: [
@@ -39,6 +41,7 @@ pub fn create_north_faces(
             ],
             tex_coords: [face.width as f32, face.height as f32],
             tex_index: face.texture as i32,
+            ambient_occlusion: f32::from(face.ambient_occlusion[2]) / 3.0,
         },
         SmallTexturedArrayVertex {
             position: [
@@ -48,6 +51,7 @@ pub fn create_north_faces(
             ],
             tex_coords: [face.width as f32, 0.0],
             tex_index: face.texture as i32,
+            ambient_occlusion: f32::from(face.ambient_occlusion[3]) / 3.0,
         },
     ]
 }
@@ -73,6 +77,7 @@ mod tests {
             height: 1,
             texture: 0,
             is_transparent: false,
+            ambient_occlusion: [3; 4],
         };
 
         let result = create_north_faces(&position, &face);
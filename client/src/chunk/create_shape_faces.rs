@@ -0,0 +1,54 @@
+use crate::gpu::primitives::SmallTexturedArrayVertex;
+use block_chunk::mesh::ShapeFace;
+use voxelcraft_core::chunk::ChunkPosition;
+use voxelcraft_server::CHUNK_SIZE;
+
+pub fn create_shape_faces(
+    position: &ChunkPosition,
+    face: &ShapeFace<i32, CHUNK_SIZE>,
+) -> [SmallTexturedArrayVertex; 4] {
+    let position_x = position.x as f32 * CHUNK_SIZE as f32 + face.position.x as f32;
+    let position_y = position.y as f32 * CHUNK_SIZE as f32 + face.position.y as f32;
+    let position_z = position.z as f32 * CHUNK_SIZE as f32 + face.position.z as f32;
+
+    let vertex = |index: usize| {
+        let [x, y, z] = face.vertices[index];
+        SmallTexturedArrayVertex {
+            position: [position_x + x, position_y + y, position_z + z],
+            tex_coords: face.tex_coords[index],
+            tex_index: face.texture as i32,
+            ambient_occlusion: 1.0,
+        }
+    };
+
+    [vertex(0), vertex(1), vertex(2), vertex(3)]
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::chunk::create_shape_faces::create_shape_faces;
+    use block_chunk::mesh::{FaceDirection, ShapeBox, ShapeFace};
+    use voxelcraft_core::chunk::ChunkPosition;
+
+    #[test]
+    fn it_should_give_correct_results() {
+        let position = ChunkPosition {
+            x: 1,
+            y: 1,
+            z: 1,
+            dimension: Default::default(),
+        };
+        let face = ShapeFace {
+            position: (1, 0, 0).into(),
+            vertices: ShapeBox::new([0.0; 3], [1.0, 0.5, 1.0]).side(FaceDirection::Up),
+            tex_coords: [[0.0; 2]; 4],
+            texture: 0,
+            is_transparent: false,
+        };
+
+        let result = create_shape_faces(&position, &face);
+
+        assert_eq!(result[0].position, [33.0, 32.5, 32.0]);
+        assert_eq!(result[2].position, [34.0, 32.5, 33.0]);
+    }
+}
@@ -20,6 +20,7 @@ pub fn create_up_faces(
             ],
             tex_coords: [0.0, 0.0],
             tex_index: face.texture as i32,
+            ambient_occlusion: f32::from(face.ambient_occlusion[0]) / 3.0,
         },
         SmallTexturedArrayVertex {
             position: [
@@ -29,6 +30,7 @@ pub fn create_up_faces(
             ],
             tex_coords: [0.0, face.height as f32],
             tex_index: face.texture as i32,
+            ambient_occlusion: f32::from(face.ambient_occlusion[1]) / 3.0,
         },
         SmallTexturedArrayVertex {
             position: [
@@ -38,6 +40,7 @@ pub fn create_up_faces(
             ],
             tex_coords: [face.width as f32, face.height as f32],
             tex_index: face.texture as i32,
+            ambient_occlusion: f32::from(face.ambient_occlusion[2]) / 3.0,
         },
         SmallTexturedArrayVertex {
             position: [
@@ -47,6 +50,7 @@ pub fn create_up_faces(
             ],
             tex_coords: [face.width as f32, 0.0],
             tex_index: face.texture as i32,
+            ambient_occlusion: f32::from(face.ambient_occlusion[3]) / 3.0,
         },
     ]
 }
@@ -1,9 +1,12 @@
+mod chunk_manager;
 mod chunk_mesh;
 mod create_down_faces;
 mod create_east_faces;
 mod create_north_faces;
+mod create_shape_faces;
 mod create_south_faces;
 mod create_up_faces;
 mod create_west_faces;
 
+pub use self::chunk_manager::ChunkManager;
 pub use self::chunk_mesh::ChunkMesh;
@@ -16,4 +16,6 @@ pub trait Game: Debug {
     fn cleanup(&mut self);
     fn get_messages(&mut self) -> Vec<Message>;
     fn resize(&mut self, size: Size);
+    /// Leaves the game, making sure everything worth keeping is saved
+    fn quit(&mut self);
 }
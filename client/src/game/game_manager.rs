@@ -101,6 +101,7 @@ impl GameManager {
                 let device = Arc::clone(&self.device);
                 self.create_new_world(device)
             }
+            Message::QuitApplication => self.quit_game(),
             _ => {}
         }
     }
@@ -118,7 +119,24 @@ impl GameManager {
             &self.resources.face_texture_map,
             &self.resources.blocks,
         );
-        self.game = GameWrapper::Local(local_game)
+        match local_game {
+            Ok(local_game) => self.game = GameWrapper::Local(local_game),
+            Err(error) => {
+                log::error!("Failed to open the local world: {}", error);
+                self.messages.push(Message::GameLoadingFailed(format!(
+                    "Failed to open the world: {}",
+                    error
+                )));
+            }
+        }
+    }
+
+    /// Quits the running game, if there is one
+    pub fn quit_game(&mut self) {
+        if let Some(game) = self.game.game_mut() {
+            game.quit()
+        }
+        self.game = GameWrapper::None;
     }
 
     pub fn get_messages(&mut self) -> Vec<Message> {
@@ -1,18 +1,21 @@
-use crate::chunk::ChunkMesh;
+use crate::chunk::ChunkManager;
 use crate::game::game::Game;
+use crate::game::local_player_id::local_player_id;
 use crate::gpu::RenderContext;
 use crate::interface::{Message, IN_GAME_HUD_PAGE_ROUTE};
 use crate::primitives::Size;
-use futures::{stream, StreamExt, TryStreamExt};
 use std::error::Error;
-use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
-use std::time::Instant;
+use std::time::{Duration, Instant};
+use tokio::sync::broadcast::error::RecvError;
 use tokio::sync::Mutex;
-use uuid::Uuid;
 use voxelcraft_server::client::Client;
 use voxelcraft_server::local::new_local_world;
 use voxelcraft_server::local::LocalClient;
+use voxelcraft_server::storage::FileStorage;
+use voxelcraft_server::world::WorldHandle;
 
 use crate::game::resources::GameResources;
 use crate::input::{InputManager, UserAction, UserActionState};
@@ -23,62 +26,90 @@ use std::collections::HashMap;
 use voxelcraft_id::BlockId;
 use voxelcraft_mod::{Block, ModPack};
 use voxelcraft_server::event::WorldEvent;
+use voxelcraft_server::CHUNK_SIZE;
 use wgpu::{
     CommandBuffer, CommandEncoderDescriptor, Device, Operations, RenderPassDepthStencilAttachment,
     RenderPassDescriptor, RenderPipeline,
 };
 
+/// Where the local world is saved, relative to the working directory
+const SAVE_DIRECTORY: &str = "saves/world";
+/// How often the local world is saved while playing
+const AUTOSAVE_INTERVAL: Duration = Duration::from_secs(5 * 60);
+/// How far around the player chunks are meshed, in chunks
+const VIEW_DISTANCE: usize = 3;
+/// How often the loading screen shows how far along meshing the first chunks is
+const LOADING_PROGRESS_INTERVAL: Duration = Duration::from_millis(100);
+
 #[derive(Debug)]
 pub struct LocalGame {
     client: Arc<LocalClient>,
+    world_handle: Option<WorldHandle>,
     messages: Arc<std::sync::Mutex<Vec<Message>>>,
     is_loading: Arc<AtomicBool>,
     device: Arc<Device>,
-    chunk_meshes: Arc<Mutex<Vec<ChunkMesh>>>,
+    chunk_manager: Arc<ChunkManager>,
     head_rotation_delta: Arc<Mutex<(f64, f64)>>,
     player_position: Arc<Mutex<(f32, f32, f32)>>,
     mod_pack: Arc<dyn ModPack>,
 }
 
 impl LocalGame {
+    /// Opens the local world, or creates it when there is none yet, and starts loading the game
     pub fn new(
         device: Arc<Device>,
         input_manager: &Arc<InputManager>,
         mod_pack: &Arc<dyn ModPack>,
         face_texture_map: &Arc<FaceTextureMap>,
         blocks: &Arc<HashMap<BlockId, Arc<dyn Block>>>,
-    ) -> Self {
-        let client = Arc::new(new_local_world(0, Uuid::new_v4(), mod_pack));
+    ) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        let player_id = local_player_id(Path::new(SAVE_DIRECTORY))?;
+        let (client, world_handle) = new_local_world(
+            "World",
+            0,
+            player_id,
+            mod_pack,
+            FileStorage::new(SAVE_DIRECTORY),
+            Some(AUTOSAVE_INTERVAL),
+        )?;
+        let client = Arc::new(client);
         let messages = Arc::new(std::sync::Mutex::new(vec![]));
-        let chunk_meshes = Arc::new(Mutex::new(vec![]));
+        let chunk_manager = Arc::new(ChunkManager::new(
+            VIEW_DISTANCE,
+            Arc::clone(&device),
+            Arc::clone(face_texture_map),
+            Arc::clone(blocks),
+        ));
         let is_loading = Arc::new(AtomicBool::new(true));
         let head_rotation_delta = Arc::new(Mutex::new((0.0, 0.0)));
         let player_position = Arc::new(Mutex::new((0.0, 0.0, 0.0)));
         let mod_pack = Arc::clone(mod_pack);
 
-        tokio::spawn(Self::process_events(Arc::clone(&client)));
+        chunk_manager.start(&client);
+        tokio::spawn(Self::process_events(
+            Arc::clone(&client),
+            Arc::clone(&chunk_manager),
+        ));
         tokio::spawn(Self::start_connection_process(
             Arc::clone(&client),
-            Arc::clone(&device),
-            Arc::clone(&chunk_meshes),
+            Arc::clone(&chunk_manager),
             Arc::clone(&messages),
             Arc::clone(&is_loading),
             Arc::clone(&input_manager),
             Arc::clone(&head_rotation_delta),
-            Arc::clone(&face_texture_map),
-            Arc::clone(&blocks),
         ));
 
-        Self {
+        Ok(Self {
             client,
+            world_handle: Some(world_handle),
             messages,
             is_loading,
             device,
-            chunk_meshes,
+            chunk_manager,
             head_rotation_delta,
             player_position,
             mod_pack,
-        }
+        })
     }
 
     async fn process_head_rotation_delta(
@@ -149,79 +180,42 @@ impl LocalGame {
 
     async fn start_connection_process(
         client: Arc<LocalClient>,
-        device: Arc<Device>,
-        chunk_meshes: Arc<Mutex<Vec<ChunkMesh>>>,
+        chunk_manager: Arc<ChunkManager>,
         messages: Arc<std::sync::Mutex<Vec<Message>>>,
         is_loading: Arc<AtomicBool>,
         input_manager: Arc<InputManager>,
         head_rotation_delta: Arc<Mutex<(f64, f64)>>,
-        face_texture_map: Arc<FaceTextureMap>,
-        blocks: Arc<HashMap<BlockId, Arc<dyn Block>>>,
     ) -> Result<(), Box<dyn Error + Send + Sync>> {
         Self::send_loading_message(&messages, "Preparing world", None);
 
-        client.begin_joining_world().await;
+        client.begin_joining_world().await?;
 
         let player_position = client.position().await;
 
-        let chunks_to_mesh = player_position.surrounding_chunks(2);
-        let chunks_to_mesh_count = chunks_to_mesh.len();
-
         Self::send_loading_message(&messages, "Building chunks in the player vicinity", None);
 
         log::info!("Meshing chunks");
         let instant_start = Instant::now();
-        let count_processed = Arc::new(AtomicU32::new(0));
-        let mut meshes = stream::iter(chunks_to_mesh)
-            .map(|position| {
-                let client = Arc::clone(&client);
-                let device = Arc::clone(&device);
-                let count_processed = Arc::clone(&count_processed);
-                let messages = Arc::clone(&messages);
-                let face_texture_map = Arc::clone(&face_texture_map);
-                let blocks = Arc::clone(&blocks);
-
-                async move {
-                    let handle = tokio::spawn(async move {
-                        client
-                            .get_chunk(position, |chunk| async move {
-                                ChunkMesh::new(
-                                    &device,
-                                    &chunk,
-                                    &position,
-                                    &face_texture_map,
-                                    &blocks,
-                                )
-                                .await
-                            })
-                            .await
-                    });
-                    let mesh = handle.await???;
-                    let current_count = count_processed.fetch_add(1, Ordering::Relaxed);
-                    let progress = (100.0 / chunks_to_mesh_count as f32) * current_count as f32;
-                    Self::send_loading_message(
-                        &messages,
-                        "Building chunks in the player vicinity",
-                        Some(progress),
-                    );
-                    Result::<_, Box<dyn Error + Send + Sync>>::Ok(mesh)
-                }
-            })
-            .buffer_unordered(num_cpus::get() * 2)
-            .try_collect::<Vec<_>>()
-            .await?;
+        chunk_manager.move_to(player_position.chunk_position).await;
+        let mut interval = tokio::time::interval(LOADING_PROGRESS_INTERVAL);
+        loop {
+            interval.tick().await;
+            let progress = chunk_manager.progress();
+            if progress >= 100.0 {
+                break;
+            }
+            Self::send_loading_message(
+                &messages,
+                "Building chunks in the player vicinity",
+                Some(progress),
+            );
+        }
 
         log::info!(
-            "Successfully meshed {} chunks in {:?}",
-            chunks_to_mesh_count,
+            "Successfully meshed the chunks around the player in {:?}",
             Instant::now().duration_since(instant_start)
         );
 
-        {
-            let mut lock = chunk_meshes.lock().await;
-            lock.append(&mut meshes);
-        }
-
         Self::finnish_loading(&messages);
         is_loading.store(false, Ordering::Relaxed);
 
@@ -265,14 +259,27 @@ impl LocalGame {
 
     pub async fn process_events(
         client: Arc<LocalClient>,
+        chunk_manager: Arc<ChunkManager>,
     ) -> Result<(), Box<dyn Error + Send + Sync>> {
         let mut receiver = client.get_world_event_receiver().await;
         loop {
-            let event = receiver.recv().await?;
-            dbg!(&event);
+            let event = match receiver.recv().await {
+                Ok(event) => event,
+                // Chunks are meshed around the next position that comes in
+                Err(RecvError::Lagged(skipped)) => {
+                    log::warn!("Fell behind, skipped {} world events", skipped);
+                    continue;
+                }
+                Err(error) => return Err(error.into()),
+            };
 
             match event {
-                WorldEvent::EntityPositionChanged(id, position) => if id == client.player_id() {},
+                WorldEvent::EntityPositionChanged(id, position) => {
+                    if id == client.player_id() {
+                        chunk_manager.move_to(position.chunk_position).await;
+                    }
+                }
+                WorldEvent::BlockChanged(position, _) => chunk_manager.block_changed(&position),
             }
         }
     }
@@ -283,7 +290,7 @@ impl LocalGame {
         resources: &mut GameResources,
         encoder: &mut CommandEncoder,
     ) {
-        let mut chunk_meshes = self.chunk_meshes.blocking_lock();
+        let mut chunk_meshes = self.chunk_manager.meshes().blocking_lock();
 
         let mut render_pass = encoder.begin_render_pass(&RenderPassDescriptor {
             label: Some("Chunk render pass"),
@@ -309,7 +316,7 @@ impl LocalGame {
         render_pass.set_bind_group(0, resources.face_texture_map.bind_group(), &[]);
         render_pass.set_bind_group(1, resources.camera.bind_group(), &[]);
 
-        chunk_meshes.iter_mut().for_each(|mesh| {
+        chunk_meshes.values_mut().for_each(|mesh| {
             mesh.render(&render_context, &mut render_pass);
         });
     }
@@ -347,13 +354,15 @@ impl Game for LocalGame {
             {
                 let new_pitch = resources.camera.increase_pitch(delta_y);
                 let new_yaw = resources.camera.increase_yaw(delta_x);
+                self.chunk_manager.set_yaw(new_yaw);
                 let client = Arc::clone(&self.client);
                 tokio::spawn(async move { client.set_pitch_yaw(new_pitch, new_yaw).await });
             }
 
+            let [x, y, z] = pos.absolute::<CHUNK_SIZE>();
             resources
                 .camera
-                .set_position((pos.offset.x, pos.offset.y, pos.offset.z));
+                .set_position((x as f32, y as f32, z as f32));
 
             resources
                 .camera
@@ -369,6 +378,25 @@ impl Game for LocalGame {
 
     fn cleanup(&mut self) {}
 
+    fn quit(&mut self) {
+        self.chunk_manager.stop();
+        let world_handle = match self.world_handle.take() {
+            Some(world_handle) => world_handle,
+            None => return,
+        };
+        let client = Arc::clone(&self.client);
+        let result = async move {
+            if let Err(error) = client.leave_world().await {
+                log::error!("Failed to leave the world: {}", error);
+            }
+            world_handle.shutdown().await
+        }
+        .block_on();
+        if let Err(error) = result {
+            log::error!("Failed to shut down the world: {}", error);
+        }
+    }
+
     fn get_messages(&mut self) -> Vec<Message> {
         let mut list = self.messages.lock().unwrap();
         let messages = list.clone();
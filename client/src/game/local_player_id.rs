@@ -0,0 +1,24 @@
+use std::error::Error;
+use std::io::ErrorKind;
+use std::path::Path;
+use uuid::Uuid;
+
+/// The file in the save directory that holds the id of the local player
+const PLAYER_ID_FILE: &str = "local_player_id";
+
+/// The id the local player plays the world in the given directory as. The id is made up the first
+/// time the world is played, and stored next to the world so that the player is restored where
+/// they left off the next time
+pub fn local_player_id(directory: &Path) -> Result<Uuid, Box<dyn Error + Send + Sync>> {
+    let path = directory.join(PLAYER_ID_FILE);
+    match std::fs::read_to_string(&path) {
+        Ok(player_id) => Ok(Uuid::parse_str(player_id.trim())?),
+        Err(error) if error.kind() == ErrorKind::NotFound => {
+            let player_id = Uuid::new_v4();
+            std::fs::create_dir_all(directory)?;
+            std::fs::write(&path, player_id.to_string())?;
+            Ok(player_id)
+        }
+        Err(error) => Err(error.into()),
+    }
+}
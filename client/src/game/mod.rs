@@ -1,6 +1,7 @@
 mod game;
 mod game_manager;
 mod local_game;
+mod local_player_id;
 mod remote_game;
 mod resources;
 
@@ -9,12 +9,14 @@ struct VertexInput {
     [[location(0)]] position: vec3<f32>;
     [[location(1)]] tex_coords: vec2<f32>;
     [[location(2)]] tex_index: i32;
+    [[location(3)]] ambient_occlusion: f32;
 };
 
 struct VertexOutput {
     [[builtin(position)]] clip_position: vec4<f32>;
     [[location(0)]] tex_coords: vec2<f32>;
     [[location(1)]] tex_index: i32;
+    [[location(2)]] ambient_occlusion: f32;
 };
 
 [[stage(vertex)]]
@@ -24,6 +26,7 @@ fn vs_main(
     var out: VertexOutput;
     out.tex_coords = model.tex_coords;
     out.tex_index = model.tex_index;
+    out.ambient_occlusion = model.ambient_occlusion;
     out.clip_position = camera.view_proj * vec4<f32>(model.position, 1.0);
     return out;
 }
@@ -35,5 +38,11 @@ var s_diffuse: sampler;
 
 [[stage(fragment)]]
 fn fs_main(in: VertexOutput) -> [[location(0)]] vec4<f32> {
-    return textureSample(t_diffuse, s_diffuse, in.tex_coords, in.tex_index);
+    let color = textureSample(t_diffuse, s_diffuse, in.tex_coords, in.tex_index);
+    // Leaves the see through parts of plants and the like out, instead of drawing them black
+    if (color.a < 0.5) {
+        discard;
+    }
+    let light = mix(0.4, 1.0, in.ambient_occlusion);
+    return vec4<f32>(color.rgb * light, color.a);
 }
@@ -4,6 +4,8 @@ pub struct SmallTexturedArrayVertex {
     pub position: [f32; 3],
     pub tex_coords: [f32; 2],
     pub tex_index: i32,
+    /// How much light reaches the vertex, from 0 when fully occluded to 1 when not occluded at all
+    pub ambient_occlusion: f32,
 }
 
 impl SmallTexturedArrayVertex {
@@ -29,6 +31,13 @@ impl SmallTexturedArrayVertex {
                     shader_location: 2,
                     format: wgpu::VertexFormat::Sint32,
                 },
+                wgpu::VertexAttribute {
+                    offset: (mem::size_of::<[f32; 3]>()
+                        + mem::size_of::<[f32; 2]>()
+                        + mem::size_of::<i32>()) as wgpu::BufferAddress,
+                    shader_location: 3,
+                    format: wgpu::VertexFormat::Float32,
+                },
             ],
         }
     }
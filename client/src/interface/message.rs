@@ -4,5 +4,7 @@ pub enum Message {
     QuitApplication,
     CreateNewGame,
     GameLoadingMessage(String, Option<f32>),
+    /// Loading the game went wrong, with the reason to show to the player
+    GameLoadingFailed(String),
     EscapePressed,
 }
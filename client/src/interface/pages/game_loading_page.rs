@@ -1,6 +1,9 @@
+use crate::interface::components;
+use crate::interface::components::styles;
 use crate::interface::message::Message;
 use crate::interface::page::Page;
-use iced::{Column, Element, Length, ProgressBar, Space, Text};
+use crate::interface::pages::WORLD_SELECTION_PAGE_ROUTE;
+use iced::{button, Column, Element, Length, ProgressBar, Space, Text};
 use iced_native::Alignment;
 
 pub const GAME_LOADING_PAGE_ROUTE: &str = "GAME_LOADING";
@@ -8,6 +11,9 @@ pub const GAME_LOADING_PAGE_ROUTE: &str = "GAME_LOADING";
 pub struct GameLoadingPage {
     loading_message: String,
     progress: Option<f32>,
+    /// Whether loading went wrong, in which case the loading message is the reason
+    has_failed: bool,
+    back_button: button::State,
 }
 
 impl GameLoadingPage {
@@ -15,6 +21,8 @@ impl GameLoadingPage {
         Self {
             loading_message: "Loading...".to_string(),
             progress: None,
+            has_failed: false,
+            back_button: button::State::new(),
         }
     }
 }
@@ -39,6 +47,19 @@ impl Page for GameLoadingPage {
             column
         };
 
+        let column = if self.has_failed {
+            column.push(
+                components::button(&mut self.back_button, "BACK")
+                    .width(Length::Units(350))
+                    .style(styles::Button::Secondary)
+                    .on_press(Message::Navigate {
+                        page: WORLD_SELECTION_PAGE_ROUTE.to_string(),
+                    }),
+            )
+        } else {
+            column
+        };
+
         column.push(Space::new(Length::Shrink, Length::Fill)).into()
     }
 
@@ -47,6 +68,12 @@ impl Page for GameLoadingPage {
             Message::GameLoadingMessage(loading_text, progress) => {
                 self.loading_message = loading_text.to_string();
                 self.progress = progress.clone();
+                self.has_failed = false;
+            }
+            Message::GameLoadingFailed(reason) => {
+                self.loading_message = reason.to_string();
+                self.progress = None;
+                self.has_failed = true;
             }
             _ => {}
         }
@@ -1,12 +1,32 @@
 use crate::chunk::ChunkPosition;
 use block_chunk::BlockOffset;
+use voxelcraft_id::DimensionId;
 
+#[derive(Debug, Clone, PartialEq, Eq, Hash, bincode::Encode, bincode::Decode)]
 pub struct BlockPosition<const SIZE: usize> {
     pub chunk_position: ChunkPosition,
     pub offset: BlockOffset<SIZE>,
 }
 
 impl<const SIZE: usize> BlockPosition<SIZE> {
+    /// Finds the chunk and offset of the block at the given absolute coordinates
+    pub fn from_absolute(x: i64, y: i64, z: i64, dimension: DimensionId) -> Self {
+        let size = SIZE as i64;
+        Self {
+            chunk_position: ChunkPosition {
+                x: x.div_euclid(size) as i32,
+                y: y.div_euclid(size) as i32,
+                z: z.div_euclid(size) as i32,
+                dimension,
+            },
+            offset: BlockOffset {
+                x: x.rem_euclid(size) as usize,
+                y: y.rem_euclid(size) as usize,
+                z: z.rem_euclid(size) as usize,
+            },
+        }
+    }
+
     pub fn absolute_x(&self) -> i64 {
         (self.chunk_position.x as i64 * SIZE as i64) + self.offset.x as i64
     }
@@ -19,3 +39,22 @@ impl<const SIZE: usize> BlockPosition<SIZE> {
         (self.chunk_position.z as i64 * SIZE as i64) + self.offset.z as i64
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::block::BlockPosition;
+    use voxelcraft_id::DimensionId;
+
+    #[test]
+    fn from_absolute_should_round_towards_negative_infinity() {
+        let position = BlockPosition::<16>::from_absolute(-1, 16, -17, DimensionId::default());
+
+        assert_eq!(position.chunk_position.x, -1);
+        assert_eq!(position.offset.x, 15);
+        assert_eq!(position.chunk_position.y, 1);
+        assert_eq!(position.offset.y, 0);
+        assert_eq!(position.chunk_position.z, -2);
+        assert_eq!(position.offset.z, 15);
+        assert_eq!(position.absolute_z(), -17);
+    }
+}
@@ -0,0 +1,102 @@
+use block_chunk::mesh::ShapeBox;
+
+/// An axis aligned box, in absolute block coordinates
+#[derive(Debug, Clone, PartialEq)]
+pub struct BoundingBox {
+    pub min: [f64; 3],
+    pub max: [f64; 3],
+}
+
+impl BoundingBox {
+    /// Touching a block closer than this does not count as overlapping it, so rounding errors
+    /// don't get entities stuck on the blocks they are standing on. Entity positions are stored as
+    /// `f32`, so this has to be larger than the precision of those within a chunk
+    pub const EPSILON: f64 = 1e-5;
+
+    pub const fn new(min: [f64; 3], max: [f64; 3]) -> Self {
+        Self { min, max }
+    }
+
+    /// A box centered horizontally on the given point, reaching `below` down and `above` up from
+    /// it
+    pub fn around(point: [f64; 3], width: f64, below: f64, above: f64) -> Self {
+        let half_width = width / 2.0;
+        Self {
+            min: [
+                point[0] - half_width,
+                point[1] - below,
+                point[2] - half_width,
+            ],
+            max: [
+                point[0] + half_width,
+                point[1] + above,
+                point[2] + half_width,
+            ],
+        }
+    }
+
+    #[must_use]
+    pub fn translated(&self, offset: [f64; 3]) -> Self {
+        Self {
+            min: [0, 1, 2].map(|axis| self.min[axis] + offset[axis]),
+            max: [0, 1, 2].map(|axis| self.max[axis] + offset[axis]),
+        }
+    }
+
+    /// The box covering every position this box passes through when moving by the offset
+    #[must_use]
+    pub fn expanded_towards(&self, offset: [f64; 3]) -> Self {
+        Self {
+            min: [0, 1, 2].map(|axis| self.min[axis] + offset[axis].min(0.0)),
+            max: [0, 1, 2].map(|axis| self.max[axis] + offset[axis].max(0.0)),
+        }
+    }
+
+    /// The box that the part of the block at the given coordinates takes up
+    pub fn of_block_part(block: [i64; 3], part: &ShapeBox) -> Self {
+        Self {
+            min: [0, 1, 2].map(|axis| block[axis] as f64 + f64::from(part.min[axis])),
+            max: [0, 1, 2].map(|axis| block[axis] as f64 + f64::from(part.max[axis])),
+        }
+    }
+
+    /// Whether the boxes overlap along the axis, by more than [`Self::EPSILON`]
+    pub fn overlaps_along(&self, other: &Self, axis: usize) -> bool {
+        self.min[axis] + Self::EPSILON < other.max[axis]
+            && other.min[axis] + Self::EPSILON < self.max[axis]
+    }
+
+    /// The first and last block the box overlaps along the axis
+    pub fn block_range(&self, axis: usize) -> (i64, i64) {
+        (
+            (self.min[axis] + Self::EPSILON).floor() as i64,
+            (self.max[axis] - Self::EPSILON).ceil() as i64 - 1,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::collision::BoundingBox;
+
+    #[test]
+    fn block_range_should_not_include_blocks_that_are_only_touched() {
+        let bounding_box = BoundingBox::new([0.0, -1.0, 0.5], [1.0, 1.0, 1.5]);
+
+        assert_eq!(bounding_box.block_range(0), (0, 0));
+        assert_eq!(bounding_box.block_range(1), (-1, 0));
+        assert_eq!(bounding_box.block_range(2), (0, 1));
+    }
+
+    #[test]
+    fn expanded_towards_should_cover_both_ends_of_the_movement() {
+        let bounding_box = BoundingBox::new([0.0; 3], [1.0; 3]);
+
+        let expanded = bounding_box.expanded_towards([2.0, -3.0, 0.0]);
+
+        assert_eq!(
+            expanded,
+            BoundingBox::new([0.0, -3.0, 0.0], [3.0, 1.0, 1.0])
+        );
+    }
+}
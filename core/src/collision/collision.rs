@@ -0,0 +1,24 @@
+/// The outcome of moving a box through the world
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Collision {
+    /// How far the box could move without entering a solid block
+    pub movement: [f64; 3],
+
+    /// Whether the box landed on a block while moving down
+    pub is_grounded: bool,
+
+    /// Whether the box bumped in to a block while moving up
+    pub hits_ceiling: bool,
+
+    /// Whether the box was stopped by a block along the x axis
+    pub hits_wall_x: bool,
+
+    /// Whether the box was stopped by a block along the z axis
+    pub hits_wall_z: bool,
+}
+
+impl Collision {
+    pub const fn hits_wall(&self) -> bool {
+        self.hits_wall_x || self.hits_wall_z
+    }
+}
@@ -0,0 +1,11 @@
+mod bounding_box;
+mod collision;
+mod solid_blocks;
+mod stop_at_ledges;
+mod sweep;
+
+pub use self::bounding_box::BoundingBox;
+pub use self::collision::Collision;
+pub use self::solid_blocks::SolidBlocks;
+pub use self::stop_at_ledges::stop_at_ledges;
+pub use self::sweep::sweep;
@@ -0,0 +1,152 @@
+use crate::block::BlockPosition;
+use crate::chunk::ChunkPosition;
+use crate::collision::BoundingBox;
+use block_chunk::mesh::ShapeBox;
+use block_chunk::BlockOffset;
+use voxelcraft_id::DimensionId;
+
+/// The parts of the blocks within a region of the world that entities collide with.
+///
+/// The region is filled in one chunk at a time, so the chunks don't have to be held at the same
+/// time. Blocks outside of the region, or in chunks that were never filled in, have no parts to
+/// collide with
+#[derive(Debug, Clone)]
+pub struct SolidBlocks {
+    min: [i64; 3],
+    size: [usize; 3],
+    blocks: Vec<&'static [ShapeBox]>,
+}
+
+impl SolidBlocks {
+    /// Creates an empty region covering every block the box overlaps
+    pub fn new(region: &BoundingBox) -> Self {
+        let ranges = [0, 1, 2].map(|axis| region.block_range(axis));
+        let min = ranges.map(|(first, _)| first);
+        let size = ranges.map(|(first, last)| (last - first + 1).max(0) as usize);
+
+        Self {
+            min,
+            size,
+            blocks: vec![&[]; size[0] * size[1] * size[2]],
+        }
+    }
+
+    /// The chunks that overlaps the region
+    pub fn chunk_positions<const SIZE: usize>(&self, dimension: DimensionId) -> Vec<ChunkPosition> {
+        if self.blocks.is_empty() {
+            return vec![];
+        }
+
+        let first = self.corner::<SIZE>(self.min, dimension);
+        let last = self.corner::<SIZE>(
+            [0, 1, 2].map(|axis| self.min[axis] + self.size[axis] as i64 - 1),
+            dimension,
+        );
+
+        let mut positions = vec![];
+        for x in first.x..=last.x {
+            for y in first.y..=last.y {
+                for z in first.z..=last.z {
+                    positions.push(ChunkPosition { x, y, z, dimension });
+                }
+            }
+        }
+        positions
+    }
+
+    /// Fills in the part of the region that lies within the chunk
+    pub fn insert_chunk<const SIZE: usize, F: FnMut(&BlockOffset<SIZE>) -> &'static [ShapeBox]>(
+        &mut self,
+        chunk_position: &ChunkPosition,
+        mut collision_boxes: F,
+    ) {
+        let base = chunk_position.base_block_position::<SIZE>();
+        let base = [base.absolute_x(), base.absolute_y(), base.absolute_z()];
+
+        let ranges = [0, 1, 2].map(|axis| {
+            let start = (self.min[axis] - base[axis]).max(0);
+            let end = (self.min[axis] + self.size[axis] as i64 - base[axis]).min(SIZE as i64);
+            start..end.max(start)
+        });
+
+        for x in ranges[0].clone() {
+            for y in ranges[1].clone() {
+                for z in ranges[2].clone() {
+                    let offset = BlockOffset {
+                        x: x as usize,
+                        y: y as usize,
+                        z: z as usize,
+                    };
+                    let boxes = collision_boxes(&offset);
+                    if let Some(index) = self.index(base[0] + x, base[1] + y, base[2] + z) {
+                        self.blocks[index] = boxes;
+                    }
+                }
+            }
+        }
+    }
+
+    /// The parts of the block at the absolute coordinates that entities collide with
+    pub fn boxes(&self, x: i64, y: i64, z: i64) -> &'static [ShapeBox] {
+        self.index(x, y, z).map_or(&[], |index| self.blocks[index])
+    }
+
+    fn index(&self, x: i64, y: i64, z: i64) -> Option<usize> {
+        let x = usize::try_from(x - self.min[0])
+            .ok()
+            .filter(|x| *x < self.size[0])?;
+        let y = usize::try_from(y - self.min[1])
+            .ok()
+            .filter(|y| *y < self.size[1])?;
+        let z = usize::try_from(z - self.min[2])
+            .ok()
+            .filter(|z| *z < self.size[2])?;
+        Some((x * self.size[1] + y) * self.size[2] + z)
+    }
+
+    fn corner<const SIZE: usize>(&self, block: [i64; 3], dimension: DimensionId) -> ChunkPosition {
+        BlockPosition::<SIZE>::from_absolute(block[0], block[1], block[2], dimension).chunk_position
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::chunk::ChunkPosition;
+    use crate::collision::{BoundingBox, SolidBlocks};
+    use block_chunk::mesh::ShapeBox;
+    use voxelcraft_id::DimensionId;
+
+    #[test]
+    fn it_should_span_the_chunks_on_both_sides_of_a_seam() {
+        let solid_blocks = SolidBlocks::new(&BoundingBox::new([-0.5, 0.0, 0.0], [0.5, 1.0, 1.0]));
+
+        let positions = solid_blocks.chunk_positions::<16>(DimensionId::default());
+
+        assert_eq!(positions.len(), 2);
+        assert_eq!(positions[0].x, -1);
+        assert_eq!(positions[1].x, 0);
+    }
+
+    #[test]
+    fn it_should_only_fill_in_the_part_of_the_chunk_inside_the_region() {
+        let mut solid_blocks =
+            SolidBlocks::new(&BoundingBox::new([-2.0, 0.0, 0.0], [2.0, 1.0, 1.0]));
+        let chunk_position = ChunkPosition {
+            x: -1,
+            ..ChunkPosition::default()
+        };
+
+        solid_blocks.insert_chunk::<16, _>(&chunk_position, |offset| {
+            if offset.x == 15 {
+                &[ShapeBox::FULL]
+            } else {
+                &[]
+            }
+        });
+
+        assert_eq!(solid_blocks.boxes(-1, 0, 0), &[ShapeBox::FULL]);
+        assert!(solid_blocks.boxes(-2, 0, 0).is_empty());
+        assert!(solid_blocks.boxes(0, 0, 0).is_empty());
+        assert!(solid_blocks.boxes(-17, 0, 0).is_empty());
+    }
+}
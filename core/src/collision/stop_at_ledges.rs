@@ -0,0 +1,124 @@
+use crate::collision::BoundingBox;
+use block_chunk::mesh::ShapeBox;
+
+/// How much the movement is shortened by at a time while looking for the edge of the ledge
+const STEP: f64 = 0.05;
+
+/// Shortens the horizontal movement of a box that is standing on the ground, so that it does not
+/// move off a ledge. A ledge is anywhere with more than `max_drop` blocks of air below the box.
+/// The callback gives the parts of the block at the given coordinates that count as ground.
+///
+/// The vertical movement is left as it is
+pub fn stop_at_ledges<'a, F: Fn(i64, i64, i64) -> &'a [ShapeBox]>(
+    bounding_box: &BoundingBox,
+    movement: [f64; 3],
+    max_drop: f64,
+    collision_boxes: F,
+) -> [f64; 3] {
+    let has_ground = |x: f64, z: f64| {
+        let below = BoundingBox::new(
+            [
+                bounding_box.min[0] + x,
+                bounding_box.min[1] - max_drop,
+                bounding_box.min[2] + z,
+            ],
+            [
+                bounding_box.max[0] + x,
+                bounding_box.min[1],
+                bounding_box.max[2] + z,
+            ],
+        );
+        overlaps_block_part(&below, &collision_boxes)
+    };
+
+    let mut x = movement[0];
+    let mut z = movement[2];
+    while x != 0.0 && !has_ground(x, 0.0) {
+        x = shorten(x);
+    }
+    while z != 0.0 && !has_ground(0.0, z) {
+        z = shorten(z);
+    }
+    while x != 0.0 && z != 0.0 && !has_ground(x, z) {
+        x = shorten(x);
+        z = shorten(z);
+    }
+
+    [x, movement[1], z]
+}
+
+fn shorten(value: f64) -> f64 {
+    if value.abs() <= STEP {
+        0.0
+    } else {
+        value - STEP * value.signum()
+    }
+}
+
+fn overlaps_block_part<'a, F: Fn(i64, i64, i64) -> &'a [ShapeBox]>(
+    bounding_box: &BoundingBox,
+    collision_boxes: &F,
+) -> bool {
+    let (x_start, x_end) = bounding_box.block_range(0);
+    let (y_start, y_end) = bounding_box.block_range(1);
+    let (z_start, z_end) = bounding_box.block_range(2);
+
+    let overlaps = |x: i64, y: i64, z: i64| {
+        collision_boxes(x, y, z).iter().any(|part| {
+            let part = BoundingBox::of_block_part([x, y, z], part);
+            (0..3).all(|axis| part.overlaps_along(bounding_box, axis))
+        })
+    };
+    (x_start..=x_end)
+        .any(|x| (y_start..=y_end).any(|y| (z_start..=z_end).any(|z| overlaps(x, y, z))))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::collision::{stop_at_ledges, BoundingBox};
+    use block_chunk::mesh::ShapeBox;
+
+    fn player_at(x: f64, z: f64) -> BoundingBox {
+        BoundingBox::new([x - 0.3, 0.0, z - 0.3], [x + 0.3, 1.8, z + 0.3])
+    }
+
+    /// A floor that ends at x = 2
+    fn ledge(x: i64, y: i64, _z: i64) -> &'static [ShapeBox] {
+        full_block(y == -1 && x < 2)
+    }
+
+    fn full_block(is_solid: bool) -> &'static [ShapeBox] {
+        if is_solid {
+            &[ShapeBox::FULL]
+        } else {
+            &[]
+        }
+    }
+
+    #[test]
+    fn it_should_not_shorten_movement_on_solid_ground() {
+        let movement = stop_at_ledges(&player_at(0.5, 0.5), [0.5, -0.1, 0.5], 0.6, ledge);
+
+        assert_eq!(movement, [0.5, -0.1, 0.5]);
+    }
+
+    #[test]
+    fn it_should_stop_at_the_edge_of_a_ledge() {
+        let movement = stop_at_ledges(&player_at(1.5, 0.5), [1.0, 0.0, 0.5], 0.6, ledge);
+
+        // The box can hang over the ledge, as long as part of it is above the ground
+        assert!(movement[0] > 0.7 && movement[0] <= 0.8);
+        assert_eq!(movement[2], 0.5);
+    }
+
+    #[test]
+    fn it_should_allow_small_drops() {
+        let step = |x: i64, y: i64, _z: i64| full_block(y == -1 || (x < 2 && y == 0));
+        let mut player = player_at(1.5, 0.5);
+        player.min[1] = 0.5;
+
+        let movement = stop_at_ledges(&player, [1.0, 0.0, 0.0], 0.6, step);
+
+        assert_eq!(movement, [1.0, 0.0, 0.0]);
+    }
+}
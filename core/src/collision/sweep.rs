@@ -0,0 +1,300 @@
+use crate::collision::{BoundingBox, Collision};
+use block_chunk::mesh::ShapeBox;
+
+/// The order the axes are resolved in. Moving vertically first lets entities slide over the ground
+/// without catching on the edges of the blocks they are standing on
+const AXES: [usize; 3] = [1, 0, 2];
+
+/// Moves the box as far as it can go without entering a part of a block it collides with. The
+/// callback gives those parts for the block at the given coordinates.
+///
+/// The movement is resolved one axis at a time, so a box moving diagonally in to a wall keeps
+/// sliding along it
+pub fn sweep<'a, F: Fn(i64, i64, i64) -> &'a [ShapeBox]>(
+    bounding_box: &BoundingBox,
+    movement: [f64; 3],
+    collision_boxes: F,
+) -> Collision {
+    let mut bounding_box = bounding_box.clone();
+    let mut collision = Collision::default();
+
+    for axis in AXES {
+        let wanted = movement[axis];
+        if wanted == 0.0 {
+            continue;
+        }
+
+        let allowed = allowed_movement(&bounding_box, axis, wanted, &collision_boxes);
+        if allowed != wanted {
+            match (axis, wanted > 0.0) {
+                (1, false) => collision.is_grounded = true,
+                (1, true) => collision.hits_ceiling = true,
+                (0, _) => collision.hits_wall_x = true,
+                _ => collision.hits_wall_z = true,
+            }
+        }
+
+        let mut offset = [0.0; 3];
+        offset[axis] = allowed;
+        bounding_box = bounding_box.translated(offset);
+        collision.movement[axis] = allowed;
+    }
+
+    collision
+}
+
+/// How far the box can move along the axis before hitting the first part of a block in its way
+fn allowed_movement<'a, F: Fn(i64, i64, i64) -> &'a [ShapeBox]>(
+    bounding_box: &BoundingBox,
+    axis: usize,
+    wanted: f64,
+    collision_boxes: &F,
+) -> f64 {
+    let (first_axis, second_axis) = match axis {
+        0 => (1, 2),
+        1 => (0, 2),
+        _ => (0, 1),
+    };
+    let (first_start, first_end) = bounding_box.block_range(first_axis);
+    let (second_start, second_end) = bounding_box.block_range(second_axis);
+
+    // The parts of a block can lie anywhere within it, so the layer the leading side of the box is
+    // in counts as well
+    let (leading, first_layer, last_layer) = if wanted > 0.0 {
+        let leading = bounding_box.max[axis];
+        (
+            leading,
+            leading.floor() as i64 - 1,
+            (leading + wanted).ceil() as i64 - 1,
+        )
+    } else {
+        let leading = bounding_box.min[axis];
+        (
+            leading,
+            (leading + wanted).floor() as i64 - 1,
+            leading.floor() as i64 + 1,
+        )
+    };
+
+    let mut allowed = wanted;
+    for layer in first_layer..=last_layer {
+        for first in first_start..=first_end {
+            for second in second_start..=second_end {
+                let mut block = [0; 3];
+                block[axis] = layer;
+                block[first_axis] = first;
+                block[second_axis] = second;
+
+                for part in collision_boxes(block[0], block[1], block[2]) {
+                    let part = BoundingBox::of_block_part(block, part);
+                    if !part.overlaps_along(bounding_box, first_axis)
+                        || !part.overlaps_along(bounding_box, second_axis)
+                    {
+                        continue;
+                    }
+
+                    if wanted > 0.0 && part.min[axis] >= leading - BoundingBox::EPSILON {
+                        allowed = allowed.min(part.min[axis] - leading);
+                    } else if wanted < 0.0 && part.max[axis] <= leading + BoundingBox::EPSILON {
+                        allowed = allowed.max(part.max[axis] - leading);
+                    }
+                }
+            }
+        }
+    }
+
+    if wanted > 0.0 {
+        allowed.clamp(0.0, wanted)
+    } else {
+        allowed.clamp(wanted, 0.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::chunk::ChunkPosition;
+    use crate::collision::{sweep, BoundingBox, Collision, SolidBlocks};
+    use block_chunk::mesh::ShapeBox;
+
+    /// A player sized box standing on the block below the given coordinates
+    fn player_at(x: f64, y: f64, z: f64) -> BoundingBox {
+        BoundingBox::new([x - 0.3, y, z - 0.3], [x + 0.3, y + 1.8, z + 0.3])
+    }
+
+    fn floor(_x: i64, y: i64, _z: i64) -> bool {
+        y < 0
+    }
+
+    /// The collision boxes for a world of full blocks, where the given blocks are solid
+    fn full_blocks(
+        is_solid: impl Fn(i64, i64, i64) -> bool,
+    ) -> impl Fn(i64, i64, i64) -> &'static [ShapeBox] {
+        move |x, y, z| {
+            if is_solid(x, y, z) {
+                &[ShapeBox::FULL]
+            } else {
+                &[]
+            }
+        }
+    }
+
+    #[test]
+    fn it_should_land_on_the_ground() {
+        let collision = sweep(
+            &player_at(0.5, 0.5, 0.5),
+            [0.0, -2.0, 0.0],
+            full_blocks(floor),
+        );
+
+        assert!(collision.is_grounded);
+        assert!((collision.movement[1] + 0.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn it_should_not_be_grounded_while_walking_on_the_ground() {
+        let collision = sweep(
+            &player_at(0.5, 0.0, 0.5),
+            [1.0, 0.0, 1.0],
+            full_blocks(floor),
+        );
+
+        assert_eq!(
+            collision,
+            Collision {
+                movement: [1.0, 0.0, 1.0],
+                ..Collision::default()
+            }
+        );
+    }
+
+    #[test]
+    fn it_should_hit_the_ceiling() {
+        let collision = sweep(
+            &player_at(0.5, 0.0, 0.5),
+            [0.0, 1.0, 0.0],
+            full_blocks(|_, y, _| y == 2),
+        );
+
+        assert!(collision.hits_ceiling);
+        assert!((collision.movement[1] - 0.2).abs() < 1e-9);
+    }
+
+    #[test]
+    fn it_should_slide_along_walls() {
+        let wall = |x: i64, y: i64, _z: i64| y < 0 || x >= 2;
+
+        let collision = sweep(
+            &player_at(1.5, 0.0, 0.5),
+            [1.0, 0.0, 1.0],
+            full_blocks(wall),
+        );
+
+        assert!(collision.hits_wall_x);
+        assert!(!collision.hits_wall_z);
+        assert!(!collision.is_grounded);
+        assert!((collision.movement[0] - 0.2).abs() < 1e-9);
+        assert_eq!(collision.movement[2], 1.0);
+    }
+
+    #[test]
+    fn it_should_not_pass_through_corners() {
+        // A single pillar, diagonal to the player
+        let pillar = |x: i64, _y: i64, z: i64| x == 1 && z == 1;
+
+        let collision = sweep(
+            &player_at(0.5, 0.0, 0.5),
+            [0.5, 0.0, 0.5],
+            full_blocks(pillar),
+        );
+
+        // Moving along x first is fine, which then blocks the movement along z
+        assert!(!collision.hits_wall_x);
+        assert!(collision.hits_wall_z);
+        assert!((collision.movement[0] - 0.5).abs() < 1e-9);
+        assert!((collision.movement[2] - 0.2).abs() < 1e-9);
+    }
+
+    #[test]
+    fn it_should_not_move_in_to_a_wall_it_is_touching() {
+        let wall = |x: i64, _y: i64, _z: i64| x >= 1;
+
+        let collision = sweep(
+            &player_at(0.7, 0.0, 0.5),
+            [0.5, 0.0, 0.0],
+            full_blocks(wall),
+        );
+
+        assert!(collision.hits_wall_x);
+        assert_eq!(collision.movement[0], 0.0);
+    }
+
+    #[test]
+    fn it_should_collide_with_blocks_across_chunk_seams() {
+        let player = player_at(0.5, 0.0, 0.5);
+        let movement = [-2.0, -1.0, 0.0];
+        let mut solid_blocks = SolidBlocks::new(&player.expanded_towards(movement));
+
+        // The floor below y = 0, and a wall in the chunk at negative x
+        for chunk_position in solid_blocks.chunk_positions::<16>(Default::default()) {
+            solid_blocks.insert_chunk::<16, _>(&chunk_position, |offset| {
+                let is_negative_x = chunk_position.x < 0;
+                let is_floor = chunk_position.y < 0 && offset.y == 15;
+                let is_wall = is_negative_x && offset.x == 15 && chunk_position.y == 0;
+                if is_floor || is_wall {
+                    &[ShapeBox::FULL]
+                } else {
+                    &[]
+                }
+            });
+        }
+        assert!(solid_blocks
+            .chunk_positions::<16>(Default::default())
+            .contains(&ChunkPosition {
+                x: -1,
+                y: -1,
+                z: 0,
+                dimension: Default::default()
+            }));
+
+        let collision = sweep(&player, movement, |x, y, z| solid_blocks.boxes(x, y, z));
+
+        assert!(collision.is_grounded);
+        assert!(collision.hits_wall_x);
+        assert_eq!(collision.movement[1], 0.0);
+        assert!((collision.movement[0] + 0.2).abs() < 1e-9);
+    }
+
+    #[test]
+    fn it_should_land_on_top_of_a_slab() {
+        let slab = [ShapeBox::new([0.0; 3], [1.0, 0.5, 1.0])];
+        let collision_boxes = |_x: i64, y: i64, _z: i64| -> &[ShapeBox] {
+            if y == 0 {
+                &slab
+            } else {
+                &[]
+            }
+        };
+
+        let collision = sweep(&player_at(0.5, 1.0, 0.5), [0.0, -1.0, 0.0], collision_boxes);
+
+        assert!(collision.is_grounded);
+        assert!((collision.movement[1] + 0.5).abs() < 1e-9);
+
+        // Nothing is in the way above the slab
+        let collision = sweep(&player_at(0.5, 0.5, 0.5), [1.0, 0.0, 0.0], collision_boxes);
+        assert_eq!(collision.movement, [1.0, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn it_should_move_through_blocks_without_collision_boxes() {
+        let collision = sweep(&player_at(0.5, 0.0, 0.5), [2.0, 0.0, 0.0], |_, _, _| &[]);
+
+        assert_eq!(
+            collision,
+            Collision {
+                movement: [2.0, 0.0, 0.0],
+                ..Collision::default()
+            }
+        );
+    }
+}
@@ -1,14 +1,208 @@
+use crate::block::BlockPosition;
 use crate::chunk::ChunkPosition;
 use crate::entity::EntityOffset;
+use voxelcraft_id::DimensionId;
 
-#[derive(Debug, Clone, PartialEq)]
+/// A position within the world, as the chunk it is in and the offset within that chunk.
+///
+/// The methods that move the position carry any part of the offset that ends up outside of
+/// `0.0..SIZE` over to the chunk position, so the offset always stays small and precise
+#[derive(Debug, Clone, PartialEq, bincode::Encode, bincode::Decode)]
 pub struct EntityPosition {
     pub chunk_position: ChunkPosition,
     pub offset: EntityOffset,
 }
 
 impl EntityPosition {
+    /// The position at the given absolute coordinates
+    pub fn from_absolute<const SIZE: usize>(
+        x: f64,
+        y: f64,
+        z: f64,
+        dimension: DimensionId,
+    ) -> Self {
+        let origin = Self {
+            chunk_position: ChunkPosition {
+                x: 0,
+                y: 0,
+                z: 0,
+                dimension,
+            },
+            offset: EntityOffset {
+                x: 0.0,
+                y: 0.0,
+                z: 0.0,
+            },
+        };
+        origin.translated::<SIZE>([x, y, z])
+    }
+
     pub fn surrounding_chunks(&self, range: usize) -> Vec<ChunkPosition> {
         self.chunk_position.surrounding_chunks(range)
     }
+
+    /// The absolute coordinates of the position
+    pub fn absolute<const SIZE: usize>(&self) -> [f64; 3] {
+        let size = SIZE as f64;
+        [
+            f64::from(self.chunk_position.x) * size + f64::from(self.offset.x),
+            f64::from(self.chunk_position.y) * size + f64::from(self.offset.y),
+            f64::from(self.chunk_position.z) * size + f64::from(self.offset.z),
+        ]
+    }
+
+    /// The block the position is within
+    pub fn block_position<const SIZE: usize>(&self) -> BlockPosition<SIZE> {
+        let [x, y, z] = self.absolute::<SIZE>();
+        BlockPosition::from_absolute(
+            x.floor() as i64,
+            y.floor() as i64,
+            z.floor() as i64,
+            self.chunk_position.dimension,
+        )
+    }
+
+    /// The position moved by the given vector
+    #[must_use]
+    pub fn translated<const SIZE: usize>(&self, vector: [f64; 3]) -> Self {
+        let (x, offset_x) =
+            carry::<SIZE>(self.chunk_position.x, f64::from(self.offset.x) + vector[0]);
+        let (y, offset_y) =
+            carry::<SIZE>(self.chunk_position.y, f64::from(self.offset.y) + vector[1]);
+        let (z, offset_z) =
+            carry::<SIZE>(self.chunk_position.z, f64::from(self.offset.z) + vector[2]);
+
+        Self {
+            chunk_position: ChunkPosition {
+                x,
+                y,
+                z,
+                dimension: self.chunk_position.dimension,
+            },
+            offset: EntityOffset {
+                x: offset_x,
+                y: offset_y,
+                z: offset_z,
+            },
+        }
+    }
+
+    /// The vector going from this position to the other one. This is precise even far away from
+    /// the origin, as the chunk and offset parts are subtracted separately
+    pub fn delta_to<const SIZE: usize>(&self, other: &Self) -> [f64; 3] {
+        let size = SIZE as f64;
+        [
+            f64::from(other.chunk_position.x - self.chunk_position.x) * size
+                + f64::from(other.offset.x - self.offset.x),
+            f64::from(other.chunk_position.y - self.chunk_position.y) * size
+                + f64::from(other.offset.y - self.offset.y),
+            f64::from(other.chunk_position.z - self.chunk_position.z) * size
+                + f64::from(other.offset.z - self.offset.z),
+        ]
+    }
+
+    pub fn distance_to<const SIZE: usize>(&self, other: &Self) -> f64 {
+        self.delta_to::<SIZE>(other)
+            .iter()
+            .map(|value| value * value)
+            .sum::<f64>()
+            .sqrt()
+    }
+}
+
+impl<const SIZE: usize> From<&BlockPosition<SIZE>> for EntityPosition {
+    /// The lowest corner of the block
+    fn from(position: &BlockPosition<SIZE>) -> Self {
+        Self {
+            chunk_position: position.chunk_position,
+            offset: EntityOffset {
+                x: position.offset.x as f32,
+                y: position.offset.y as f32,
+                z: position.offset.z as f32,
+            },
+        }
+    }
+}
+
+/// Moves whole chunks out of the offset and in to the chunk coordinate
+fn carry<const SIZE: usize>(chunk: i32, offset: f64) -> (i32, f32) {
+    let size = SIZE as f64;
+    let chunk = chunk + offset.div_euclid(size) as i32;
+    let offset = offset.rem_euclid(size) as f32;
+
+    // An offset just below the size can be rounded up to it, either by `rem_euclid` or when
+    // converting it to `f32`
+    if offset >= SIZE as f32 {
+        (chunk + 1, 0.0)
+    } else {
+        (chunk, offset)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::block::BlockPosition;
+    use crate::chunk::ChunkPosition;
+    use crate::entity::{EntityOffset, EntityPosition};
+    use voxelcraft_id::DimensionId;
+
+    fn position(chunk: [i32; 3], offset: [f32; 3]) -> EntityPosition {
+        EntityPosition {
+            chunk_position: ChunkPosition {
+                x: chunk[0],
+                y: chunk[1],
+                z: chunk[2],
+                dimension: DimensionId::default(),
+            },
+            offset: EntityOffset {
+                x: offset[0],
+                y: offset[1],
+                z: offset[2],
+            },
+        }
+    }
+
+    #[test]
+    fn translated_should_carry_the_offset_over_to_the_chunk() {
+        let moved = position([0, 0, 0], [15.5, 0.5, 0.5]).translated::<16>([1.0, -1.0, 32.0]);
+
+        assert_eq!(moved, position([1, -1, 2], [0.5, 15.5, 0.5]));
+    }
+
+    #[test]
+    fn translated_should_keep_the_offset_below_the_size() {
+        let moved = position([0, 0, 0], [0.0, 0.0, 0.0]).translated::<16>([-1e-10, 0.0, 0.0]);
+
+        assert!(moved.offset.x < 16.0);
+        assert_eq!(moved.chunk_position.x, 0);
+    }
+
+    #[test]
+    fn it_should_convert_to_and_from_absolute_coordinates() {
+        let converted =
+            EntityPosition::from_absolute::<16>(-0.5, 20.0, -33.0, DimensionId::default());
+
+        assert_eq!(converted, position([-1, 1, -3], [15.5, 4.0, 15.0]));
+        assert_eq!(converted.absolute::<16>(), [-0.5, 20.0, -33.0]);
+    }
+
+    #[test]
+    fn it_should_stay_precise_far_away_from_the_origin() {
+        let start = position([1_000_000, 0, 0], [0.25, 0.0, 0.0]);
+
+        let moved = start.translated::<16>([0.001, 0.0, 0.0]);
+
+        assert!((start.delta_to::<16>(&moved)[0] - 0.001).abs() < 1e-6);
+        assert!((start.distance_to::<16>(&moved) - 0.001).abs() < 1e-6);
+    }
+
+    #[test]
+    fn it_should_convert_to_and_from_block_positions() {
+        let block = BlockPosition::<16>::from_absolute(-1, 5, 17, DimensionId::default());
+
+        let position = EntityPosition::from(&block).translated::<16>([0.5, 0.5, 0.5]);
+
+        assert_eq!(position.block_position::<16>(), block);
+        assert_eq!(position.absolute::<16>(), [-0.5, 5.5, 17.5]);
+    }
 }
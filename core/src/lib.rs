@@ -1,3 +1,5 @@
 pub mod block;
 pub mod chunk;
+pub mod collision;
 pub mod entity;
+pub mod raycast;
@@ -0,0 +1,5 @@
+mod ray_hit;
+mod raycast;
+
+pub use self::ray_hit::RayHit;
+pub use self::raycast::{raycast, Raycast};
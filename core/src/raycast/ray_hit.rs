@@ -0,0 +1,16 @@
+use crate::block::BlockPosition;
+use block_chunk::mesh::FaceDirection;
+
+/// A block hit by a ray
+#[derive(Debug, Clone, PartialEq)]
+pub struct RayHit<const SIZE: usize> {
+    /// The block that was hit
+    pub position: BlockPosition<SIZE>,
+
+    /// The face of the block the ray entered through, or `None` if the ray started inside of the
+    /// block
+    pub face: Option<FaceDirection>,
+
+    /// How far along the ray the block was hit
+    pub distance: f32,
+}
@@ -0,0 +1,262 @@
+use crate::block::BlockPosition;
+use crate::chunk::ChunkPosition;
+use crate::entity::EntityPosition;
+use crate::raycast::RayHit;
+use block_chunk::mesh::FaceDirection;
+use block_chunk::BlockOffset;
+use voxelcraft_id::DimensionId;
+
+/// Walks a ray through the blocks it passes, one block at a time, using the voxel traversal
+/// algorithm by Amanatides and Woo.
+///
+/// The walk is split up per chunk, so the caller only needs a single chunk at hand at a time. Call
+/// [`Raycast::walk_chunk`] with the chunk given by [`Raycast::chunk_position`] until a block is
+/// hit or there are no more chunks to walk
+#[derive(Debug, Clone)]
+pub struct Raycast<const SIZE: usize> {
+    dimension: DimensionId,
+    /// The absolute coordinates of the block the ray is in
+    block: [i64; 3],
+    step: [i64; 3],
+    /// How far along the ray the next block boundary is crossed, for each axis
+    next_boundary: [f64; 3],
+    /// How far along the ray it is from one block boundary to the next, for each axis
+    boundary_distance: [f64; 3],
+    distance: f64,
+    max_distance: f64,
+    entered_through: Option<FaceDirection>,
+    is_done: bool,
+}
+
+impl<const SIZE: usize> Raycast<SIZE> {
+    /// Starts a ray at the given position. The direction does not have to be normalized, but a ray
+    /// without a direction never hits anything
+    pub fn new(origin: &EntityPosition, direction: [f32; 3], max_distance: f32) -> Self {
+        let dimension = origin.chunk_position.dimension;
+        let start = origin.absolute::<SIZE>();
+        let direction = direction.map(f64::from);
+        let length = direction
+            .iter()
+            .map(|value| value * value)
+            .sum::<f64>()
+            .sqrt();
+
+        let mut block = [0; 3];
+        let mut step = [0; 3];
+        let mut next_boundary = [f64::INFINITY; 3];
+        let mut boundary_distance = [f64::INFINITY; 3];
+        for axis in 0..3 {
+            block[axis] = start[axis].floor() as i64;
+            if length == 0.0 || direction[axis] == 0.0 {
+                continue;
+            }
+
+            let direction = direction[axis] / length;
+            boundary_distance[axis] = 1.0 / direction.abs();
+            if direction > 0.0 {
+                step[axis] = 1;
+                next_boundary[axis] = (block[axis] as f64 + 1.0 - start[axis]) / direction;
+            } else {
+                step[axis] = -1;
+                next_boundary[axis] = (block[axis] as f64 - start[axis]) / direction;
+            }
+        }
+
+        Self {
+            dimension,
+            block,
+            step,
+            next_boundary,
+            boundary_distance,
+            distance: 0.0,
+            max_distance: f64::from(max_distance),
+            entered_through: None,
+            is_done: !length.is_normal(),
+        }
+    }
+
+    /// The chunk that should be walked next, or `None` if the ray is done
+    pub fn chunk_position(&self) -> Option<ChunkPosition> {
+        if self.is_done {
+            None
+        } else {
+            Some(self.block_position().chunk_position)
+        }
+    }
+
+    /// Walks the ray through the chunk given by [`Raycast::chunk_position`], until it either hits a
+    /// block or leaves the chunk
+    pub fn walk_chunk<F: FnMut(&BlockOffset<SIZE>) -> bool>(
+        &mut self,
+        mut is_solid: F,
+    ) -> Option<RayHit<SIZE>> {
+        let chunk_position = self.chunk_position()?;
+
+        loop {
+            let position = self.block_position();
+            if position.chunk_position != chunk_position {
+                return None;
+            }
+
+            if is_solid(&position.offset) {
+                self.is_done = true;
+                return Some(RayHit {
+                    position,
+                    face: self.entered_through,
+                    distance: self.distance as f32,
+                });
+            }
+
+            if !self.step_to_next_block() {
+                self.is_done = true;
+                return None;
+            }
+        }
+    }
+
+    /// Moves on to the next block along the ray, returning false if it is too far away
+    fn step_to_next_block(&mut self) -> bool {
+        let mut axis = 0;
+        for other_axis in 1..3 {
+            if self.next_boundary[other_axis] < self.next_boundary[axis] {
+                axis = other_axis;
+            }
+        }
+
+        if self.next_boundary[axis] > self.max_distance {
+            return false;
+        }
+
+        self.distance = self.next_boundary[axis];
+        self.next_boundary[axis] += self.boundary_distance[axis];
+        self.block[axis] += self.step[axis];
+
+        // Moving along an axis means entering the next block through the opposite face
+        self.entered_through = Some(match (axis, self.step[axis] > 0) {
+            (0, true) => FaceDirection::West,
+            (0, false) => FaceDirection::East,
+            (1, true) => FaceDirection::Down,
+            (1, false) => FaceDirection::Up,
+            (_, true) => FaceDirection::North,
+            (_, false) => FaceDirection::South,
+        });
+
+        true
+    }
+
+    fn block_position(&self) -> BlockPosition<SIZE> {
+        BlockPosition::from_absolute(self.block[0], self.block[1], self.block[2], self.dimension)
+    }
+}
+
+/// Casts a ray, returning the first block for which `is_solid` returns true
+pub fn raycast<const SIZE: usize, F: FnMut(&BlockPosition<SIZE>) -> bool>(
+    origin: &EntityPosition,
+    direction: [f32; 3],
+    max_distance: f32,
+    mut is_solid: F,
+) -> Option<RayHit<SIZE>> {
+    let mut ray = Raycast::new(origin, direction, max_distance);
+    while let Some(chunk_position) = ray.chunk_position() {
+        let hit = ray.walk_chunk(|offset| {
+            is_solid(&BlockPosition {
+                chunk_position,
+                offset: offset.clone(),
+            })
+        });
+        if hit.is_some() {
+            return hit;
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::block::BlockPosition;
+    use crate::chunk::ChunkPosition;
+    use crate::entity::{EntityOffset, EntityPosition};
+    use crate::raycast::{raycast, Raycast};
+    use block_chunk::mesh::FaceDirection;
+
+    fn origin(x: f32, y: f32, z: f32) -> EntityPosition {
+        EntityPosition {
+            chunk_position: ChunkPosition::default(),
+            offset: EntityOffset { x, y, z },
+        }
+    }
+
+    fn is_at(x: i64, y: i64, z: i64) -> impl FnMut(&BlockPosition<16>) -> bool {
+        move |position| {
+            position.absolute_x() == x && position.absolute_y() == y && position.absolute_z() == z
+        }
+    }
+
+    #[test]
+    fn it_should_hit_the_face_facing_the_ray() {
+        let hit = raycast(
+            &origin(0.5, 0.5, 0.5),
+            [1.0, 0.0, 0.0],
+            10.0,
+            is_at(3, 0, 0),
+        )
+        .unwrap();
+
+        assert_eq!(hit.position.absolute_x(), 3);
+        assert_eq!(hit.face, Some(FaceDirection::West));
+        assert!((hit.distance - 2.5).abs() < 0.0001);
+    }
+
+    #[test]
+    fn it_should_cross_in_to_negative_chunks() {
+        let hit = raycast(
+            &origin(0.5, 0.5, 0.5),
+            [0.0, -1.0, -1.0],
+            40.0,
+            is_at(0, -17, -17),
+        )
+        .unwrap();
+
+        assert_eq!(hit.position.chunk_position.y, -2);
+        assert_eq!(hit.position.chunk_position.z, -2);
+        assert_eq!(hit.position.offset.y, 15);
+        assert!(matches!(
+            hit.face,
+            Some(FaceDirection::Up | FaceDirection::South)
+        ));
+    }
+
+    #[test]
+    fn it_should_not_go_further_than_the_max_distance() {
+        let hit = raycast(&origin(0.5, 0.5, 0.5), [0.0, 0.0, 1.0], 5.0, is_at(0, 0, 6));
+
+        assert!(hit.is_none());
+    }
+
+    #[test]
+    fn it_should_report_no_face_when_starting_inside_a_block() {
+        let hit = raycast::<16, _>(&origin(0.5, 0.5, 0.5), [0.0, 1.0, 0.0], 5.0, |_| true).unwrap();
+
+        assert_eq!(hit.face, None);
+        assert_eq!(hit.distance, 0.0);
+    }
+
+    #[test]
+    fn it_should_walk_one_chunk_at_a_time() {
+        let mut ray = Raycast::<16>::new(&origin(15.5, 0.5, 0.5), [1.0, 0.0, 0.0], 20.0);
+
+        let first_chunk = ray.chunk_position().unwrap();
+        assert!(ray.walk_chunk(|_| false).is_none());
+        let second_chunk = ray.chunk_position().unwrap();
+
+        assert_eq!(first_chunk.x, 0);
+        assert_eq!(second_chunk.x, 1);
+    }
+
+    #[test]
+    fn it_should_not_walk_without_a_direction() {
+        let ray = Raycast::<16>::new(&origin(0.5, 0.5, 0.5), [0.0; 3], 20.0);
+
+        assert!(ray.chunk_position().is_none());
+    }
+}
@@ -1,6 +1,9 @@
+use block_chunk::mesh::binary_greedy_mesh;
+use block_chunk::mesh::binary_greedy_mesh_with_neighbours;
 use block_chunk::mesh::fast_mesh;
 use block_chunk::mesh::greedy_mesh;
-use block_chunk::mesh::BlockDescriptor;
+use block_chunk::mesh::greedy_mesh_with_neighbours;
+use block_chunk::mesh::{BlockDescriptor, BlockShape, ChunkNeighbours, FaceDirection};
 use block_chunk::Chunk;
 use criterion::{criterion_group, criterion_main, Criterion};
 
@@ -9,13 +12,16 @@ fn benchmark_set<const SIZE: usize>(c: &mut Criterion) {
         let chunk = Chunk::<u32, SIZE>::default();
         c.bench_function(&format!("chunk_{} fast_mesh box", SIZE), |b| {
             b.iter(|| {
-                fast_mesh(&chunk, |_| {
-                    Some(BlockDescriptor {
-                        is_standard_square: true,
-                        is_transparent: false,
-                        texture_id: (),
-                    })
-                })
+                fast_mesh(
+                    &chunk,
+                    |_| {
+                        Some(BlockDescriptor {
+                            shape: BlockShape::Cube,
+                            is_transparent: false,
+                        })
+                    },
+                    |_, _| (),
+                )
             })
         });
     }
@@ -24,17 +30,20 @@ fn benchmark_set<const SIZE: usize>(c: &mut Criterion) {
         let chunk = Chunk::<u32, SIZE>::new_checker(0, 1);
         c.bench_function(&format!("chunk_{} fast_mesh checker", SIZE), |b| {
             b.iter(|| {
-                fast_mesh(&chunk, |val| {
-                    if *val == 0 {
-                        None
-                    } else {
-                        Some(BlockDescriptor {
-                            is_standard_square: true,
-                            is_transparent: false,
-                            texture_id: (),
-                        })
-                    }
-                })
+                fast_mesh(
+                    &chunk,
+                    |val| {
+                        if *val == 0 {
+                            None
+                        } else {
+                            Some(BlockDescriptor {
+                                shape: BlockShape::Cube,
+                                is_transparent: false,
+                            })
+                        }
+                    },
+                    |_, _| (),
+                )
             })
         });
     }
@@ -43,13 +52,16 @@ fn benchmark_set<const SIZE: usize>(c: &mut Criterion) {
         let chunk = Chunk::<u32, SIZE>::default();
         c.bench_function(&format!("chunk_{} greedy_mesh box", SIZE), |b| {
             b.iter(|| {
-                greedy_mesh(&chunk, |_| {
-                    Some(BlockDescriptor {
-                        is_standard_square: true,
-                        is_transparent: false,
-                        texture_id: (),
-                    })
-                })
+                greedy_mesh(
+                    &chunk,
+                    |_| {
+                        Some(BlockDescriptor {
+                            shape: BlockShape::Cube,
+                            is_transparent: false,
+                        })
+                    },
+                    |_, _| (),
+                )
             })
         });
     }
@@ -58,20 +70,112 @@ fn benchmark_set<const SIZE: usize>(c: &mut Criterion) {
         let chunk = Chunk::<u32, SIZE>::new_checker(0, 1);
         c.bench_function(&format!("chunk_{} greedy_mesh checker", SIZE), |b| {
             b.iter(|| {
-                greedy_mesh(&chunk, |val| {
-                    if *val == 0 {
-                        None
-                    } else {
+                greedy_mesh(
+                    &chunk,
+                    |val| {
+                        if *val == 0 {
+                            None
+                        } else {
+                            Some(BlockDescriptor {
+                                shape: BlockShape::Cube,
+                                is_transparent: false,
+                            })
+                        }
+                    },
+                    |_, _| (),
+                )
+            })
+        });
+    }
+
+    {
+        let chunk = Chunk::<u32, SIZE>::default();
+        let mut neighbours = ChunkNeighbours::new();
+        for direction in FaceDirection::ALL {
+            neighbours.insert(direction, &chunk);
+        }
+        c.bench_function(&format!("chunk_{} greedy_mesh buried box", SIZE), |b| {
+            b.iter(|| {
+                greedy_mesh_with_neighbours(
+                    &chunk,
+                    &neighbours,
+                    |_| {
                         Some(BlockDescriptor {
-                            is_standard_square: true,
+                            shape: BlockShape::Cube,
                             is_transparent: false,
-                            texture_id: (),
                         })
-                    }
-                })
+                    },
+                    |_, _| (),
+                )
+            })
+        });
+    }
+
+    {
+        let chunk = Chunk::<u32, SIZE>::default();
+        c.bench_function(&format!("chunk_{} binary_greedy_mesh box", SIZE), |b| {
+            b.iter(|| {
+                binary_greedy_mesh(
+                    &chunk,
+                    |_| {
+                        Some(BlockDescriptor {
+                            shape: BlockShape::Cube,
+                            is_transparent: false,
+                        })
+                    },
+                    |_, _| (),
+                )
             })
         });
     }
+
+    {
+        let chunk = Chunk::<u32, SIZE>::new_checker(0, 1);
+        c.bench_function(&format!("chunk_{} binary_greedy_mesh checker", SIZE), |b| {
+            b.iter(|| {
+                binary_greedy_mesh(
+                    &chunk,
+                    |val| {
+                        if *val == 0 {
+                            None
+                        } else {
+                            Some(BlockDescriptor {
+                                shape: BlockShape::Cube,
+                                is_transparent: false,
+                            })
+                        }
+                    },
+                    |_, _| (),
+                )
+            })
+        });
+    }
+
+    {
+        let chunk = Chunk::<u32, SIZE>::default();
+        let mut neighbours = ChunkNeighbours::new();
+        for direction in FaceDirection::ALL {
+            neighbours.insert(direction, &chunk);
+        }
+        c.bench_function(
+            &format!("chunk_{} binary_greedy_mesh buried box", SIZE),
+            |b| {
+                b.iter(|| {
+                    binary_greedy_mesh_with_neighbours(
+                        &chunk,
+                        &neighbours,
+                        |_| {
+                            Some(BlockDescriptor {
+                                shape: BlockShape::Cube,
+                                is_transparent: false,
+                            })
+                        },
+                        |_, _| (),
+                    )
+                })
+            },
+        );
+    }
 }
 
 pub fn meshable_chunk(c: &mut Criterion) {
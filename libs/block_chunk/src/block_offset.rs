@@ -1,5 +1,5 @@
 /// A offset within a chunk. Points out a specific block
-#[derive(Default, Debug, Clone, Eq, PartialEq, Hash)]
+#[derive(Default, Debug, Clone, Eq, PartialEq, Hash, bincode::Encode, bincode::Decode)]
 pub struct BlockOffset<const SIZE: usize> {
     pub x: usize,
     pub y: usize,
@@ -1,97 +1,241 @@
-use crate::BlockOffset;
+use crate::chunk_format::{ChunkHeader, DenseBlocks};
+use crate::palette::PaletteStorage;
+use crate::{BlockOffset, Codec, FormatVersion};
 use bincode::config;
-use flate2::read::GzDecoder;
-use flate2::write::GzEncoder;
-use flate2::Compression;
+use bincode::de::{BorrowDecoder, Decoder};
+use bincode::error::DecodeError;
 use std::error::Error;
-use std::io::Read;
 use std::mem;
 
-#[derive(Clone, Debug, bincode::Encode, bincode::Decode)]
+/// A cube of blocks.
+///
+/// The blocks are stored as a palette of the distinct blocks in the chunk, with a bit packed index
+/// in to that palette for every position. Chunks that only contain a single kind of block, like
+/// chunks of nothing but air or stone, takes up no more space than that one block
+#[derive(Clone, Debug, bincode::Encode)]
 pub struct Chunk<T: 'static + Send + Sync, const SIZE: usize> {
-    blocks: Box<[[[T; SIZE]; SIZE]; SIZE]>,
+    blocks: PaletteStorage<T>,
 }
 
-impl<T: 'static + Send + Sync + Default + Copy, const SIZE: usize> Chunk<T, SIZE> {
+impl<T: 'static + Send + Sync + Default, const SIZE: usize> Chunk<T, SIZE> {
     pub fn new() -> Self {
-        Self {
-            blocks: Box::new([[[T::default(); SIZE]; SIZE]; SIZE]),
-        }
+        Self::filled(T::default())
     }
 }
 
 impl<T: 'static + Send + Sync, const SIZE: usize> Chunk<T, SIZE> {
+    const BLOCK_COUNT: usize = SIZE * SIZE * SIZE;
+
+    /// Creates a chunk where every block is the given value
+    pub const fn filled(value: T) -> Self {
+        Self {
+            blocks: PaletteStorage::new(value, Self::BLOCK_COUNT),
+        }
+    }
+
     /// # Panics
     #[inline]
     pub fn get(&self, position: &BlockOffset<SIZE>) -> &T {
-        assert!(position.x < SIZE);
-        assert!(position.y < SIZE);
-        assert!(position.z < SIZE);
+        self.blocks.get(Self::index(position))
+    }
 
-        &self.blocks[position.z][position.y][position.x]
+    /// Whether every block in the chunk is the same
+    pub const fn is_uniform(&self) -> bool {
+        self.blocks.is_uniform()
+    }
+
+    /// The amount of memory used by the chunk, in bytes
+    pub fn byte_size(&self) -> usize {
+        mem::size_of::<Self>() + self.blocks.byte_size()
     }
 
-    /// # Panics
     #[inline]
-    pub fn set(&mut self, mut value: T, position: &BlockOffset<SIZE>) -> T {
+    fn index(position: &BlockOffset<SIZE>) -> usize {
         assert!(position.x < SIZE);
         assert!(position.y < SIZE);
         assert!(position.z < SIZE);
 
-        mem::swap(
-            &mut self.blocks[position.z][position.y][position.x],
-            &mut value,
-        );
-        value
+        (position.z * SIZE + position.y) * SIZE + position.x
     }
 }
 
-impl<T: 'static + Send + Sync + Clone + Copy, const SIZE: usize> Chunk<T, SIZE> {
+impl<T: 'static + Send + Sync + Clone + PartialEq, const SIZE: usize> Chunk<T, SIZE> {
+    /// # Panics
+    #[inline]
+    pub fn set(&mut self, value: T, position: &BlockOffset<SIZE>) -> T {
+        self.blocks.set(Self::index(position), value)
+    }
+
     pub fn new_checker(val_1: T, val_2: T) -> Self {
-        let mut blocks = Box::new([[[val_1; SIZE]; SIZE]; SIZE]);
+        let mut chunk = Self::filled(val_1);
         for x in 0..SIZE {
             for y in 0..SIZE {
                 for z in 0..SIZE {
                     if (z + ((y + (x % 2)) % 2)) % 2 == 1 {
-                        blocks[z][y][x] = val_2.clone();
+                        chunk.set(val_2.clone(), &BlockOffset { x, y, z });
                     }
                 }
             }
         }
 
-        Self { blocks }
+        chunk
     }
 
     pub fn set_all(&mut self, value: T) {
-        for x in 0..SIZE {
-            for y in 0..SIZE {
-                for z in 0..SIZE {
-                    self.blocks[z][y][x] = value
-                }
-            }
-        }
+        self.blocks = PaletteStorage::new(value, Self::BLOCK_COUNT);
     }
 }
 
 impl<T: 'static + Send + Sync + bincode::Encode, const SIZE: usize> Chunk<T, SIZE> {
-    pub fn compress(self) -> Result<Vec<u8>, Box<dyn Error + Send + Sync>> {
-        let encoded = bincode::encode_to_vec(self, config::standard())?;
-        let encoder = GzEncoder::new(encoded, Compression::best());
-        Ok(encoder.finish()?)
+    /// Serializes the chunk in the current format, compressed with gzip
+    ///
+    /// # Errors
+    /// If the chunk could not be encoded or compressed
+    pub fn compress(&self) -> Result<Vec<u8>, Box<dyn Error + Send + Sync>> {
+        self.compress_with(FormatVersion::CURRENT, Codec::Gzip)
+    }
+
+    /// Serializes the chunk in the given format, compressed with the given codec. The result
+    /// starts with a header holding a magic number, the format version, the codec, the chunk size
+    /// and a checksum of the rest
+    ///
+    /// # Errors
+    /// If the chunk could not be encoded or compressed
+    pub fn compress_with(
+        &self,
+        version: FormatVersion,
+        codec: Codec,
+    ) -> Result<Vec<u8>, Box<dyn Error + Send + Sync>> {
+        let encoded = match version {
+            FormatVersion::Dense => {
+                let blocks = (0..Self::BLOCK_COUNT)
+                    .map(|index| self.blocks.get(index))
+                    .collect();
+                bincode::encode_to_vec(DenseBlocks::<_, SIZE>(blocks), config::standard())?
+            }
+            FormatVersion::Palette => bincode::encode_to_vec(self, config::standard())?,
+        };
+        let payload = codec.compress(encoded)?;
+
+        let header = ChunkHeader::for_payload(version, codec, SIZE, &payload)?;
+        let mut bytes = Vec::with_capacity(ChunkHeader::BYTE_SIZE + payload.len());
+        header.write(&mut bytes);
+        bytes.extend_from_slice(&payload);
+        Ok(bytes)
     }
 }
 
-impl<T: 'static + Send + Sync + bincode::Decode, const SIZE: usize> Chunk<T, SIZE> {
+impl<T: 'static + Send + Sync + bincode::Decode + Clone + PartialEq, const SIZE: usize>
+    Chunk<T, SIZE>
+{
+    /// Deserializes a chunk written by [`Chunk::compress`], in the current or any earlier format.
+    /// Chunks in older formats are upgraded, and are written in the current format the next time
+    /// they are compressed
+    ///
+    /// # Errors
+    /// If the bytes are not a chunk, are corrupt or belong to a chunk of another size
     pub fn from_compressed(compressed_bytes: &[u8]) -> Result<Self, Box<dyn Error + Send + Sync>> {
-        let mut decoder = GzDecoder::new(compressed_bytes);
-        let mut decompressed = vec![];
-        decoder.read(&mut decompressed)?;
-        let (chunk, _) = bincode::decode_from_slice(&decompressed, config::standard())?;
+        if !ChunkHeader::is_present(compressed_bytes) {
+            return Err("Bytes are not a chunk".into());
+        }
+
+        let (header, payload) = ChunkHeader::read(compressed_bytes)?;
+        if usize::from(header.chunk_size) != SIZE {
+            return Err(format!(
+                "Expected a chunk of size {}, found {}",
+                SIZE, header.chunk_size
+            )
+            .into());
+        }
+
+        let payload = header.codec.decompress(payload, Self::max_payload_size())?;
+        Self::decode_payload(header.version, &payload)
+    }
+
+    /// The most bytes the payload of a chunk can take up before it is compressed, so that a small
+    /// corrupt or malicious payload can not decompress to something huge. In either format every
+    /// block is encoded at most twice, once in the palette and once as an index in to it, and
+    /// encoding a block never takes more than a byte on top of its size
+    const fn max_payload_size() -> usize {
+        Self::BLOCK_COUNT * 2 * (mem::size_of::<T>() + 1) + 64
+    }
+
+    fn decode_payload(
+        version: FormatVersion,
+        bytes: &[u8],
+    ) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        let (chunk, read) = match version {
+            FormatVersion::Dense => {
+                let (DenseBlocks(blocks), read) = bincode::decode_from_slice::<
+                    DenseBlocks<T, SIZE>,
+                    _,
+                >(bytes, config::standard())?;
+                (Self::from_dense(blocks)?, read)
+            }
+            // Lengths in the palette are checked against the size of the chunk before anything is
+            // allocated for them
+            FormatVersion::Palette => bincode::decode_from_slice(bytes, config::standard())?,
+        };
+
+        if read == bytes.len() {
+            Ok(chunk)
+        } else {
+            Err("Chunk payload contains trailing bytes".into())
+        }
+    }
+
+    fn from_dense(blocks: Vec<T>) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        let mut blocks = blocks.into_iter().enumerate();
+        let mut chunk = match blocks.next() {
+            Some((_, first)) => Self::filled(first),
+            None => return Err("Chunk contains no blocks".into()),
+        };
+        for (index, block) in blocks {
+            chunk.blocks.set(index, block);
+        }
         Ok(chunk)
     }
 }
 
-impl<T: 'static + Send + Sync + Default + Copy, const SIZE: usize> Default for Chunk<T, SIZE> {
+impl<T: 'static + Send + Sync + bincode::Decode, const SIZE: usize> bincode::Decode
+    for Chunk<T, SIZE>
+{
+    fn decode<D: Decoder>(decoder: &mut D) -> Result<Self, DecodeError> {
+        Self::from_blocks(PaletteStorage::decode_with_len(
+            decoder,
+            Self::BLOCK_COUNT,
+            T::decode,
+        )?)
+    }
+}
+
+impl<'de, T: 'static + Send + Sync + bincode::BorrowDecode<'de>, const SIZE: usize>
+    bincode::BorrowDecode<'de> for Chunk<T, SIZE>
+{
+    fn borrow_decode<D: BorrowDecoder<'de>>(decoder: &mut D) -> Result<Self, DecodeError> {
+        Self::from_blocks(PaletteStorage::decode_with_len(
+            decoder,
+            Self::BLOCK_COUNT,
+            T::borrow_decode,
+        )?)
+    }
+}
+
+impl<T: 'static + Send + Sync, const SIZE: usize> Chunk<T, SIZE> {
+    fn from_blocks(blocks: PaletteStorage<T>) -> Result<Self, DecodeError> {
+        if blocks.len() == Self::BLOCK_COUNT {
+            Ok(Self { blocks })
+        } else {
+            Err(DecodeError::OtherString(format!(
+                "Expected {} blocks in chunk, found {}",
+                Self::BLOCK_COUNT,
+                blocks.len()
+            )))
+        }
+    }
+}
+
+impl<T: 'static + Send + Sync + Default, const SIZE: usize> Default for Chunk<T, SIZE> {
     fn default() -> Self {
         Self::new()
     }
@@ -99,7 +243,29 @@ impl<T: 'static + Send + Sync + Default + Copy, const SIZE: usize> Default for C
 
 #[cfg(test)]
 mod tests {
-    use crate::Chunk;
+    use crate::chunk_format::ChunkHeader;
+    use crate::{Chunk, Codec, FormatVersion};
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+    use std::io::Write;
+    use std::mem;
+
+    fn assert_same_blocks<const SIZE: usize>(first: &Chunk<u32, SIZE>, second: &Chunk<u32, SIZE>) {
+        for x in 0..SIZE {
+            for y in 0..SIZE {
+                for z in 0..SIZE {
+                    let position = (x, y, z).into();
+                    assert_eq!(first.get(&position), second.get(&position));
+                }
+            }
+        }
+    }
+
+    fn create_test_chunk() -> Chunk<u32, 4> {
+        let mut chunk = Chunk::new_checker(0, 1);
+        chunk.set(7, &(1, 2, 3).into());
+        chunk
+    }
 
     #[test]
     fn it_should_get_and_set() {
@@ -114,14 +280,99 @@ mod tests {
     fn checker_should_be_correct() {
         let chunk: Chunk<_, 4> = Chunk::new_checker(0, 1);
 
-        assert_eq!(chunk.blocks[0][0][0], 0);
-        assert_eq!(chunk.blocks[1][0][0], 1);
-        assert_eq!(chunk.blocks[0][1][0], 1);
-        assert_eq!(chunk.blocks[1][1][0], 0);
+        assert_eq!(*chunk.get(&(0, 0, 0).into()), 0);
+        assert_eq!(*chunk.get(&(0, 0, 1).into()), 1);
+        assert_eq!(*chunk.get(&(0, 1, 0).into()), 1);
+        assert_eq!(*chunk.get(&(0, 1, 1).into()), 0);
+
+        assert_eq!(*chunk.get(&(1, 0, 0).into()), 1);
+        assert_eq!(*chunk.get(&(1, 0, 1).into()), 0);
+        assert_eq!(*chunk.get(&(1, 1, 0).into()), 0);
+        assert_eq!(*chunk.get(&(1, 1, 1).into()), 1);
+    }
+
+    #[test]
+    fn it_should_survive_compression() {
+        let chunk = create_test_chunk();
+
+        let compressed = chunk.compress().unwrap();
+        let decompressed = Chunk::<u32, 4>::from_compressed(&compressed).unwrap();
+
+        assert_same_blocks(&chunk, &decompressed);
+    }
+
+    #[test]
+    fn every_format_version_should_survive_compression() {
+        let chunk = create_test_chunk();
+
+        for version in [FormatVersion::Dense, FormatVersion::Palette] {
+            for codec in [Codec::None, Codec::Gzip] {
+                let compressed = chunk.compress_with(version, codec).unwrap();
+                let decompressed = Chunk::<u32, 4>::from_compressed(&compressed).unwrap();
+
+                assert_same_blocks(&chunk, &decompressed);
+            }
+        }
+    }
+
+    #[test]
+    fn it_should_not_decompress_payloads_larger_than_a_chunk() {
+        let huge = vec![0; 1024 * 1024];
+        let mut encoder = GzEncoder::new(vec![], Compression::best());
+        encoder.write_all(&huge).unwrap();
+        let payload = encoder.finish().unwrap();
+        let header =
+            ChunkHeader::for_payload(FormatVersion::Dense, Codec::Gzip, 4, &payload).unwrap();
+        let mut compressed = vec![];
+        header.write(&mut compressed);
+        compressed.extend_from_slice(&payload);
+
+        assert!(Chunk::<u32, 4>::from_compressed(&compressed).is_err());
+    }
+
+    #[test]
+    fn it_should_not_decode_palettes_larger_than_a_chunk() {
+        // A palette that claims to hold 2^40 entries
+        let mut payload = vec![1, 253];
+        payload.extend_from_slice(&(1_u64 << 40).to_le_bytes());
+        let header =
+            ChunkHeader::for_payload(FormatVersion::Palette, Codec::None, 4, &payload).unwrap();
+        let mut compressed = vec![];
+        header.write(&mut compressed);
+        compressed.extend_from_slice(&payload);
+
+        assert!(Chunk::<u32, 4>::from_compressed(&compressed).is_err());
+    }
+
+    #[test]
+    fn it_should_not_decompress_corrupted_chunks() {
+        let mut compressed = create_test_chunk().compress().unwrap();
+        let last = compressed.len() - 1;
+        compressed[last] ^= 0xff;
+
+        assert!(Chunk::<u32, 4>::from_compressed(&compressed).is_err());
+        assert!(Chunk::<u32, 4>::from_compressed(&[1, 2, 3]).is_err());
+    }
+
+    #[test]
+    fn uniform_chunks_should_be_small() {
+        let mut chunk = Chunk::<u128, 32>::new();
+        assert!(chunk.is_uniform());
+        assert_eq!(chunk.byte_size(), mem::size_of::<Chunk<u128, 32>>());
+
+        chunk.set(1, &(0, 0, 0).into());
+        assert!(!chunk.is_uniform());
+        // One bit per block, plus the palette
+        assert!(chunk.byte_size() < 32 * 32 * 32 / 8 + 256);
+
+        chunk.set(0, &(0, 0, 0).into());
+        assert!(chunk.is_uniform());
+    }
+
+    #[test]
+    fn it_should_not_decompress_chunks_of_another_size() {
+        let compressed = Chunk::<u32, 4>::new_checker(0, 1).compress().unwrap();
 
-        assert_eq!(chunk.blocks[0][0][1], 1);
-        assert_eq!(chunk.blocks[1][0][1], 0);
-        assert_eq!(chunk.blocks[0][1][1], 0);
-        assert_eq!(chunk.blocks[1][1][1], 1);
+        assert!(Chunk::<u32, 8>::from_compressed(&compressed).is_err());
     }
 }
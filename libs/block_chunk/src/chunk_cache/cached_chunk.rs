@@ -0,0 +1,124 @@
+use crate::Chunk;
+use std::mem;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+/// A chunk living in the in memory tier of the cache, together with the last time it was accessed
+/// and whether it has been modified since it was last persisted
+#[derive(Debug)]
+pub struct CachedChunk<T: 'static + Send + Sync, const SIZE: usize> {
+    chunk: Arc<RwLock<Chunk<T, SIZE>>>,
+    last_access: AtomicU64,
+    is_dirty: AtomicBool,
+    /// The size of the chunk the last time it could be measured
+    byte_size: AtomicUsize,
+}
+
+impl<T: 'static + Send + Sync, const SIZE: usize> CachedChunk<T, SIZE> {
+    pub fn new(chunk: Arc<RwLock<Chunk<T, SIZE>>>, access: u64, is_dirty: bool) -> Self {
+        let byte_size = chunk
+            .try_read()
+            .map_or(mem::size_of::<Chunk<T, SIZE>>(), |chunk| chunk.byte_size());
+
+        Self {
+            chunk,
+            last_access: AtomicU64::new(access),
+            is_dirty: AtomicBool::new(is_dirty),
+            byte_size: AtomicUsize::new(byte_size),
+        }
+    }
+
+    /// Marks the chunk as used and hands out a new reference to it
+    pub fn touch(&self, access: u64) -> Arc<RwLock<Chunk<T, SIZE>>> {
+        self.last_access.fetch_max(access, Ordering::Relaxed);
+        Arc::clone(&self.chunk)
+    }
+
+    /// Hands out a new reference to the chunk, without counting it as a use
+    pub fn chunk(&self) -> Arc<RwLock<Chunk<T, SIZE>>> {
+        Arc::clone(&self.chunk)
+    }
+
+    /// Whether this is the given chunk, and not one that took its place
+    pub fn is(&self, chunk: &Arc<RwLock<Chunk<T, SIZE>>>) -> bool {
+        Arc::ptr_eq(&self.chunk, chunk)
+    }
+
+    pub fn last_access(&self) -> u64 {
+        self.last_access.load(Ordering::Relaxed)
+    }
+
+    pub fn is_dirty(&self) -> bool {
+        self.is_dirty.load(Ordering::Acquire)
+    }
+
+    pub fn mark_dirty(&self) {
+        self.is_dirty.store(true, Ordering::Release);
+    }
+
+    /// Marks the chunk as clean, returning whether it was dirty
+    pub fn take_dirty(&self) -> bool {
+        self.is_dirty.swap(false, Ordering::AcqRel)
+    }
+
+    /// The amount of memory used by the chunk. Chunks that are currently being written to can not
+    /// be measured, so the size from the last time they could is used instead
+    pub fn byte_size(&self) -> usize {
+        if let Ok(chunk) = self.chunk.try_read() {
+            self.byte_size.store(chunk.byte_size(), Ordering::Relaxed);
+        }
+        self.byte_size.load(Ordering::Relaxed)
+    }
+
+    /// A chunk is borrowed as long as anyone outside of the cache holds a reference to it
+    pub fn is_borrowed(&self) -> bool {
+        Arc::strong_count(&self.chunk) > 1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::chunk_cache::cached_chunk::CachedChunk;
+    use crate::Chunk;
+    use std::sync::Arc;
+    use tokio::sync::RwLock;
+
+    #[test]
+    fn it_should_be_borrowed_while_a_reference_is_alive() {
+        let cached = CachedChunk::new(Arc::new(RwLock::new(Chunk::<u32, 4>::default())), 0, false);
+        assert!(!cached.is_borrowed());
+
+        let reference = cached.touch(1);
+        assert!(cached.is_borrowed());
+        assert_eq!(cached.last_access(), 1);
+
+        drop(reference);
+        assert!(!cached.is_borrowed());
+    }
+
+    #[tokio::test]
+    async fn it_should_remember_its_size_while_being_written_to() {
+        let cached = CachedChunk::new(Arc::new(RwLock::new(Chunk::<u32, 4>::default())), 0, false);
+        let uniform_size = cached.byte_size();
+
+        let chunk = cached.chunk();
+        let mut lock = chunk.write().await;
+        lock.set(1, &(0, 0, 0).into());
+        assert_eq!(cached.byte_size(), uniform_size);
+
+        drop(lock);
+        assert!(cached.byte_size() > uniform_size);
+    }
+
+    #[test]
+    fn take_dirty_should_clean_the_chunk() {
+        let cached = CachedChunk::new(Arc::new(RwLock::new(Chunk::<u32, 4>::default())), 0, false);
+        assert!(!cached.take_dirty());
+
+        cached.mark_dirty();
+        assert!(cached.is_dirty());
+        assert!(cached.take_dirty());
+        assert!(!cached.is_dirty());
+    }
+}
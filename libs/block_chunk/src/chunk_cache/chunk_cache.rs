@@ -0,0 +1,934 @@
+use crate::chunk_cache::cached_chunk::CachedChunk;
+use crate::chunk_cache::compressed_chunks::CompressedChunks;
+use crate::chunk_cache::in_flight_load::{InFlightLoad, InFlightLoads};
+use crate::{Chunk, ChunkFactory, ChunkStorage};
+use bincode::{Decode, Encode};
+use std::collections::HashMap;
+use std::error::Error;
+use std::future::Future;
+use std::hash::Hash;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use tokio::sync::{Mutex, OwnedRwLockReadGuard, OwnedRwLockWriteGuard, RwLock};
+
+type LockedChunk<T, const SIZE: usize> = Arc<RwLock<Chunk<T, SIZE>>>;
+
+/// A tiered cache of chunks.
+///
+/// Recently used chunks are kept in memory, up to the in memory budget. When that budget is
+/// exceeded the least recently used chunks are compressed and moved to the compressed tier. When
+/// the compressed tier in turn grows past its budget, the oldest compressed chunks are evicted.
+/// Chunks that are borrowed are never evicted.
+///
+/// Chunks that has been borrowed mutably are considered dirty, and are written to the
+/// [`ChunkStorage`] when they leave the cache or when [`ChunkCache::flush_all`] is called. Clean
+/// chunks are never written, since they can always be loaded or generated again.
+///
+/// Loading is single flight. If several tasks ask for the same chunk while it is being loaded,
+/// they all wait for that one load and get the same chunk back.
+#[derive(Debug)]
+pub struct ChunkCache<
+    P: 'static + Hash + Eq + Send + Sync,
+    T: 'static + Send + Sync,
+    const SIZE: usize,
+> {
+    compressed_chunks: RwLock<CompressedChunks<P>>,
+    chunks: RwLock<HashMap<P, CachedChunk<T, SIZE>>>,
+    /// Chunks that has left the cache, but that the storage has not finished writing yet
+    pending_stores: Mutex<HashMap<P, Arc<Vec<u8>>>>,
+    /// Chunks that are being written to storage. A chunk is only written by one store at a time,
+    /// so that an older version can never overwrite a newer one
+    store_locks: Mutex<HashMap<P, Arc<Mutex<()>>>>,
+    /// Loads that are currently in flight, shared by everyone waiting for the same chunk
+    loading: InFlightLoads<P, LockedChunk<T, SIZE>>,
+    access_counter: AtomicU64,
+    max_in_mem_chunk_byte_size: usize,
+    max_compressed_byte_size: usize,
+    storage: Arc<dyn ChunkStorage<P>>,
+    factory: Arc<dyn ChunkFactory<P, Chunk = Chunk<T, SIZE>>>,
+}
+
+impl<
+        P: 'static + Hash + Eq + Clone + Send + Sync,
+        T: 'static + Send + Sync + Clone + PartialEq + Encode + Decode,
+        const SIZE: usize,
+    > ChunkCache<P, T, SIZE>
+{
+    #[must_use]
+    pub fn new<
+        S: 'static + ChunkStorage<P>,
+        F: 'static + ChunkFactory<P, Chunk = Chunk<T, SIZE>>,
+    >(
+        max_in_mem_chunk_byte_size: usize,
+        max_compressed_byte_size: usize,
+        storage: Arc<S>,
+        factory: Arc<F>,
+    ) -> Self {
+        Self {
+            compressed_chunks: RwLock::new(CompressedChunks::new()),
+            chunks: RwLock::new(HashMap::new()),
+            pending_stores: Mutex::new(HashMap::new()),
+            store_locks: Mutex::new(HashMap::new()),
+            loading: std::sync::Mutex::new(HashMap::new()),
+            access_counter: AtomicU64::new(0),
+            max_in_mem_chunk_byte_size,
+            max_compressed_byte_size,
+            storage,
+            factory,
+        }
+    }
+
+    /// Borrows a chunk from the cache. The chunk can only be used for the duration of the closure
+    ///
+    /// # Returns
+    /// The result from the closure
+    ///
+    /// # Errors
+    /// If the storage can not load the given chunk
+    pub async fn borrow_chunk<
+        C: Send + Sync + FnOnce(OwnedRwLockReadGuard<Chunk<T, SIZE>>) -> FR,
+        FR: Future<Output = R> + Send,
+        R: Send + Sync,
+    >(
+        &self,
+        position: &P,
+        callback: C,
+    ) -> Result<R, Box<dyn Error + Send + Sync>> {
+        let chunk = self.acquire_chunk(position).await?;
+        let lock = chunk.read_owned().await;
+        Ok(callback(lock).await)
+    }
+
+    /// Mutably borrows a chunk from the cache. The chunk can only be used for the duration of the
+    /// closure. The chunk is marked as dirty, and will be written to storage once it leaves the
+    /// cache, or when the cache is flushed
+    ///
+    /// # Returns
+    /// The result from the closure
+    ///
+    /// # Errors
+    /// If the storage can not load the given chunk
+    pub async fn borrow_chunk_mut<
+        C: Send + Sync + FnOnce(OwnedRwLockWriteGuard<Chunk<T, SIZE>>) -> FR,
+        FR: Future<Output = R> + Send,
+        R: Send + Sync,
+    >(
+        &self,
+        position: &P,
+        callback: C,
+    ) -> Result<R, Box<dyn Error + Send + Sync>> {
+        let chunk = self.acquire_chunk(position).await?;
+        let lock = chunk.write_owned().await;
+        {
+            // Only marked once we hold the chunk, so that a flush can not clean it while we are
+            // still waiting for it. Any flush that cleans it from now on has to wait for us to
+            // finish before it can read it. The chunk is borrowed by us, so it can not have been
+            // evicted
+            let chunks = self.chunks.read().await;
+            if let Some(cached) = chunks.get(position) {
+                cached.mark_dirty();
+            }
+        }
+        Ok(callback(lock).await)
+    }
+
+    /// Writes every dirty chunk to storage, both the ones in memory and the compressed ones. The
+    /// chunks are kept in the cache, but are considered clean afterwards
+    ///
+    /// # Errors
+    /// If the storage fails to store any of the chunks. The chunks that failed are kept dirty, so
+    /// that flushing can be retried later
+    pub async fn flush_all(&self) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let dirty_chunks = {
+            let chunks = self.chunks.read().await;
+            chunks
+                .iter()
+                .filter(|(_, cached)| cached.take_dirty())
+                .map(|(position, cached)| (position.clone(), cached.chunk()))
+                .collect::<Vec<_>>()
+        };
+        let dirty_compressed_chunks = self.compressed_chunks.write().await.take_dirty();
+
+        log::debug!(
+            "Flushing {} chunks",
+            dirty_chunks.len() + dirty_compressed_chunks.len()
+        );
+
+        let mut first_error = None;
+
+        for (position, chunk) in dirty_chunks {
+            let compressed = chunk.read().await.compress();
+            let bytes = match compressed {
+                Ok(bytes) => bytes,
+                Err(error) => {
+                    log::error!("Failed to compress chunk while flushing: {}", error);
+                    // We still hold a reference to the chunk, so it is still in memory
+                    self.mark_dirty(&position, None).await;
+                    first_error.get_or_insert(error);
+                    continue;
+                }
+            };
+            drop(chunk);
+
+            if let Err(error) = self.storage.store(&position, bytes.clone()).await {
+                log::error!("Failed to flush chunk: {}", error);
+                self.mark_dirty(&position, Some(bytes)).await;
+                first_error.get_or_insert(error);
+            }
+        }
+
+        for (position, bytes) in dirty_compressed_chunks {
+            if let Err(error) = self.storage.store(&position, bytes.clone()).await {
+                log::error!("Failed to flush chunk: {}", error);
+                self.mark_dirty(&position, Some(bytes)).await;
+                first_error.get_or_insert(error);
+            }
+        }
+
+        first_error.map_or(Ok(()), Err)
+    }
+
+    /// The number of chunks currently held in memory, uncompressed
+    pub async fn in_memory_count(&self) -> usize {
+        self.chunks.read().await.len()
+    }
+
+    /// The number of chunks currently held in the compressed tier
+    pub async fn compressed_count(&self) -> usize {
+        self.compressed_chunks.read().await.len()
+    }
+
+    async fn acquire_chunk(
+        &self,
+        position: &P,
+    ) -> Result<LockedChunk<T, SIZE>, Box<dyn Error + Send + Sync>> {
+        if let Some(chunk) = self.acquire_from_chunk_cache(position).await {
+            return Ok(chunk);
+        }
+
+        // Only one of the waiting tasks gets to run the load. As long as the load is around it
+        // holds on to the chunk, which keeps it from being evicted, so everyone in line gets the
+        // very same chunk
+        let load = InFlightLoad::join(&self.loading, position);
+        load.get_or_try_init(|| self.load_chunk(position))
+            .await
+            .map(Arc::clone)
+    }
+
+    async fn load_chunk(
+        &self,
+        position: &P,
+    ) -> Result<LockedChunk<T, SIZE>, Box<dyn Error + Send + Sync>> {
+        if let Some(chunk) = self.load_from_compressed_cache(position).await? {
+            Ok(chunk)
+        } else if let Some(chunk) = self.load_from_storage(position).await? {
+            Ok(chunk)
+        } else {
+            Ok(self.load_from_factory(position).await)
+        }
+    }
+
+    fn next_access(&self) -> u64 {
+        self.access_counter.fetch_add(1, Ordering::Relaxed)
+    }
+
+    async fn acquire_from_chunk_cache(&self, position: &P) -> Option<LockedChunk<T, SIZE>> {
+        let lock = self.chunks.read().await;
+        lock.get(position)
+            .map(|chunk| chunk.touch(self.next_access()))
+    }
+
+    async fn load_from_compressed_cache(
+        &self,
+        position: &P,
+    ) -> Result<Option<LockedChunk<T, SIZE>>, Box<dyn Error + Send + Sync>> {
+        // Locks are always taken in the order chunks -> compressed chunks -> pending stores.
+        // Holding them all makes sure that the chunk is never missing from every tier at once
+        let mut chunks = self.chunks.write().await;
+        if let Some(chunk) = chunks.get(position) {
+            return Ok(Some(chunk.touch(self.next_access())));
+        }
+
+        let mut compressed_chunks = self.compressed_chunks.write().await;
+        let (data, is_dirty) = match compressed_chunks.remove(position) {
+            Some(entry) => entry,
+            None => {
+                // A chunk that is on its way to storage is just as up to date as the one stored
+                let pending_stores = self.pending_stores.lock().await;
+                match pending_stores.get(position) {
+                    Some(data) => (data.as_ref().clone(), false),
+                    None => return Ok(None),
+                }
+            }
+        };
+
+        match Chunk::from_compressed(&data) {
+            Ok(chunk) => {
+                let locked_chunk = Arc::new(RwLock::new(chunk));
+                chunks.insert(
+                    position.clone(),
+                    CachedChunk::new(Arc::clone(&locked_chunk), self.next_access(), is_dirty),
+                );
+                drop(compressed_chunks);
+                drop(chunks);
+
+                self.evict().await;
+                Ok(Some(locked_chunk))
+            }
+            Err(error) => {
+                compressed_chunks.insert(position.clone(), data, is_dirty);
+                Err(error)
+            }
+        }
+    }
+
+    async fn load_from_storage(
+        &self,
+        position: &P,
+    ) -> Result<Option<LockedChunk<T, SIZE>>, Box<dyn Error + Send + Sync>> {
+        if let Some(loaded_data) = self.storage.load(position).await? {
+            let chunk = Chunk::from_compressed(&loaded_data)?;
+            Ok(Some(self.insert_chunk(position, chunk).await))
+        } else {
+            Ok(None)
+        }
+    }
+
+    async fn load_from_factory(&self, position: &P) -> LockedChunk<T, SIZE> {
+        let chunk = self.factory.generate_chunk(position).await;
+        self.insert_chunk(position, chunk).await
+    }
+
+    async fn insert_chunk(&self, position: &P, chunk: Chunk<T, SIZE>) -> LockedChunk<T, SIZE> {
+        let locked_chunk = Arc::new(RwLock::new(chunk));
+
+        {
+            let mut lock = self.chunks.write().await;
+            if lock
+                .insert(
+                    position.clone(),
+                    CachedChunk::new(Arc::clone(&locked_chunk), self.next_access(), false),
+                )
+                .is_some()
+            {
+                log::error!("Inserting chunk that already existed in cache... weird!");
+            }
+        }
+
+        self.evict().await;
+
+        locked_chunk
+    }
+
+    /// Makes sure both tiers are within their budgets
+    async fn evict(&self) {
+        self.compress_least_recently_used().await;
+
+        let overflow = {
+            let mut compressed_chunks = self.compressed_chunks.write().await;
+
+            let mut overflow = vec![];
+            while compressed_chunks.byte_size() > self.max_compressed_byte_size {
+                match compressed_chunks.pop_oldest() {
+                    // Clean chunks can just be dropped
+                    Some((_, _, false)) => {}
+                    Some((position, bytes, true)) => overflow.push((position, Arc::new(bytes))),
+                    None => break,
+                }
+            }
+
+            if !overflow.is_empty() {
+                let mut pending_stores = self.pending_stores.lock().await;
+                for (position, bytes) in &overflow {
+                    pending_stores.insert(position.clone(), Arc::clone(bytes));
+                }
+            }
+
+            overflow
+        };
+
+        self.store_overflow(overflow).await;
+    }
+
+    /// Moves the least recently used chunks that are not borrowed over to the compressed tier,
+    /// until the in memory tier is within its budget.
+    ///
+    /// Compressing is slow, so it is done without holding the cache locks. The chunks stay in
+    /// memory meanwhile, and are only swapped for their compressed bytes if nobody used them while
+    /// they were being compressed
+    async fn compress_least_recently_used(&self) {
+        let victims = {
+            let chunks = self.chunks.read().await;
+            let mut byte_size = chunks.values().map(CachedChunk::byte_size).sum::<usize>();
+            if byte_size <= self.max_in_mem_chunk_byte_size {
+                return;
+            }
+
+            let mut candidates = chunks
+                .iter()
+                .filter(|(_, cached)| !cached.is_borrowed())
+                .collect::<Vec<_>>();
+            candidates.sort_unstable_by_key(|(_, cached)| cached.last_access());
+
+            let mut victims = vec![];
+            for (position, cached) in candidates {
+                if byte_size <= self.max_in_mem_chunk_byte_size {
+                    break;
+                }
+                byte_size -= cached.byte_size();
+                victims.push((position.clone(), cached.chunk(), cached.last_access()));
+            }
+            victims
+        };
+
+        let mut compressed = Vec::with_capacity(victims.len());
+        for (position, chunk, last_access) in victims {
+            let result = chunk.read().await.compress();
+            match result {
+                Ok(bytes) => compressed.push((position, chunk, last_access, bytes)),
+                Err(error) => {
+                    log::error!("Failed to compress chunk, keeping it in memory: {}", error);
+                }
+            }
+        }
+
+        let mut chunks = self.chunks.write().await;
+        let mut compressed_chunks = self.compressed_chunks.write().await;
+        for (position, chunk, last_access, bytes) in compressed {
+            // Anyone who borrowed the chunk since touched it, and might have changed it
+            let is_unused = chunks.get(&position).map_or(false, |cached| {
+                cached.is(&chunk) && cached.last_access() == last_access
+            });
+            if is_unused {
+                if let Some(cached) = chunks.remove(&position) {
+                    compressed_chunks.insert(position, bytes, cached.is_dirty());
+                }
+            }
+        }
+    }
+
+    async fn store_overflow(&self, overflow: Vec<(P, Arc<Vec<u8>>)>) {
+        for (position, bytes) in overflow {
+            let store_lock = Arc::clone(
+                self.store_locks
+                    .lock()
+                    .await
+                    .entry(position.clone())
+                    .or_default(),
+            );
+            let guard = store_lock.lock().await;
+            self.store_pending(&position, &bytes).await;
+            drop(guard);
+
+            let mut store_locks = self.store_locks.lock().await;
+            // Only the map and this store hold the lock, so nobody else is waiting for it
+            if Arc::strong_count(&store_lock) == 2 {
+                store_locks.remove(&position);
+            }
+        }
+    }
+
+    /// Writes a chunk that has left the cache to storage, unless a newer version of it has left
+    /// the cache since. The newer version is written by its own store instead
+    async fn store_pending(&self, position: &P, bytes: &Arc<Vec<u8>>) {
+        let is_pending = |pending_stores: &HashMap<P, Arc<Vec<u8>>>| {
+            pending_stores
+                .get(position)
+                .map_or(false, |pending| Arc::ptr_eq(pending, bytes))
+        };
+        if !is_pending(&*self.pending_stores.lock().await) {
+            return;
+        }
+
+        let result = self.storage.store(position, bytes.as_ref().clone()).await;
+
+        // Same lock order as when loading, so the chunk is never missing from every tier at once
+        let chunks = self.chunks.read().await;
+        let mut compressed_chunks = self.compressed_chunks.write().await;
+        let mut pending_stores = self.pending_stores.lock().await;
+        if !is_pending(&pending_stores) {
+            return;
+        }
+        pending_stores.remove(position);
+
+        if let Err(error) = result {
+            log::error!(
+                "Failed to move chunk to storage, keeping it compressed in memory: {}",
+                error
+            );
+            // Better to go over the budget than to lose chunks
+            if let Some(cached) = chunks.get(position) {
+                cached.mark_dirty();
+            } else if !compressed_chunks.mark_dirty(position) {
+                compressed_chunks.insert(position.clone(), bytes.as_ref().clone(), true);
+            }
+        }
+    }
+
+    /// Marks the chunk as dirty in whichever tier it currently lives in. If it has left the cache,
+    /// the given bytes are put back in to the compressed tier
+    async fn mark_dirty(&self, position: &P, bytes: Option<Vec<u8>>) {
+        let chunks = self.chunks.read().await;
+        if let Some(cached) = chunks.get(position) {
+            cached.mark_dirty();
+            return;
+        }
+
+        let mut compressed_chunks = self.compressed_chunks.write().await;
+        if !compressed_chunks.mark_dirty(position) {
+            if let Some(bytes) = bytes {
+                compressed_chunks.insert(position.clone(), bytes, true);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::chunk_cache::ChunkCache;
+    use crate::chunk_factory::MockChunkFactory;
+    use crate::chunk_storage::MockChunkStorage;
+    use crate::{BlockOffset, Chunk, ChunkFactory, ChunkStorage};
+    use std::collections::HashMap;
+    use std::error::Error;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::{Arc, Mutex};
+
+    /// The size of a chunk that has not been modified
+    fn chunk_byte_size() -> usize {
+        Chunk::<usize, 16>::default().byte_size()
+    }
+
+    #[derive(Debug, Default)]
+    struct TestStorage {
+        chunks: Mutex<HashMap<usize, Vec<u8>>>,
+        store_count: AtomicUsize,
+        /// Makes the first store slow, so that later stores get the chance to overtake it
+        first_store_yields: usize,
+    }
+
+    impl TestStorage {
+        fn store_count(&self) -> usize {
+            self.store_count.load(Ordering::SeqCst)
+        }
+
+        fn stored(&self, position: usize) -> Option<Vec<u8>> {
+            self.chunks.lock().unwrap().get(&position).cloned()
+        }
+    }
+
+    #[derive(Debug, Default)]
+    struct SlowFactory {
+        generated: AtomicUsize,
+    }
+
+    #[async_trait::async_trait]
+    impl ChunkFactory<usize> for SlowFactory {
+        type Chunk = Chunk<usize, 16>;
+
+        async fn generate_chunk(&self, _position: &usize) -> Self::Chunk {
+            self.generated.fetch_add(1, Ordering::SeqCst);
+            // Give everyone else the chance to ask for the same chunk
+            for _ in 0..10 {
+                tokio::task::yield_now().await;
+            }
+            Chunk::default()
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl ChunkStorage<usize> for TestStorage {
+        async fn store(
+            &self,
+            position: &usize,
+            bytes: Vec<u8>,
+        ) -> Result<(), Box<dyn Error + Send + Sync>> {
+            if self.store_count.fetch_add(1, Ordering::SeqCst) == 0 {
+                for _ in 0..self.first_store_yields {
+                    tokio::task::yield_now().await;
+                }
+            }
+            self.chunks.lock().unwrap().insert(*position, bytes);
+            Ok(())
+        }
+
+        async fn load(
+            &self,
+            position: &usize,
+        ) -> Result<Option<Vec<u8>>, Box<dyn Error + Send + Sync>> {
+            Ok(self.stored(*position))
+        }
+    }
+
+    fn create_factory(times: usize) -> MockChunkFactory<usize> {
+        let mut mock_factory = MockChunkFactory::<usize>::new();
+        mock_factory
+            .expect_generate_chunk()
+            .times(times)
+            .returning(|_| Chunk::default());
+        mock_factory
+    }
+
+    #[tokio::test]
+    async fn it_should_store_chunk() -> Result<(), Box<dyn Error + Send + Sync>> {
+        let mut mock_storage = MockChunkStorage::new();
+        mock_storage.expect_load().returning(|_| Ok(None));
+
+        let mut mock_factory = MockChunkFactory::<usize>::new();
+        mock_factory
+            .expect_generate_chunk()
+            .returning(|_| Chunk::default());
+
+        let cache = ChunkCache::new(
+            1_000_000,
+            1_000_000,
+            Arc::new(mock_storage),
+            Arc::new(mock_factory),
+        );
+
+        cache
+            .borrow_chunk(&1, |chunk| async move {
+                chunk.get(&BlockOffset::default());
+            })
+            .await?;
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn it_should_compress_least_recently_used_chunks(
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let mut mock_storage = MockChunkStorage::new();
+        mock_storage.expect_load().returning(|_| Ok(None));
+        mock_storage.expect_store().never();
+
+        let cache = ChunkCache::new(
+            chunk_byte_size() * 2,
+            1_000_000,
+            Arc::new(mock_storage),
+            Arc::new(create_factory(3)),
+        );
+
+        for position in [1, 2, 1, 3] {
+            cache.borrow_chunk(&position, |_| async move {}).await?;
+        }
+
+        assert_eq!(cache.in_memory_count().await, 2);
+        assert_eq!(cache.compressed_count().await, 1);
+
+        // Chunk 2 was the least recently used one, it should come back from the compressed tier
+        cache
+            .borrow_chunk(&2, |chunk| async move {
+                assert_eq!(*chunk.get(&BlockOffset::default()), 0);
+            })
+            .await?;
+
+        assert_eq!(cache.in_memory_count().await, 2);
+        assert_eq!(cache.compressed_count().await, 1);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn it_should_measure_chunks_by_their_contents() -> Result<(), Box<dyn Error + Send + Sync>>
+    {
+        let mut mock_storage = MockChunkStorage::new();
+        mock_storage.expect_load().returning(|_| Ok(None));
+
+        let cache = ChunkCache::new(
+            chunk_byte_size() * 2,
+            1_000_000,
+            Arc::new(mock_storage),
+            Arc::new(create_factory(2)),
+        );
+
+        cache
+            .borrow_chunk_mut(&1, |mut chunk| async move {
+                chunk.set(1, &BlockOffset::default());
+            })
+            .await?;
+        cache.borrow_chunk(&2, |_| async move {}).await?;
+
+        // Two unmodified chunks would have fit, but chunk 1 is no longer uniform
+        assert_eq!(cache.in_memory_count().await, 1);
+        assert_eq!(cache.compressed_count().await, 1);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn it_should_move_dirty_chunks_to_storage_when_over_budget(
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let mut mock_storage = MockChunkStorage::new();
+        mock_storage.expect_load().returning(|_| Ok(None));
+        mock_storage
+            .expect_store()
+            .withf(|position, bytes| *position == 1 && !bytes.is_empty())
+            .times(1)
+            .returning(|_, _| Ok(()));
+
+        let cache = ChunkCache::new(
+            chunk_byte_size(),
+            0,
+            Arc::new(mock_storage),
+            Arc::new(create_factory(2)),
+        );
+
+        cache
+            .borrow_chunk_mut(&1, |mut chunk| async move {
+                chunk.set(1, &BlockOffset::default());
+            })
+            .await?;
+        cache.borrow_chunk(&2, |_| async move {}).await?;
+
+        assert_eq!(cache.in_memory_count().await, 1);
+        assert_eq!(cache.compressed_count().await, 0);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn it_should_never_write_clean_chunks() -> Result<(), Box<dyn Error + Send + Sync>> {
+        let mut mock_storage = MockChunkStorage::new();
+        mock_storage.expect_load().returning(|_| Ok(None));
+        mock_storage.expect_store().never();
+
+        let cache = ChunkCache::new(
+            chunk_byte_size(),
+            0,
+            Arc::new(mock_storage),
+            Arc::new(create_factory(3)),
+        );
+
+        for position in [1, 2, 3] {
+            cache.borrow_chunk(&position, |_| async move {}).await?;
+        }
+        cache.flush_all().await?;
+
+        assert_eq!(cache.in_memory_count().await, 1);
+        assert_eq!(cache.compressed_count().await, 0);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn flush_all_should_write_dirty_chunks_once() -> Result<(), Box<dyn Error + Send + Sync>>
+    {
+        let storage = Arc::new(TestStorage::default());
+        let cache = ChunkCache::new(
+            chunk_byte_size(),
+            1_000_000,
+            Arc::clone(&storage),
+            Arc::new(create_factory(2)),
+        );
+
+        for position in [1, 2] {
+            cache
+                .borrow_chunk_mut(&position, |mut chunk| async move {
+                    chunk.set(position, &BlockOffset::default());
+                })
+                .await?;
+        }
+
+        // Chunk 1 has been compressed by now, chunk 2 is still in memory
+        assert_eq!(cache.compressed_count().await, 1);
+        assert_eq!(storage.store_count(), 0);
+
+        cache.flush_all().await?;
+        assert_eq!(storage.store_count(), 2);
+
+        cache.flush_all().await?;
+        assert_eq!(storage.store_count(), 2);
+
+        for position in [1, 2] {
+            let stored = storage.stored(position).unwrap();
+            let chunk = Chunk::<usize, 16>::from_compressed(&stored)?;
+            assert_eq!(*chunk.get(&BlockOffset::default()), position);
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn flush_all_should_not_lose_changes_of_a_pending_mutable_borrow(
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let storage = Arc::new(TestStorage::default());
+        let cache = ChunkCache::new(
+            1_000_000,
+            1_000_000,
+            Arc::clone(&storage),
+            Arc::new(create_factory(1)),
+        );
+        cache
+            .borrow_chunk_mut(&1, |mut chunk| async move {
+                chunk.set(1, &BlockOffset::default());
+            })
+            .await?;
+
+        // A reader keeps the writer waiting for the chunk while the flush starts
+        let reader = cache.acquire_chunk(&1).await?.read_owned().await;
+        let writer = cache.borrow_chunk_mut(&1, |mut chunk| async move {
+            chunk.set(2, &BlockOffset::default());
+        });
+        let flush = async {
+            tokio::task::yield_now().await;
+            cache.flush_all().await
+        };
+        let release = async {
+            for _ in 0..10 {
+                tokio::task::yield_now().await;
+            }
+            drop(reader);
+        };
+        let (written, flushed, _) = tokio::join!(writer, flush, release);
+        written?;
+        flushed?;
+
+        cache.flush_all().await?;
+        let chunk = Chunk::<usize, 16>::from_compressed(&storage.stored(1).unwrap())?;
+        assert_eq!(*chunk.get(&BlockOffset::default()), 2);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn it_should_load_written_back_chunks_from_storage(
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let storage = Arc::new(TestStorage::default());
+        let cache = ChunkCache::new(
+            chunk_byte_size(),
+            0,
+            Arc::clone(&storage),
+            Arc::new(create_factory(2)),
+        );
+
+        cache
+            .borrow_chunk_mut(&1, |mut chunk| async move {
+                chunk.set(42, &BlockOffset::default());
+            })
+            .await?;
+        cache.borrow_chunk(&2, |_| async move {}).await?;
+        assert_eq!(storage.store_count(), 1);
+
+        cache
+            .borrow_chunk(&1, |chunk| async move {
+                assert_eq!(*chunk.get(&BlockOffset::default()), 42);
+            })
+            .await?;
+
+        // Coming back from storage does not make the chunk dirty again
+        cache.flush_all().await?;
+        assert_eq!(storage.store_count(), 1);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn it_should_never_overwrite_a_chunk_with_an_older_version(
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let storage = Arc::new(TestStorage {
+            first_store_yields: 10,
+            ..TestStorage::default()
+        });
+        let cache = ChunkCache::new(
+            chunk_byte_size(),
+            0,
+            Arc::clone(&storage),
+            Arc::new(create_factory(3)),
+        );
+
+        // The first version is still being written when the second one leaves the cache
+        let first = async {
+            cache
+                .borrow_chunk_mut(&1, |mut chunk| async move {
+                    chunk.set(1, &BlockOffset::default());
+                })
+                .await?;
+            cache.borrow_chunk(&2, |_| async move {}).await
+        };
+        let second = async {
+            tokio::task::yield_now().await;
+            cache
+                .borrow_chunk_mut(&1, |mut chunk| async move {
+                    chunk.set(2, &BlockOffset::default());
+                })
+                .await?;
+            cache.borrow_chunk(&3, |_| async move {}).await
+        };
+        let (first, second) = tokio::join!(first, second);
+        first?;
+        second?;
+
+        let chunk = Chunk::<usize, 16>::from_compressed(&storage.stored(1).unwrap())?;
+        assert_eq!(*chunk.get(&BlockOffset::default()), 2);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn it_should_never_evict_borrowed_chunks() -> Result<(), Box<dyn Error + Send + Sync>> {
+        let mut mock_storage = MockChunkStorage::new();
+        mock_storage.expect_load().returning(|_| Ok(None));
+
+        let cache = ChunkCache::new(
+            chunk_byte_size(),
+            1_000_000,
+            Arc::new(mock_storage),
+            Arc::new(create_factory(4)),
+        );
+
+        let borrowed = cache.acquire_chunk(&1).await?;
+        cache.borrow_chunk(&2, |_| async move {}).await?;
+        cache.borrow_chunk(&3, |_| async move {}).await?;
+
+        // The cache is allowed to go over budget while chunks are borrowed
+        assert!(cache.chunks.read().await.contains_key(&1));
+        assert_eq!(cache.in_memory_count().await, 2);
+        assert_eq!(cache.compressed_count().await, 1);
+
+        drop(borrowed);
+        cache.borrow_chunk(&4, |_| async move {}).await?;
+
+        assert!(!cache.chunks.read().await.contains_key(&1));
+        assert_eq!(cache.in_memory_count().await, 1);
+        assert_eq!(cache.compressed_count().await, 3);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn concurrent_borrows_should_only_load_the_chunk_once(
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let factory = Arc::new(SlowFactory::default());
+        let cache = ChunkCache::new(
+            1_000_000,
+            1_000_000,
+            Arc::new(TestStorage::default()),
+            Arc::clone(&factory),
+        );
+
+        let (first, second, third) = tokio::join!(
+            cache.acquire_chunk(&1),
+            cache.acquire_chunk(&1),
+            cache.acquire_chunk(&1)
+        );
+        let (first, second, third) = (first?, second?, third?);
+
+        assert_eq!(factory.generated.load(Ordering::SeqCst), 1);
+        assert!(Arc::ptr_eq(&first, &second));
+        assert!(Arc::ptr_eq(&first, &third));
+        assert!(cache.loading.lock().unwrap().is_empty());
+
+        let (first, second) = tokio::join!(
+            cache.borrow_chunk(&2, |_| async move {}),
+            cache.borrow_chunk(&2, |_| async move {})
+        );
+        first?;
+        second?;
+
+        assert_eq!(factory.generated.load(Ordering::SeqCst), 2);
+        assert_eq!(cache.in_memory_count().await, 2);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,145 @@
+use std::collections::{BTreeMap, HashMap};
+use std::hash::Hash;
+
+#[derive(Debug)]
+struct CompressedChunk {
+    sequence: u64,
+    bytes: Vec<u8>,
+    is_dirty: bool,
+}
+
+/// The compressed tier of the cache. Keeps track of the order chunks was added in, so that the
+/// oldest ones can be moved out to storage once the tier grows too large
+#[derive(Debug)]
+pub struct CompressedChunks<P: Hash + Eq> {
+    entries: HashMap<P, CompressedChunk>,
+    order: BTreeMap<u64, P>,
+    next_sequence: u64,
+    byte_size: usize,
+}
+
+impl<P: Hash + Eq + Clone> CompressedChunks<P> {
+    pub fn new() -> Self {
+        Self {
+            entries: HashMap::new(),
+            order: BTreeMap::new(),
+            next_sequence: 0,
+            byte_size: 0,
+        }
+    }
+
+    /// The total amount of compressed bytes held by this tier
+    pub const fn byte_size(&self) -> usize {
+        self.byte_size
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn insert(&mut self, position: P, bytes: Vec<u8>, is_dirty: bool) {
+        self.remove(&position);
+
+        let sequence = self.next_sequence;
+        self.next_sequence += 1;
+
+        self.byte_size += bytes.len();
+        self.order.insert(sequence, position.clone());
+        self.entries.insert(
+            position,
+            CompressedChunk {
+                sequence,
+                bytes,
+                is_dirty,
+            },
+        );
+    }
+
+    /// Removes the entry, returning its bytes and whether it was dirty
+    pub fn remove(&mut self, position: &P) -> Option<(Vec<u8>, bool)> {
+        let entry = self.entries.remove(position)?;
+        self.order.remove(&entry.sequence);
+        self.byte_size -= entry.bytes.len();
+        Some((entry.bytes, entry.is_dirty))
+    }
+
+    /// Removes the entry that has been in this tier for the longest time
+    pub fn pop_oldest(&mut self) -> Option<(P, Vec<u8>, bool)> {
+        let sequence = *self.order.keys().next()?;
+        let position = self.order.remove(&sequence)?;
+        let entry = self.entries.remove(&position)?;
+        self.byte_size -= entry.bytes.len();
+        Some((position, entry.bytes, entry.is_dirty))
+    }
+
+    /// Returns `true` if the entry existed and could be marked as dirty
+    pub fn mark_dirty(&mut self, position: &P) -> bool {
+        self.entries
+            .get_mut(position)
+            .map(|entry| entry.is_dirty = true)
+            .is_some()
+    }
+
+    /// Marks every entry as clean, returning a copy of the ones that were dirty
+    pub fn take_dirty(&mut self) -> Vec<(P, Vec<u8>)> {
+        self.entries
+            .iter_mut()
+            .filter(|(_, entry)| entry.is_dirty)
+            .map(|(position, entry)| {
+                entry.is_dirty = false;
+                (position.clone(), entry.bytes.clone())
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::chunk_cache::compressed_chunks::CompressedChunks;
+
+    #[test]
+    fn it_should_keep_track_of_byte_size() {
+        let mut compressed = CompressedChunks::new();
+        compressed.insert(1, vec![0; 10], false);
+        compressed.insert(2, vec![0; 5], false);
+        assert_eq!(compressed.byte_size(), 15);
+
+        compressed.insert(1, vec![0; 2], false);
+        assert_eq!(compressed.byte_size(), 7);
+
+        compressed.remove(&2);
+        assert_eq!(compressed.byte_size(), 2);
+        assert_eq!(compressed.len(), 1);
+    }
+
+    #[test]
+    fn it_should_pop_the_oldest_entry_first() {
+        let mut compressed = CompressedChunks::new();
+        compressed.insert(1, vec![1], false);
+        compressed.insert(2, vec![2], true);
+        compressed.insert(3, vec![3], false);
+
+        // Re-inserting moves the entry to the back of the line
+        compressed.insert(1, vec![1], false);
+
+        assert_eq!(compressed.pop_oldest(), Some((2, vec![2], true)));
+        assert_eq!(compressed.pop_oldest(), Some((3, vec![3], false)));
+        assert_eq!(compressed.pop_oldest(), Some((1, vec![1], false)));
+        assert_eq!(compressed.pop_oldest(), None);
+        assert_eq!(compressed.byte_size(), 0);
+    }
+
+    #[test]
+    fn take_dirty_should_only_return_dirty_entries_once() {
+        let mut compressed = CompressedChunks::new();
+        compressed.insert(1, vec![1], true);
+        compressed.insert(2, vec![2], false);
+
+        assert_eq!(compressed.take_dirty(), vec![(1, vec![1])]);
+        assert!(compressed.take_dirty().is_empty());
+
+        assert!(compressed.mark_dirty(&2));
+        assert!(!compressed.mark_dirty(&3));
+        assert_eq!(compressed.take_dirty(), vec![(2, vec![2])]);
+    }
+}
@@ -0,0 +1,111 @@
+use std::collections::HashMap;
+use std::future::Future;
+use std::hash::Hash;
+use std::sync::{Arc, Mutex, PoisonError};
+use tokio::sync::OnceCell;
+
+pub type InFlightLoads<P, V> = Mutex<HashMap<P, Arc<OnceCell<V>>>>;
+
+/// A load that is shared by everyone asking for the same position at the same time.
+///
+/// Only one of the participants gets to run the load, the rest waits for its result. If the load
+/// fails, the next one in line makes an attempt of its own. The last participant to leave removes
+/// the load, which also happens if a participant is cancelled half way through.
+#[derive(Debug)]
+pub struct InFlightLoad<'a, P: Hash + Eq, V> {
+    loads: &'a InFlightLoads<P, V>,
+    position: P,
+    cell: Arc<OnceCell<V>>,
+}
+
+impl<'a, P: Hash + Eq + Clone, V> InFlightLoad<'a, P, V> {
+    pub fn join(loads: &'a InFlightLoads<P, V>, position: &P) -> Self {
+        let cell = {
+            let mut lock = loads.lock().unwrap_or_else(PoisonError::into_inner);
+            Arc::clone(
+                lock.entry(position.clone())
+                    .or_insert_with(|| Arc::new(OnceCell::new())),
+            )
+        };
+
+        Self {
+            loads,
+            position: position.clone(),
+            cell,
+        }
+    }
+
+    pub async fn get_or_try_init<E, F: Future<Output = Result<V, E>>>(
+        &self,
+        load: impl FnOnce() -> F,
+    ) -> Result<&V, E> {
+        self.cell.get_or_try_init(load).await
+    }
+}
+
+impl<'a, P: Hash + Eq, V> Drop for InFlightLoad<'a, P, V> {
+    fn drop(&mut self) {
+        let mut lock = self.loads.lock().unwrap_or_else(PoisonError::into_inner);
+        // One reference is held by the map, and one by us
+        if lock.get(&self.position).map_or(false, |cell| {
+            Arc::ptr_eq(cell, &self.cell) && Arc::strong_count(cell) == 2
+        }) {
+            lock.remove(&self.position);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::chunk_cache::in_flight_load::{InFlightLoad, InFlightLoads};
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+
+    #[tokio::test]
+    async fn participants_should_share_the_same_load() {
+        let loads: InFlightLoads<u32, u32> = Mutex::new(HashMap::new());
+
+        let first = InFlightLoad::join(&loads, &1);
+        let second = InFlightLoad::join(&loads, &1);
+
+        let value = first
+            .get_or_try_init(|| async { Result::<_, ()>::Ok(10) })
+            .await;
+        assert_eq!(value, Ok(&10));
+
+        let value = second
+            .get_or_try_init(|| async { Result::<_, ()>::Ok(20) })
+            .await;
+        assert_eq!(value, Ok(&10));
+    }
+
+    #[tokio::test]
+    async fn the_last_participant_should_remove_the_load() {
+        let loads: InFlightLoads<u32, u32> = Mutex::new(HashMap::new());
+
+        let first = InFlightLoad::join(&loads, &1);
+        let second = InFlightLoad::join(&loads, &1);
+
+        drop(first);
+        assert_eq!(loads.lock().unwrap().len(), 1);
+
+        drop(second);
+        assert!(loads.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn a_failed_load_should_be_retried_by_the_next_participant() {
+        let loads: InFlightLoads<u32, u32> = Mutex::new(HashMap::new());
+
+        let first = InFlightLoad::join(&loads, &1);
+        let second = InFlightLoad::join(&loads, &1);
+
+        let value = first.get_or_try_init(|| async { Err("failed") }).await;
+        assert_eq!(value, Err("failed"));
+
+        let value = second
+            .get_or_try_init(|| async { Result::<_, &str>::Ok(20) })
+            .await;
+        assert_eq!(value, Ok(&20));
+    }
+}
@@ -0,0 +1,6 @@
+mod cached_chunk;
+mod chunk_cache;
+mod compressed_chunks;
+mod in_flight_load;
+
+pub use self::chunk_cache::ChunkCache;
@@ -0,0 +1,154 @@
+use crate::chunk_format::{Codec, FormatVersion};
+use flate2::Crc;
+use std::error::Error;
+
+/// The header that every serialized chunk starts with.
+///
+/// All numbers are little endian:
+///
+/// | Bytes | Content                           |
+/// |-------|-----------------------------------|
+/// | 4     | The magic number `VXCH`           |
+/// | 2     | The format version                |
+/// | 1     | The codec used for the payload    |
+/// | 2     | The size of the chunk             |
+/// | 4     | The length of the payload         |
+/// | 4     | A CRC-32 checksum of the payload  |
+///
+/// The header is directly followed by the payload, which is the chunk encoded according to the
+/// format version and then compressed with the codec
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct ChunkHeader {
+    pub version: FormatVersion,
+    pub codec: Codec,
+    pub chunk_size: u16,
+    pub payload_len: u32,
+    pub checksum: u32,
+}
+
+impl ChunkHeader {
+    pub const MAGIC: [u8; 4] = *b"VXCH";
+    pub const BYTE_SIZE: usize = 17;
+
+    /// Creates a header describing the given payload
+    ///
+    /// # Errors
+    /// If the chunk size or the payload is too large to be described by a header
+    pub fn for_payload(
+        version: FormatVersion,
+        codec: Codec,
+        chunk_size: usize,
+        payload: &[u8],
+    ) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        Ok(Self {
+            version,
+            codec,
+            chunk_size: chunk_size.try_into()?,
+            payload_len: payload.len().try_into()?,
+            checksum: checksum(payload),
+        })
+    }
+
+    /// Whether the bytes starts with a header
+    pub fn is_present(bytes: &[u8]) -> bool {
+        bytes.starts_with(&Self::MAGIC)
+    }
+
+    pub fn write(&self, bytes: &mut Vec<u8>) {
+        bytes.extend_from_slice(&Self::MAGIC);
+        bytes.extend_from_slice(&self.version.id().to_le_bytes());
+        bytes.push(self.codec.id());
+        bytes.extend_from_slice(&self.chunk_size.to_le_bytes());
+        bytes.extend_from_slice(&self.payload_len.to_le_bytes());
+        bytes.extend_from_slice(&self.checksum.to_le_bytes());
+    }
+
+    /// Reads the header from the start of the bytes, returning it together with its payload. The
+    /// payload is checked against the checksum
+    ///
+    /// # Errors
+    /// If the header is missing, unknown or does not match the payload
+    pub fn read(bytes: &[u8]) -> Result<(Self, &[u8]), Box<dyn Error + Send + Sync>> {
+        if bytes.len() < Self::BYTE_SIZE {
+            return Err("Chunk is too short to contain a header".into());
+        }
+        if !Self::is_present(bytes) {
+            return Err("Chunk does not start with a header".into());
+        }
+
+        let header = Self {
+            version: FormatVersion::from_id(u16::from_le_bytes([bytes[4], bytes[5]]))?,
+            codec: Codec::from_id(bytes[6])?,
+            chunk_size: u16::from_le_bytes([bytes[7], bytes[8]]),
+            payload_len: u32::from_le_bytes([bytes[9], bytes[10], bytes[11], bytes[12]]),
+            checksum: u32::from_le_bytes([bytes[13], bytes[14], bytes[15], bytes[16]]),
+        };
+
+        let payload = &bytes[Self::BYTE_SIZE..];
+        if payload.len() != header.payload_len as usize {
+            return Err(format!(
+                "Expected a chunk payload of {} bytes, found {}",
+                header.payload_len,
+                payload.len()
+            )
+            .into());
+        }
+        if checksum(payload) != header.checksum {
+            return Err("Chunk checksum does not match its payload".into());
+        }
+
+        Ok((header, payload))
+    }
+}
+
+fn checksum(payload: &[u8]) -> u32 {
+    let mut crc = Crc::new();
+    crc.update(payload);
+    crc.sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::chunk_format::{ChunkHeader, Codec, FormatVersion};
+
+    #[test]
+    fn it_should_read_what_it_writes() {
+        let payload = [1, 2, 3, 4];
+        let header =
+            ChunkHeader::for_payload(FormatVersion::Palette, Codec::Gzip, 32, &payload).unwrap();
+
+        let mut bytes = vec![];
+        header.write(&mut bytes);
+        assert_eq!(bytes.len(), ChunkHeader::BYTE_SIZE);
+        bytes.extend_from_slice(&payload);
+
+        let (read_header, read_payload) = ChunkHeader::read(&bytes).unwrap();
+        assert_eq!(read_header, header);
+        assert_eq!(read_payload, payload);
+    }
+
+    #[test]
+    fn it_should_detect_corrupted_payloads() {
+        let payload = [1, 2, 3, 4];
+        let header =
+            ChunkHeader::for_payload(FormatVersion::Palette, Codec::None, 32, &payload).unwrap();
+
+        let mut bytes = vec![];
+        header.write(&mut bytes);
+        bytes.extend_from_slice(&[1, 2, 3, 5]);
+
+        assert!(ChunkHeader::read(&bytes).is_err());
+        assert!(ChunkHeader::read(&bytes[..bytes.len() - 1]).is_err());
+    }
+
+    #[test]
+    fn it_should_reject_unknown_versions() {
+        let mut bytes = vec![];
+        ChunkHeader::for_payload(FormatVersion::Palette, Codec::None, 32, &[])
+            .unwrap()
+            .write(&mut bytes);
+        bytes[4] = 99;
+
+        assert!(ChunkHeader::read(&bytes).is_err());
+    }
+}
@@ -0,0 +1,75 @@
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use std::error::Error;
+use std::io::{Read, Write};
+
+/// How the payload of a serialized chunk is compressed
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum Codec {
+    /// The payload is stored as is
+    None,
+    Gzip,
+}
+
+impl Codec {
+    pub const fn id(self) -> u8 {
+        match self {
+            Self::None => 0,
+            Self::Gzip => 1,
+        }
+    }
+
+    /// # Errors
+    /// If the id does not belong to any known codec
+    pub fn from_id(id: u8) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        match id {
+            0 => Ok(Self::None),
+            1 => Ok(Self::Gzip),
+            _ => Err(format!("Unknown chunk codec {}", id).into()),
+        }
+    }
+
+    /// # Errors
+    /// If the bytes could not be compressed
+    pub fn compress(self, bytes: Vec<u8>) -> Result<Vec<u8>, Box<dyn Error + Send + Sync>> {
+        match self {
+            Self::None => Ok(bytes),
+            Self::Gzip => {
+                let mut encoder = GzEncoder::new(vec![], Compression::best());
+                encoder.write_all(&bytes)?;
+                Ok(encoder.finish()?)
+            }
+        }
+    }
+
+    /// Decompresses the bytes, which are not allowed to grow past `max_size`
+    ///
+    /// # Errors
+    /// If the bytes are not valid for this codec, or decompress to more than `max_size` bytes
+    pub fn decompress(
+        self,
+        bytes: &[u8],
+        max_size: usize,
+    ) -> Result<Vec<u8>, Box<dyn Error + Send + Sync>> {
+        let decompressed = match self {
+            Self::None => bytes.to_vec(),
+            Self::Gzip => {
+                // Reading one byte more than allowed tells a payload that is too large apart
+                let mut decoder = GzDecoder::new(bytes).take(max_size as u64 + 1);
+                let mut decompressed = vec![];
+                decoder.read_to_end(&mut decompressed)?;
+                decompressed
+            }
+        };
+
+        if decompressed.len() > max_size {
+            return Err(format!(
+                "Payload decompresses to more than the allowed {} bytes",
+                max_size
+            )
+            .into());
+        }
+        Ok(decompressed)
+    }
+}
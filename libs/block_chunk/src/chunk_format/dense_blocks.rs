@@ -0,0 +1,50 @@
+use bincode::de::{BorrowDecoder, Decoder};
+use bincode::enc::Encoder;
+use bincode::error::{DecodeError, EncodeError};
+use bincode::{BorrowDecode, Decode, Encode};
+
+/// The blocks of a chunk in the dense layout, one after the other ordered by z, y and then x. This
+/// is the layout chunks were stored in before they got a palette
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct DenseBlocks<T, const SIZE: usize>(pub Vec<T>);
+
+impl<T, const SIZE: usize> DenseBlocks<T, SIZE> {
+    const BLOCK_COUNT: usize = SIZE * SIZE * SIZE;
+}
+
+impl<T: Encode, const SIZE: usize> Encode for DenseBlocks<T, SIZE> {
+    fn encode<E: Encoder>(&self, encoder: &mut E) -> Result<(), EncodeError> {
+        if self.0.len() != Self::BLOCK_COUNT {
+            return Err(EncodeError::OtherString(format!(
+                "Expected {} blocks, found {}",
+                Self::BLOCK_COUNT,
+                self.0.len()
+            )));
+        }
+
+        for block in &self.0 {
+            block.encode(encoder)?;
+        }
+        Ok(())
+    }
+}
+
+impl<T: Decode, const SIZE: usize> Decode for DenseBlocks<T, SIZE> {
+    fn decode<D: Decoder>(decoder: &mut D) -> Result<Self, DecodeError> {
+        let mut blocks = Vec::with_capacity(Self::BLOCK_COUNT);
+        for _ in 0..Self::BLOCK_COUNT {
+            blocks.push(T::decode(decoder)?);
+        }
+        Ok(Self(blocks))
+    }
+}
+
+impl<'de, T: BorrowDecode<'de>, const SIZE: usize> BorrowDecode<'de> for DenseBlocks<T, SIZE> {
+    fn borrow_decode<D: BorrowDecoder<'de>>(decoder: &mut D) -> Result<Self, DecodeError> {
+        let mut blocks = Vec::with_capacity(Self::BLOCK_COUNT);
+        for _ in 0..Self::BLOCK_COUNT {
+            blocks.push(T::borrow_decode(decoder)?);
+        }
+        Ok(Self(blocks))
+    }
+}
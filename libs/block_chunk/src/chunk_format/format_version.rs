@@ -0,0 +1,33 @@
+use std::error::Error;
+
+/// The layout of the blocks within a serialized chunk
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, PartialOrd, Ord)]
+pub enum FormatVersion {
+    /// Every block stored one after the other, ordered by z, y and then x
+    Dense,
+    /// A palette of the distinct blocks, with a bit packed index in to the palette for every block
+    Palette,
+}
+
+impl FormatVersion {
+    /// The version new chunks are written in
+    pub const CURRENT: Self = Self::Palette;
+
+    pub const fn id(self) -> u16 {
+        match self {
+            Self::Dense => 1,
+            Self::Palette => 2,
+        }
+    }
+
+    /// # Errors
+    /// If the id does not belong to any known version, which most likely means the chunk was
+    /// written by a newer version of the game
+    pub fn from_id(id: u16) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        match id {
+            1 => Ok(Self::Dense),
+            2 => Ok(Self::Palette),
+            _ => Err(format!("Unsupported chunk format version {}", id).into()),
+        }
+    }
+}
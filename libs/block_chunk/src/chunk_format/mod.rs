@@ -0,0 +1,9 @@
+mod chunk_header;
+mod codec;
+mod dense_blocks;
+mod format_version;
+
+pub use self::chunk_header::ChunkHeader;
+pub use self::codec::Codec;
+pub use self::dense_blocks::DenseBlocks;
+pub use self::format_version::FormatVersion;
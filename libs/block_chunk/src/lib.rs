@@ -17,13 +17,16 @@ pub use self::block_offset::BlockOffset;
 pub use self::chunk::Chunk;
 pub use self::chunk_cache::ChunkCache;
 pub use self::chunk_factory::ChunkFactory;
+pub use self::chunk_format::{Codec, FormatVersion};
 pub use self::chunk_storage::ChunkStorage;
 
 mod block_offset;
 mod chunk;
 mod chunk_cache;
 mod chunk_factory;
+mod chunk_format;
 mod chunk_storage;
+mod palette;
 
 #[cfg(feature = "mesh")]
 pub mod mesh;
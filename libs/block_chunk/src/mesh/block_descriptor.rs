@@ -1,5 +1,15 @@
+use crate::mesh::BlockShape;
+
 #[derive(Debug, Clone)]
 pub struct BlockDescriptor {
-    pub is_standard_square: bool,
+    pub shape: BlockShape,
     pub is_transparent: bool,
 }
+
+impl BlockDescriptor {
+    /// Whether the block fills its whole space, and is meshed like a regular cube
+    #[must_use]
+    pub const fn is_standard_square(&self) -> bool {
+        self.shape.is_cube()
+    }
+}
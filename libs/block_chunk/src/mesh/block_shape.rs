@@ -0,0 +1,100 @@
+use crate::mesh::{FaceDirection, ShapeBox};
+
+/// The geometry of a block
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum BlockShape {
+    /// A full block, which is meshed together with the other full blocks
+    #[default]
+    Cube,
+
+    /// Any number of boxes inside of the block. The boxes should not overlap each other
+    Boxes(&'static [ShapeBox]),
+
+    /// Two planes crossing each other diagonally, used for plants and the like
+    Cross,
+
+    /// A thin post in the middle of the block, with rails to the fences and full blocks next to it
+    Fence,
+}
+
+impl BlockShape {
+    /// The lower half of a block
+    pub const SLAB: Self = Self::Boxes(&[ShapeBox::new([0.0; 3], [1.0, 0.5, 1.0])]);
+
+    /// The upper half of a block
+    pub const TOP_SLAB: Self = Self::Boxes(&[ShapeBox::new([0.0, 0.5, 0.0], [1.0; 3])]);
+
+    /// Stairs going up towards the north
+    pub const STAIRS_NORTH: Self = Self::Boxes(&[
+        ShapeBox::new([0.0, 0.0, 0.5], [1.0, 0.5, 1.0]),
+        ShapeBox::new([0.0; 3], [1.0, 1.0, 0.5]),
+    ]);
+
+    /// Stairs going up towards the south
+    pub const STAIRS_SOUTH: Self = Self::Boxes(&[
+        ShapeBox::new([0.0; 3], [1.0, 0.5, 0.5]),
+        ShapeBox::new([0.0, 0.0, 0.5], [1.0; 3]),
+    ]);
+
+    /// Stairs going up towards the west
+    pub const STAIRS_WEST: Self = Self::Boxes(&[
+        ShapeBox::new([0.5, 0.0, 0.0], [1.0, 0.5, 1.0]),
+        ShapeBox::new([0.0; 3], [0.5, 1.0, 1.0]),
+    ]);
+
+    /// Stairs going up towards the east
+    pub const STAIRS_EAST: Self = Self::Boxes(&[
+        ShapeBox::new([0.0; 3], [0.5, 0.5, 1.0]),
+        ShapeBox::new([0.5, 0.0, 0.0], [1.0; 3]),
+    ]);
+
+    /// The post of a [`BlockShape::Fence`]
+    pub const FENCE_POST: ShapeBox = ShapeBox::new([0.375, 0.0, 0.375], [0.625, 1.0, 0.625]);
+
+    /// Stairs going up towards the given side of the block. As there is no way to go up or down
+    /// stairs vertically, those directions give stairs going up towards the south
+    #[must_use]
+    pub const fn stairs(direction: FaceDirection) -> Self {
+        match direction {
+            FaceDirection::North => Self::STAIRS_NORTH,
+            FaceDirection::West => Self::STAIRS_WEST,
+            FaceDirection::East => Self::STAIRS_EAST,
+            FaceDirection::South | FaceDirection::Up | FaceDirection::Down => Self::STAIRS_SOUTH,
+        }
+    }
+
+    /// The two rails going from the post of a [`BlockShape::Fence`] to the given side of the
+    /// block. Vertical directions have no rails
+    #[must_use]
+    pub const fn fence_rails(direction: FaceDirection) -> Option<[ShapeBox; 2]> {
+        let (min_x, max_x, min_z, max_z) = match direction {
+            FaceDirection::North => (0.4375, 0.5625, 0.0, 0.375),
+            FaceDirection::South => (0.4375, 0.5625, 0.625, 1.0),
+            FaceDirection::West => (0.0, 0.375, 0.4375, 0.5625),
+            FaceDirection::East => (0.625, 1.0, 0.4375, 0.5625),
+            FaceDirection::Up | FaceDirection::Down => return None,
+        };
+
+        Some([
+            ShapeBox::new([min_x, 0.375, min_z], [max_x, 0.5625, max_z]),
+            ShapeBox::new([min_x, 0.75, min_z], [max_x, 0.9375, max_z]),
+        ])
+    }
+
+    /// The parts of the block that entities collide with. Plants can be walked through, and fences
+    /// only collide with their post
+    #[must_use]
+    pub const fn collision_boxes(&self) -> &'static [ShapeBox] {
+        match *self {
+            Self::Cube => &[ShapeBox::FULL],
+            Self::Boxes(boxes) => boxes,
+            Self::Cross => &[],
+            Self::Fence => &[Self::FENCE_POST],
+        }
+    }
+
+    #[must_use]
+    pub const fn is_cube(&self) -> bool {
+        matches!(self, Self::Cube)
+    }
+}
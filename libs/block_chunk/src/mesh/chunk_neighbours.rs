@@ -0,0 +1,239 @@
+use crate::mesh::FaceDirection;
+use crate::{BlockOffset, Chunk};
+
+/// The blocks just outside of a chunk, used to cull the faces on the edges of the chunk.
+///
+/// Only the plane of blocks that touches the chunk is copied from each neighbour, so the
+/// neighbouring chunks don't have to be kept locked while meshing. Neighbours that are missing are
+/// treated as air, which means the faces towards them are always created
+#[derive(Debug, Clone)]
+pub struct ChunkNeighbours<T, const SIZE: usize> {
+    north: Option<Vec<T>>,
+    south: Option<Vec<T>>,
+    west: Option<Vec<T>>,
+    east: Option<Vec<T>>,
+    up: Option<Vec<T>>,
+    down: Option<Vec<T>>,
+}
+
+impl<T, const SIZE: usize> ChunkNeighbours<T, SIZE> {
+    /// Creates an empty set of neighbours, where every neighbour is treated as air
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            north: None,
+            south: None,
+            west: None,
+            east: None,
+            up: None,
+            down: None,
+        }
+    }
+
+    /// The block in the neighbouring chunk that touches the given position. The position has to
+    /// be on the edge of the chunk facing the given direction
+    #[must_use]
+    pub fn get(&self, direction: FaceDirection, position: &BlockOffset<SIZE>) -> Option<&T> {
+        let plane = self.plane(direction).as_ref()?;
+        plane.get(Self::plane_index(direction, position))
+    }
+
+    /// The block next to the given position in the given direction, which might be in the
+    /// neighbouring chunk
+    #[must_use]
+    pub fn block_next_to<'a>(
+        &'a self,
+        chunk: &'a Chunk<T, SIZE>,
+        position: &BlockOffset<SIZE>,
+        direction: FaceDirection,
+    ) -> Option<&'a T>
+    where
+        T: 'static + Send + Sync,
+    {
+        let neighbour_position = match direction {
+            FaceDirection::North => position.north(),
+            FaceDirection::South => position.south(),
+            FaceDirection::West => position.west(),
+            FaceDirection::East => position.east(),
+            FaceDirection::Up => position.up(),
+            FaceDirection::Down => position.down(),
+        };
+
+        match neighbour_position {
+            Some(neighbour_position) => Some(chunk.get(&neighbour_position)),
+            None => self.get(direction, position),
+        }
+    }
+
+    /// The block at the given coordinates, relative to the chunk. Coordinates just outside of the
+    /// chunk are looked up in the neighbours, while the blocks in the chunks diagonal to us are
+    /// unknown
+    #[must_use]
+    pub fn block_at<'a>(
+        &'a self,
+        chunk: &'a Chunk<T, SIZE>,
+        x: isize,
+        y: isize,
+        z: isize,
+    ) -> Option<&'a T>
+    where
+        T: 'static + Send + Sync,
+    {
+        let to_offset = |value: isize| usize::try_from(value).ok().filter(|value| *value < SIZE);
+
+        match (to_offset(x), to_offset(y), to_offset(z)) {
+            (Some(x), Some(y), Some(z)) => Some(chunk.get(&BlockOffset { x, y, z })),
+            (None, Some(y), Some(z)) if x == -1 => {
+                self.get(FaceDirection::West, &BlockOffset { x: 0, y, z })
+            }
+            (None, Some(y), Some(z)) if x == SIZE as isize => {
+                self.get(FaceDirection::East, &BlockOffset { x: SIZE - 1, y, z })
+            }
+            (Some(x), None, Some(z)) if y == -1 => {
+                self.get(FaceDirection::Down, &BlockOffset { x, y: 0, z })
+            }
+            (Some(x), None, Some(z)) if y == SIZE as isize => {
+                self.get(FaceDirection::Up, &BlockOffset { x, y: SIZE - 1, z })
+            }
+            (Some(x), Some(y), None) if z == -1 => {
+                self.get(FaceDirection::North, &BlockOffset { x, y, z: 0 })
+            }
+            (Some(x), Some(y), None) if z == SIZE as isize => {
+                self.get(FaceDirection::South, &BlockOffset { x, y, z: SIZE - 1 })
+            }
+            _ => None,
+        }
+    }
+
+    const fn plane(&self, direction: FaceDirection) -> &Option<Vec<T>> {
+        match direction {
+            FaceDirection::North => &self.north,
+            FaceDirection::South => &self.south,
+            FaceDirection::West => &self.west,
+            FaceDirection::East => &self.east,
+            FaceDirection::Up => &self.up,
+            FaceDirection::Down => &self.down,
+        }
+    }
+
+    /// Planes are indexed by the two coordinates that changes along the plane
+    const fn plane_index(direction: FaceDirection, position: &BlockOffset<SIZE>) -> usize {
+        match direction {
+            FaceDirection::North | FaceDirection::South => position.y * SIZE + position.x,
+            FaceDirection::West | FaceDirection::East => position.y * SIZE + position.z,
+            FaceDirection::Up | FaceDirection::Down => position.z * SIZE + position.x,
+        }
+    }
+}
+
+impl<T: 'static + Send + Sync + Clone, const SIZE: usize> ChunkNeighbours<T, SIZE> {
+    /// Copies the blocks that touches us from the chunk lying in the given direction
+    pub fn insert(&mut self, direction: FaceDirection, chunk: &Chunk<T, SIZE>) {
+        let mut plane = Vec::with_capacity(SIZE * SIZE);
+        for a in 0..SIZE {
+            for b in 0..SIZE {
+                // The position in the neighbour, on the side that faces back towards us
+                let position = match direction {
+                    FaceDirection::North => BlockOffset {
+                        x: b,
+                        y: a,
+                        z: SIZE - 1,
+                    },
+                    FaceDirection::South => BlockOffset { x: b, y: a, z: 0 },
+                    FaceDirection::West => BlockOffset {
+                        x: SIZE - 1,
+                        y: a,
+                        z: b,
+                    },
+                    FaceDirection::East => BlockOffset { x: 0, y: a, z: b },
+                    FaceDirection::Up => BlockOffset { x: b, y: 0, z: a },
+                    FaceDirection::Down => BlockOffset {
+                        x: b,
+                        y: SIZE - 1,
+                        z: a,
+                    },
+                };
+                plane.push(chunk.get(&position).clone());
+            }
+        }
+
+        match direction {
+            FaceDirection::North => self.north = Some(plane),
+            FaceDirection::South => self.south = Some(plane),
+            FaceDirection::West => self.west = Some(plane),
+            FaceDirection::East => self.east = Some(plane),
+            FaceDirection::Up => self.up = Some(plane),
+            FaceDirection::Down => self.down = Some(plane),
+        }
+    }
+}
+
+impl<T, const SIZE: usize> Default for ChunkNeighbours<T, SIZE> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::mesh::{ChunkNeighbours, FaceDirection};
+    use crate::{BlockOffset, Chunk};
+
+    #[test]
+    fn it_should_look_across_the_edge_of_the_chunk() {
+        let chunk = Chunk::<u32, 4>::default();
+        let mut north = Chunk::<u32, 4>::default();
+        north.set(1, &(2, 3, 3).into());
+
+        let mut neighbours = ChunkNeighbours::new();
+        neighbours.insert(FaceDirection::North, &north);
+
+        let position: BlockOffset<4> = (2, 3, 0).into();
+        assert_eq!(
+            neighbours.block_next_to(&chunk, &position, FaceDirection::North),
+            Some(&1)
+        );
+        assert_eq!(
+            neighbours.block_next_to(&chunk, &position, FaceDirection::South),
+            Some(&0)
+        );
+        // There is no chunk above us
+        assert_eq!(
+            neighbours.block_next_to(&chunk, &position, FaceDirection::Up),
+            None
+        );
+    }
+
+    #[test]
+    fn it_should_copy_the_plane_facing_us() {
+        let mut neighbour = Chunk::<u32, 4>::default();
+        neighbour.set(1, &(0, 1, 2).into());
+        neighbour.set(2, &(3, 1, 2).into());
+
+        let mut neighbours = ChunkNeighbours::new();
+        neighbours.insert(FaceDirection::East, &neighbour);
+        neighbours.insert(FaceDirection::West, &neighbour);
+
+        let position: BlockOffset<4> = (3, 1, 2).into();
+        assert_eq!(neighbours.get(FaceDirection::East, &position), Some(&1));
+        let position: BlockOffset<4> = (0, 1, 2).into();
+        assert_eq!(neighbours.get(FaceDirection::West, &position), Some(&2));
+    }
+
+    #[test]
+    fn block_at_should_only_know_about_direct_neighbours() {
+        let mut chunk = Chunk::<u32, 4>::default();
+        chunk.set(1, &(3, 0, 0).into());
+        let mut neighbour = Chunk::<u32, 4>::default();
+        neighbour.set(2, &(0, 0, 0).into());
+
+        let mut neighbours = ChunkNeighbours::new();
+        neighbours.insert(FaceDirection::East, &neighbour);
+
+        assert_eq!(neighbours.block_at(&chunk, 3, 0, 0), Some(&1));
+        assert_eq!(neighbours.block_at(&chunk, 4, 0, 0), Some(&2));
+        assert_eq!(neighbours.block_at(&chunk, -1, 0, 0), None);
+        assert_eq!(neighbours.block_at(&chunk, 4, -1, 0), None);
+        assert_eq!(neighbours.block_at(&chunk, 5, 0, 0), None);
+    }
+}
@@ -19,9 +19,17 @@ pub struct Face<TE, const SIZE: usize> {
     pub texture: TE,
 
     pub is_transparent: bool,
+
+    /// How much light reaches each corner of the face, from 0 when fully occluded to 3 when not
+    /// occluded at all. The corners are ordered `(0, 0)`, `(0, height)`, `(width, height)` and
+    /// `(width, 0)`, along the axes the width and height of the face are measured in
+    pub ambient_occlusion: [u8; 4],
 }
 
 impl<TE: Clone + PartialEq, const SIZE: usize> Face<TE, SIZE> {
+    /// The ambient occlusion of a face that nothing is blocking the light for
+    pub const NO_OCCLUSION: [u8; 4] = [3; 4];
+
     pub fn north(position: &BlockOffset<SIZE>, texture: &TE, is_transparent: bool) -> Self {
         Self {
             direction: FaceDirection::North,
@@ -30,6 +38,7 @@ impl<TE: Clone + PartialEq, const SIZE: usize> Face<TE, SIZE> {
             height: 1,
             texture: texture.clone(),
             is_transparent,
+            ambient_occlusion: Self::NO_OCCLUSION,
         }
     }
 
@@ -41,6 +50,7 @@ impl<TE: Clone + PartialEq, const SIZE: usize> Face<TE, SIZE> {
             height: 1,
             texture: texture.clone(),
             is_transparent,
+            ambient_occlusion: Self::NO_OCCLUSION,
         }
     }
 
@@ -52,6 +62,7 @@ impl<TE: Clone + PartialEq, const SIZE: usize> Face<TE, SIZE> {
             height: 1,
             texture: texture.clone(),
             is_transparent,
+            ambient_occlusion: Self::NO_OCCLUSION,
         }
     }
 
@@ -63,6 +74,7 @@ impl<TE: Clone + PartialEq, const SIZE: usize> Face<TE, SIZE> {
             height: 1,
             texture: texture.clone(),
             is_transparent,
+            ambient_occlusion: Self::NO_OCCLUSION,
         }
     }
 
@@ -74,6 +86,7 @@ impl<TE: Clone + PartialEq, const SIZE: usize> Face<TE, SIZE> {
             height: 1,
             texture: texture.clone(),
             is_transparent,
+            ambient_occlusion: Self::NO_OCCLUSION,
         }
     }
 
@@ -85,9 +98,16 @@ impl<TE: Clone + PartialEq, const SIZE: usize> Face<TE, SIZE> {
             height: 1,
             texture: texture.clone(),
             is_transparent,
+            ambient_occlusion: Self::NO_OCCLUSION,
         }
     }
 
+    #[must_use]
+    pub const fn with_ambient_occlusion(mut self, ambient_occlusion: [u8; 4]) -> Self {
+        self.ambient_occlusion = ambient_occlusion;
+        self
+    }
+
     #[must_use]
     pub fn is_single_block(&self) -> bool {
         self.is_single_height() && self.is_single_width()
@@ -132,6 +152,7 @@ impl<TE: Clone + PartialEq, const SIZE: usize> Face<TE, SIZE> {
             && other.position.eq(&expected_position)
             && other.direction == self.direction
             && other.texture == self.texture
+            && other.ambient_occlusion == self.ambient_occlusion
     }
 
     #[must_use]
@@ -162,6 +183,7 @@ impl<TE: Clone + PartialEq, const SIZE: usize> Face<TE, SIZE> {
             && self.width == other.width
             && other.direction == self.direction
             && other.texture == self.texture
+            && other.ambient_occlusion == self.ambient_occlusion
     }
 
     #[inline]
@@ -192,4 +214,16 @@ mod tests {
 
         assert!(face_1.can_merge_row(&face_2))
     }
+
+    #[test]
+    fn it_should_not_merge_faces_with_different_ambient_occlusion() {
+        let face_1 = Face::north(&BlockOffset::<16> { x: 0, y: 0, z: 0 }, &0, false);
+        let face_2 = Face::north(&BlockOffset::<16> { x: 1, y: 0, z: 0 }, &0, false)
+            .with_ambient_occlusion([3, 3, 2, 2]);
+        let face_3 = Face::north(&BlockOffset::<16> { x: 0, y: 1, z: 0 }, &0, false)
+            .with_ambient_occlusion([2, 2, 3, 3]);
+
+        assert!(!face_1.can_merge_row(&face_2));
+        assert!(!face_1.can_merge_column(&face_3));
+    }
 }
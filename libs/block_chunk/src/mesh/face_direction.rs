@@ -7,3 +7,14 @@ pub enum FaceDirection {
     Up,
     Down,
 }
+
+impl FaceDirection {
+    pub const ALL: [Self; 6] = [
+        Self::North,
+        Self::South,
+        Self::West,
+        Self::East,
+        Self::Up,
+        Self::Down,
+    ];
+}
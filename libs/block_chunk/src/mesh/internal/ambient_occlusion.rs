@@ -0,0 +1,130 @@
+use crate::mesh::{BlockDescriptor, ChunkNeighbours, FaceDirection};
+use crate::{BlockOffset, Chunk};
+
+/// Calculates how much light reaches each corner of the face of the given block, by looking at
+/// the blocks around the corner in front of the face. The corners are ordered the same way as
+/// [`Face::ambient_occlusion`](crate::mesh::Face::ambient_occlusion)
+pub fn ambient_occlusion<
+    T: 'static + Send + Sync,
+    C: Fn(&T) -> Option<BlockDescriptor>,
+    const SIZE: usize,
+>(
+    chunk: &Chunk<T, SIZE>,
+    neighbours: &ChunkNeighbours<T, SIZE>,
+    describe_callback: &C,
+    position: &BlockOffset<SIZE>,
+    direction: FaceDirection,
+) -> [u8; 4] {
+    // The direction the face is facing, and the axes its width and height are measured along
+    let (normal, width_axis, height_axis) = match direction {
+        FaceDirection::North => ([0, 0, -1], [1, 0, 0], [0, 1, 0]),
+        FaceDirection::South => ([0, 0, 1], [1, 0, 0], [0, 1, 0]),
+        FaceDirection::West => ([-1, 0, 0], [0, 0, 1], [0, 1, 0]),
+        FaceDirection::East => ([1, 0, 0], [0, 0, 1], [0, 1, 0]),
+        FaceDirection::Up => ([0, 1, 0], [1, 0, 0], [0, 0, 1]),
+        FaceDirection::Down => ([0, -1, 0], [1, 0, 0], [0, 0, 1]),
+    };
+    let front = [
+        position.x as isize + normal[0],
+        position.y as isize + normal[1],
+        position.z as isize + normal[2],
+    ];
+
+    let is_occluding = |width_side: isize, height_side: isize| {
+        let block = neighbours.block_at(
+            chunk,
+            front[0] + width_axis[0] * width_side + height_axis[0] * height_side,
+            front[1] + width_axis[1] * width_side + height_axis[1] * height_side,
+            front[2] + width_axis[2] * width_side + height_axis[2] * height_side,
+        );
+        block
+            .and_then(describe_callback)
+            .map_or(false, |descriptor| {
+                descriptor.is_standard_square() && !descriptor.is_transparent
+            })
+    };
+
+    let corner = |width_side: isize, height_side: isize| {
+        let side_1 = is_occluding(width_side, 0);
+        let side_2 = is_occluding(0, height_side);
+        if side_1 && side_2 {
+            // The corner is completely hidden, no matter what's in the corner itself
+            0
+        } else {
+            3 - u8::from(side_1)
+                - u8::from(side_2)
+                - u8::from(is_occluding(width_side, height_side))
+        }
+    };
+
+    [corner(-1, -1), corner(-1, 1), corner(1, 1), corner(1, -1)]
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::mesh::internal::ambient_occlusion::ambient_occlusion;
+    use crate::mesh::{BlockDescriptor, BlockShape, ChunkNeighbours, FaceDirection};
+    use crate::Chunk;
+
+    fn describe(block: &u32) -> Option<BlockDescriptor> {
+        (*block == 1).then(|| BlockDescriptor {
+            shape: BlockShape::Cube,
+            is_transparent: false,
+        })
+    }
+
+    #[test]
+    fn it_should_not_occlude_faces_in_the_open() {
+        let mut chunk = Chunk::<u32, 4>::default();
+        chunk.set(1, &(1, 1, 1).into());
+
+        let result = ambient_occlusion(
+            &chunk,
+            &ChunkNeighbours::new(),
+            &describe,
+            &(1, 1, 1).into(),
+            FaceDirection::Up,
+        );
+
+        assert_eq!(result, [3, 3, 3, 3]);
+    }
+
+    #[test]
+    fn it_should_occlude_corners_next_to_walls() {
+        let mut chunk = Chunk::<u32, 4>::default();
+        chunk.set(1, &(1, 1, 1).into());
+        // A wall on the west side, one block up
+        for z in 0..4 {
+            chunk.set(1, &(0, 2, z).into());
+        }
+
+        let result = ambient_occlusion(
+            &chunk,
+            &ChunkNeighbours::new(),
+            &describe,
+            &(1, 1, 1).into(),
+            FaceDirection::Up,
+        );
+
+        // The corners on the west side are next to the wall, and has the wall in the corner too
+        assert_eq!(result, [1, 1, 3, 3]);
+    }
+
+    #[test]
+    fn it_should_fully_occlude_corners_between_two_sides() {
+        let mut chunk = Chunk::<u32, 4>::default();
+        chunk.set(1, &(1, 1, 1).into());
+        chunk.set(1, &(0, 2, 1).into());
+        chunk.set(1, &(1, 2, 0).into());
+
+        let result = ambient_occlusion(
+            &chunk,
+            &ChunkNeighbours::new(),
+            &describe,
+            &(1, 1, 1).into(),
+            FaceDirection::Up,
+        );
+
+        assert_eq!(result, [0, 2, 3, 2]);
+    }
+}
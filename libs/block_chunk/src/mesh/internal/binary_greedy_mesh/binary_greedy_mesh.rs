@@ -0,0 +1,340 @@
+use crate::mesh::internal::ambient_occlusion::ambient_occlusion;
+use crate::mesh::internal::binary_greedy_mesh::occupancy::{position_in_row, Occupancy};
+use crate::mesh::internal::greedy_mesh_with_neighbours;
+use crate::mesh::internal::shape_mesh::mesh_shape;
+use crate::mesh::{BlockDescriptor, ChunkNeighbours, Face, FaceDirection, MeshResult};
+use crate::{BlockOffset, Chunk};
+use std::fmt::Debug;
+
+/// The largest chunk that fits in the rows of bits, larger chunks are meshed by
+/// [`greedy_mesh`](crate::mesh::greedy_mesh) instead
+const MAX_SIZE: usize = u64::BITS as usize;
+
+/// Meshes the chunk on its own, creating faces on every edge of the chunk
+pub fn binary_greedy_mesh<
+    T: Sync + Send + Debug,
+    TE: Sync + Send + Clone + PartialEq + Debug,
+    C: Send + Sync + Fn(&T) -> Option<BlockDescriptor>,
+    TEC: Send + Sync + Fn(&T, FaceDirection) -> TE,
+    const SIZE: usize,
+>(
+    chunk: &Chunk<T, SIZE>,
+    describe_callback: C,
+    texture_callback: TEC,
+) -> MeshResult<TE, SIZE> {
+    binary_greedy_mesh_with_neighbours(
+        chunk,
+        &ChunkNeighbours::new(),
+        describe_callback,
+        texture_callback,
+    )
+}
+
+/// Gives the same result as [`greedy_mesh_with_neighbours`](crate::mesh::greedy_mesh_with_neighbours).
+///
+/// The blocks are handled as rows of bits. The visible faces of a slice are sorted in to a mask for
+/// every kind of face they are, and the faces in each mask are merged with shifts and ands
+pub fn binary_greedy_mesh_with_neighbours<
+    T: Sync + Send + Debug,
+    TE: Sync + Send + Clone + PartialEq + Debug,
+    C: Send + Sync + Fn(&T) -> Option<BlockDescriptor>,
+    TEC: Send + Sync + Fn(&T, FaceDirection) -> TE,
+    const SIZE: usize,
+>(
+    chunk: &Chunk<T, SIZE>,
+    neighbours: &ChunkNeighbours<T, SIZE>,
+    describe_callback: C,
+    texture_callback: TEC,
+) -> MeshResult<TE, SIZE> {
+    if SIZE > MAX_SIZE {
+        return greedy_mesh_with_neighbours(chunk, neighbours, describe_callback, texture_callback);
+    }
+
+    let mut mesh = vec![];
+    let mut transparent_mesh = vec![];
+    let mut shape_mesh = vec![];
+    let mut transparent_shape_mesh = vec![];
+
+    let occupancy = Occupancy::build(
+        chunk,
+        neighbours,
+        &describe_callback,
+        |position, block, descriptor| {
+            mesh_shape(
+                chunk,
+                neighbours,
+                &describe_callback,
+                &texture_callback,
+                &mut shape_mesh,
+                &mut transparent_shape_mesh,
+                descriptor,
+                position,
+                block,
+            );
+        },
+    );
+
+    let mut masks: Vec<FaceMask<TE, SIZE>> = vec![];
+    for direction in FaceDirection::ALL {
+        for slice in 0..SIZE {
+            masks.clear();
+
+            for height in 0..SIZE {
+                let visible = occupancy.visible(direction, slice, height);
+                let transparent = occupancy.transparent(direction, slice, height);
+
+                for_each_bit(visible, |width| {
+                    let position = position_in_row(direction, slice, height, width);
+                    let texture = texture_callback(chunk.get(&position), direction);
+                    let ambient_occlusion = ambient_occlusion(
+                        chunk,
+                        neighbours,
+                        &describe_callback,
+                        &position,
+                        direction,
+                    );
+                    let is_transparent = transparent & (1 << width) != 0;
+
+                    let index = masks
+                        .iter()
+                        .position(|mask| {
+                            mask.texture == texture
+                                && mask.ambient_occlusion == ambient_occlusion
+                                && mask.is_transparent == is_transparent
+                        })
+                        .unwrap_or_else(|| {
+                            masks.push(FaceMask::new(texture, ambient_occlusion, is_transparent));
+                            masks.len() - 1
+                        });
+                    masks[index].rows[height] |= 1 << width;
+                });
+            }
+
+            for mask in &mut masks {
+                mask.merge(direction, slice, |face| {
+                    push_face(&mut mesh, &mut transparent_mesh, face);
+                });
+            }
+        }
+    }
+
+    MeshResult {
+        mesh,
+        transparent_mesh,
+        shape_mesh,
+        transparent_shape_mesh,
+    }
+}
+
+/// The faces in a slice that share a texture, ambient occlusion and transparency, one row of bits
+/// per height. Only faces in the same mask can be merged with each other
+struct FaceMask<TE, const SIZE: usize> {
+    texture: TE,
+    ambient_occlusion: [u8; 4],
+    is_transparent: bool,
+    rows: [u64; SIZE],
+}
+
+impl<TE: Clone + PartialEq, const SIZE: usize> FaceMask<TE, SIZE> {
+    const fn new(texture: TE, ambient_occlusion: [u8; 4], is_transparent: bool) -> Self {
+        Self {
+            texture,
+            ambient_occlusion,
+            is_transparent,
+            rows: [0; SIZE],
+        }
+    }
+
+    /// Merges the faces in to as few faces as possible, clearing the rows while doing so. Every
+    /// run of faces in a row is grown over the rows above it for as long as they have the exact
+    /// same run
+    fn merge<F: FnMut(Face<TE, SIZE>)>(
+        &mut self,
+        direction: FaceDirection,
+        slice: usize,
+        mut callback: F,
+    ) {
+        for height in 0..SIZE {
+            while self.rows[height] != 0 {
+                let start = self.rows[height].trailing_zeros();
+                let length = (self.rows[height] >> start).trailing_ones();
+                let run = u64::MAX >> (u64::BITS - length) << start;
+                // The bits right next to the run, which have to be clear for a row to continue it
+                let edges = ((run << 1) | (run >> 1)) & !run;
+                self.rows[height] &= !run;
+
+                let mut end = height + 1;
+                while end < SIZE && self.rows[end] & (run | edges) == run {
+                    self.rows[end] &= !run;
+                    end += 1;
+                }
+
+                let position = position_in_row(direction, slice, height, start as usize);
+                let mut face = unit_face(direction, &position, &self.texture, self.is_transparent)
+                    .with_ambient_occlusion(self.ambient_occlusion);
+                face.width = length as usize;
+                face.height = end - height;
+                callback(face);
+            }
+        }
+    }
+}
+
+/// Calls the callback with the index of every set bit
+#[inline]
+fn for_each_bit<F: FnMut(usize)>(mut bits: u64, mut callback: F) {
+    while bits != 0 {
+        callback(bits.trailing_zeros() as usize);
+
+        // Clear the lowest set bit, which we just handled
+        bits &= bits - 1;
+    }
+}
+
+fn push_face<TE, const SIZE: usize>(
+    mesh: &mut Vec<Face<TE, SIZE>>,
+    transparent_mesh: &mut Vec<Face<TE, SIZE>>,
+    face: Face<TE, SIZE>,
+) {
+    if face.is_transparent {
+        transparent_mesh.push(face);
+    } else {
+        mesh.push(face);
+    }
+}
+
+fn unit_face<TE: Clone + PartialEq, const SIZE: usize>(
+    direction: FaceDirection,
+    position: &BlockOffset<SIZE>,
+    texture: &TE,
+    is_transparent: bool,
+) -> Face<TE, SIZE> {
+    match direction {
+        FaceDirection::North => Face::north(position, texture, is_transparent),
+        FaceDirection::South => Face::south(position, texture, is_transparent),
+        FaceDirection::West => Face::west(position, texture, is_transparent),
+        FaceDirection::East => Face::east(position, texture, is_transparent),
+        FaceDirection::Up => Face::up(position, texture, is_transparent),
+        FaceDirection::Down => Face::down(position, texture, is_transparent),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::mesh::internal::{binary_greedy_mesh_with_neighbours, greedy_mesh_with_neighbours};
+    use crate::mesh::{BlockDescriptor, BlockShape, ChunkNeighbours, FaceDirection, MeshResult};
+    use crate::Chunk;
+    use std::collections::HashSet;
+
+    fn describe(value: &usize) -> Option<BlockDescriptor> {
+        match value {
+            1 | 2 => Some(BlockDescriptor {
+                shape: BlockShape::Cube,
+                is_transparent: false,
+            }),
+            3 => Some(BlockDescriptor {
+                shape: BlockShape::Cube,
+                is_transparent: true,
+            }),
+            4 => Some(BlockDescriptor {
+                shape: BlockShape::SLAB,
+                is_transparent: false,
+            }),
+            _ => None,
+        }
+    }
+
+    fn texture(value: &usize, direction: FaceDirection) -> usize {
+        if direction == FaceDirection::Up {
+            *value * 10
+        } else {
+            *value
+        }
+    }
+
+    /// Fills a chunk with a mix of every kind of block, without any real structure
+    fn noise_chunk<const SIZE: usize>(seed: u64) -> Chunk<usize, SIZE> {
+        let mut chunk = Chunk::default();
+        let mut state = seed;
+        for x in 0..SIZE {
+            for y in 0..SIZE {
+                for z in 0..SIZE {
+                    state = state
+                        .wrapping_mul(6_364_136_223_846_793_005)
+                        .wrapping_add(1_442_695_040_888_963_407);
+                    let value = match (state >> 33) % 10 {
+                        0..=3 => 0,
+                        4..=6 => 1,
+                        7 => 2,
+                        8 => 3,
+                        _ => 4,
+                    };
+                    chunk.set(value, &(x, y, z).into());
+                }
+            }
+        }
+        chunk
+    }
+
+    fn assert_equivalent<const SIZE: usize>(
+        chunk: &Chunk<usize, SIZE>,
+        neighbours: &ChunkNeighbours<usize, SIZE>,
+    ) {
+        let expected = greedy_mesh_with_neighbours(chunk, neighbours, describe, texture);
+        let result = binary_greedy_mesh_with_neighbours(chunk, neighbours, describe, texture);
+
+        let faces = |result: &MeshResult<usize, SIZE>| {
+            (
+                result.mesh.iter().cloned().collect::<HashSet<_>>(),
+                result
+                    .transparent_mesh
+                    .iter()
+                    .cloned()
+                    .collect::<HashSet<_>>(),
+            )
+        };
+        assert_eq!(result.mesh.len(), expected.mesh.len());
+        assert_eq!(
+            result.transparent_mesh.len(),
+            expected.transparent_mesh.len()
+        );
+        assert_eq!(faces(&result), faces(&expected));
+        assert_eq!(result.shape_mesh, expected.shape_mesh);
+        assert_eq!(
+            result.transparent_shape_mesh,
+            expected.transparent_shape_mesh
+        );
+    }
+
+    #[test]
+    fn it_should_give_the_same_faces_as_the_greedy_mesh() {
+        for seed in 0..4 {
+            assert_equivalent(&noise_chunk::<8>(seed), &ChunkNeighbours::new());
+        }
+    }
+
+    #[test]
+    fn it_should_give_the_same_faces_as_the_greedy_mesh_with_neighbours() {
+        let chunk = noise_chunk::<16>(42);
+        let mut neighbours = ChunkNeighbours::new();
+        for (seed, direction) in FaceDirection::ALL.into_iter().enumerate() {
+            neighbours.insert(direction, &noise_chunk::<16>(seed as u64));
+        }
+
+        assert_equivalent(&chunk, &neighbours);
+    }
+
+    #[test]
+    fn it_should_handle_rows_as_wide_as_the_bits() {
+        let mut chunk = Chunk::<usize, 64>::default();
+        for x in 0..64 {
+            for z in 0..64 {
+                for y in 0..=(x + z) % 7 {
+                    chunk.set(1 + (x / 16) % 3, &(x, y, z).into());
+                }
+            }
+        }
+
+        assert_equivalent(&chunk, &ChunkNeighbours::new());
+    }
+}
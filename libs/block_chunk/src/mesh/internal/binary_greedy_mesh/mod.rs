@@ -0,0 +1,4 @@
+mod binary_greedy_mesh;
+mod occupancy;
+
+pub use self::binary_greedy_mesh::{binary_greedy_mesh, binary_greedy_mesh_with_neighbours};
@@ -0,0 +1,201 @@
+use crate::mesh::{BlockDescriptor, ChunkNeighbours, FaceDirection};
+use crate::{BlockOffset, Chunk};
+
+/// The blocks of a chunk as rows of bits, one bit per block.
+///
+/// Every face direction is handled as slices along its normal, where each slice is made up of rows
+/// running along the width of the faces. North, south, up and down faces have their width along
+/// the x axis, while west and east faces have their width along the z axis, so the rows are
+/// stored along both of those axes
+pub struct Occupancy<const SIZE: usize> {
+    /// Full blocks, in rows along the x axis indexed by `y * SIZE + z`
+    cubes_x: Vec<u64>,
+    /// Full blocks that hides the faces next to them, in rows along the x axis
+    opaque_x: Vec<u64>,
+    /// Full blocks that are transparent, in rows along the x axis
+    transparent_x: Vec<u64>,
+    /// Full blocks, in rows along the z axis indexed by `x * SIZE + y`
+    cubes_z: Vec<u64>,
+    /// Full blocks that hides the faces next to them, in rows along the z axis
+    opaque_z: Vec<u64>,
+    /// Full blocks that are transparent, in rows along the z axis
+    transparent_z: Vec<u64>,
+    /// The blocks in each neighbouring chunk that hides the faces on our edge, in rows indexed by
+    /// the height coordinate of the faces
+    neighbours: [Vec<u64>; 6],
+}
+
+impl<const SIZE: usize> Occupancy<SIZE> {
+    /// Builds the rows for the blocks of the chunk, handing every block that is not a full cube over
+    /// to the given callback
+    pub fn build<
+        T: Send + Sync,
+        C: Fn(&T) -> Option<BlockDescriptor>,
+        S: FnMut(&BlockOffset<SIZE>, &T, &BlockDescriptor),
+    >(
+        chunk: &Chunk<T, SIZE>,
+        neighbours: &ChunkNeighbours<T, SIZE>,
+        describe_callback: &C,
+        mut shape_callback: S,
+    ) -> Self {
+        let mut cubes_x = vec![0; SIZE * SIZE];
+        let mut opaque_x = vec![0; SIZE * SIZE];
+        let mut transparent_x = vec![0; SIZE * SIZE];
+        let mut cubes_z = vec![0; SIZE * SIZE];
+        let mut opaque_z = vec![0; SIZE * SIZE];
+        let mut transparent_z = vec![0; SIZE * SIZE];
+
+        for x in 0..SIZE {
+            for y in 0..SIZE {
+                for z in 0..SIZE {
+                    let position = BlockOffset { x, y, z };
+                    let block = chunk.get(&position);
+                    let descriptor = match describe_callback(block) {
+                        Some(descriptor) => descriptor,
+                        None => continue,
+                    };
+
+                    if !descriptor.is_standard_square() {
+                        shape_callback(&position, block, &descriptor);
+                        continue;
+                    }
+
+                    let row_x = y * SIZE + z;
+                    let row_z = x * SIZE + y;
+                    cubes_x[row_x] |= 1 << x;
+                    cubes_z[row_z] |= 1 << z;
+                    if descriptor.is_transparent {
+                        transparent_x[row_x] |= 1 << x;
+                        transparent_z[row_z] |= 1 << z;
+                    } else {
+                        opaque_x[row_x] |= 1 << x;
+                        opaque_z[row_z] |= 1 << z;
+                    }
+                }
+            }
+        }
+
+        Self {
+            cubes_x,
+            opaque_x,
+            transparent_x,
+            cubes_z,
+            opaque_z,
+            transparent_z,
+            neighbours: Self::neighbour_rows(neighbours, describe_callback),
+        }
+    }
+
+    /// Reads the rows for the blocks that touches our edges from the neighbours
+    fn neighbour_rows<T: Send + Sync, C: Fn(&T) -> Option<BlockDescriptor>>(
+        neighbours: &ChunkNeighbours<T, SIZE>,
+        describe_callback: &C,
+    ) -> [Vec<u64>; 6] {
+        FaceDirection::ALL.map(|direction| {
+            let mut rows = vec![0; SIZE];
+            for (height, row) in rows.iter_mut().enumerate() {
+                for width in 0..SIZE {
+                    // Looking up the neighbour only depends on the position along the edge
+                    let position = position_in_row(direction, 0, height, width);
+                    let is_opaque = neighbours
+                        .get(direction, &position)
+                        .and_then(describe_callback)
+                        .map_or(false, |descriptor| {
+                            descriptor.is_standard_square() && !descriptor.is_transparent
+                        });
+                    if is_opaque {
+                        *row |= 1 << width;
+                    }
+                }
+            }
+            rows
+        })
+    }
+
+    /// The faces in the given direction that can be seen, in a row of the given slice
+    #[inline]
+    pub fn visible(&self, direction: FaceDirection, slice: usize, height: usize) -> u64 {
+        let covering = match direction {
+            FaceDirection::South | FaceDirection::East | FaceDirection::Up if slice + 1 < SIZE => {
+                self.opaque(direction, slice + 1, height)
+            }
+            FaceDirection::North | FaceDirection::West | FaceDirection::Down if slice > 0 => {
+                self.opaque(direction, slice - 1, height)
+            }
+            _ => self.neighbours[direction_index(direction)][height],
+        };
+
+        self.row(&self.cubes_x, &self.cubes_z, direction, slice, height) & !covering
+    }
+
+    /// The blocks in a row that are transparent
+    #[inline]
+    pub fn transparent(&self, direction: FaceDirection, slice: usize, height: usize) -> u64 {
+        self.row(
+            &self.transparent_x,
+            &self.transparent_z,
+            direction,
+            slice,
+            height,
+        )
+    }
+
+    #[inline]
+    fn opaque(&self, direction: FaceDirection, slice: usize, height: usize) -> u64 {
+        self.row(&self.opaque_x, &self.opaque_z, direction, slice, height)
+    }
+
+    #[inline]
+    fn row(
+        &self,
+        rows_x: &[u64],
+        rows_z: &[u64],
+        direction: FaceDirection,
+        slice: usize,
+        height: usize,
+    ) -> u64 {
+        match direction {
+            FaceDirection::North | FaceDirection::South => rows_x[height * SIZE + slice],
+            FaceDirection::Up | FaceDirection::Down => rows_x[slice * SIZE + height],
+            FaceDirection::West | FaceDirection::East => rows_z[slice * SIZE + height],
+        }
+    }
+}
+
+/// The position of the block at the given width in a row of a slice
+#[inline]
+pub const fn position_in_row<const SIZE: usize>(
+    direction: FaceDirection,
+    slice: usize,
+    height: usize,
+    width: usize,
+) -> BlockOffset<SIZE> {
+    match direction {
+        FaceDirection::North | FaceDirection::South => BlockOffset {
+            x: width,
+            y: height,
+            z: slice,
+        },
+        FaceDirection::West | FaceDirection::East => BlockOffset {
+            x: slice,
+            y: height,
+            z: width,
+        },
+        FaceDirection::Up | FaceDirection::Down => BlockOffset {
+            x: width,
+            y: slice,
+            z: height,
+        },
+    }
+}
+
+const fn direction_index(direction: FaceDirection) -> usize {
+    match direction {
+        FaceDirection::North => 0,
+        FaceDirection::South => 1,
+        FaceDirection::West => 2,
+        FaceDirection::East => 3,
+        FaceDirection::Up => 4,
+        FaceDirection::Down => 5,
+    }
+}
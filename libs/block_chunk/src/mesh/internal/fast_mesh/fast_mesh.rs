@@ -1,7 +1,8 @@
 use crate::mesh::internal::fast_mesh::handle_block::handle_block;
-use crate::mesh::{BlockDescriptor, FaceDirection, MeshResult};
+use crate::mesh::{BlockDescriptor, ChunkNeighbours, FaceDirection, MeshResult};
 use crate::Chunk;
 
+/// Meshes the chunk on its own, creating faces on every edge of the chunk
 pub fn fast_mesh<
     T: Send + Sync,
     TE: Clone + PartialEq + Send + Sync,
@@ -12,21 +13,46 @@ pub fn fast_mesh<
     chunk: &Chunk<T, SIZE>,
     describe_callback: C,
     texture_callback: TEC,
+) -> MeshResult<TE, SIZE> {
+    fast_mesh_with_neighbours(
+        chunk,
+        &ChunkNeighbours::new(),
+        describe_callback,
+        texture_callback,
+    )
+}
+
+/// Meshes the chunk, culling the faces on the edges of the chunk that are covered by the
+/// neighbouring chunks
+pub fn fast_mesh_with_neighbours<
+    T: Send + Sync,
+    TE: Clone + PartialEq + Send + Sync,
+    C: Send + Sync + Fn(&T) -> Option<BlockDescriptor>,
+    TEC: Send + Sync + Fn(&T, FaceDirection) -> TE,
+    const SIZE: usize,
+>(
+    chunk: &Chunk<T, SIZE>,
+    neighbours: &ChunkNeighbours<T, SIZE>,
+    describe_callback: C,
+    texture_callback: TEC,
 ) -> MeshResult<TE, SIZE> {
     let mut mesh = vec![];
     let mut transparent_mesh = vec![];
-    let mut unhandled = vec![];
+    let mut shape_mesh = vec![];
+    let mut transparent_shape_mesh = vec![];
 
     for x in 0..SIZE {
         for y in 0..SIZE {
             for z in 0..SIZE {
                 handle_block(
                     chunk,
+                    neighbours,
                     &describe_callback,
                     &texture_callback,
                     &mut mesh,
                     &mut transparent_mesh,
-                    &mut unhandled,
+                    &mut shape_mesh,
+                    &mut transparent_shape_mesh,
                     x,
                     y,
                     z,
@@ -38,14 +64,15 @@ pub fn fast_mesh<
     MeshResult {
         mesh,
         transparent_mesh,
-        unhandled,
+        shape_mesh,
+        transparent_shape_mesh,
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::mesh::internal::fast_mesh;
-    use crate::mesh::{BlockDescriptor, MeshableChunk};
+    use crate::mesh::internal::{fast_mesh, fast_mesh_with_neighbours};
+    use crate::mesh::{BlockDescriptor, BlockShape, ChunkNeighbours, FaceDirection, MeshableChunk};
     use crate::Chunk;
 
     #[tokio::test]
@@ -53,36 +80,73 @@ mod tests {
         let chunk = Chunk::<usize, 8>::default();
 
         let result = chunk
-            .fast_mesh(|_| {
-                Some(BlockDescriptor {
-                    is_standard_square: true,
-                    is_transparent: false,
-                })
-            })
+            .fast_mesh(
+                |_| {
+                    Some(BlockDescriptor {
+                        shape: BlockShape::Cube,
+                        is_transparent: false,
+                    })
+                },
+                |_, _| (),
+            )
             .await;
 
         assert_eq!(result.mesh.len(), 8 * 8 * 6);
         assert_eq!(result.transparent_mesh.len(), 0);
-        assert_eq!(result.unhandled.len(), 0);
+        assert_eq!(result.shape_mesh.len(), 0);
     }
 
     #[test]
     fn it_should_give_correct_amount_of_faces_checker() {
         let chunk = Chunk::<usize, 8>::new_checker(0, 1);
 
-        let result = fast_mesh(&chunk, |val| {
-            if *val == 0 {
-                None
-            } else {
-                Some(BlockDescriptor {
-                    is_standard_square: true,
-                    is_transparent: false,
-                })
-            }
-        });
+        let result = fast_mesh(
+            &chunk,
+            |val| {
+                if *val == 0 {
+                    None
+                } else {
+                    Some(BlockDescriptor {
+                        shape: BlockShape::Cube,
+                        is_transparent: false,
+                    })
+                }
+            },
+            |_, _| (),
+        );
 
         assert_eq!(result.mesh.len(), 1536);
         assert_eq!(result.transparent_mesh.len(), 0);
-        assert_eq!(result.unhandled.len(), 0);
+        assert_eq!(result.shape_mesh.len(), 0);
+    }
+
+    #[test]
+    fn it_should_cull_faces_covered_by_neighbours() {
+        let chunk = Chunk::<usize, 8>::filled(1);
+        let mut neighbours = ChunkNeighbours::new();
+        for direction in FaceDirection::ALL {
+            if direction != FaceDirection::Up {
+                neighbours.insert(direction, &chunk);
+            }
+        }
+
+        let result = fast_mesh_with_neighbours(
+            &chunk,
+            &neighbours,
+            |val| {
+                (*val == 1).then(|| BlockDescriptor {
+                    shape: BlockShape::Cube,
+                    is_transparent: false,
+                })
+            },
+            |_, _| (),
+        );
+
+        // Only the top of the chunk is exposed
+        assert_eq!(result.mesh.len(), 8 * 8);
+        assert!(result
+            .mesh
+            .iter()
+            .all(|face| face.direction == FaceDirection::Up));
     }
 }
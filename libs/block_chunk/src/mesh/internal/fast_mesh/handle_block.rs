@@ -1,5 +1,6 @@
 use crate::mesh::internal::fast_mesh::handle_face::handle_face;
-use crate::mesh::{BlockDescriptor, Face, FaceDirection};
+use crate::mesh::internal::shape_mesh::mesh_shape;
+use crate::mesh::{BlockDescriptor, ChunkNeighbours, Face, FaceDirection, ShapeFace};
 use crate::{BlockOffset, Chunk};
 
 pub fn handle_block<
@@ -10,11 +11,13 @@ pub fn handle_block<
     const SIZE: usize,
 >(
     chunk: &Chunk<T, SIZE>,
+    neighbours: &ChunkNeighbours<T, SIZE>,
     describe_callback: &C,
     texture_callback: &TEC,
     mesh: &mut Vec<Face<TE, SIZE>>,
     transparent_mesh: &mut Vec<Face<TE, SIZE>>,
-    unhandled: &mut Vec<(usize, usize, usize)>,
+    shape_mesh: &mut Vec<ShapeFace<TE, SIZE>>,
+    transparent_shape_mesh: &mut Vec<ShapeFace<TE, SIZE>>,
     x: usize,
     y: usize,
     z: usize,
@@ -23,15 +26,16 @@ pub fn handle_block<
 
     let block = chunk.get(&position);
     if let Some(descriptor) = describe_callback(block) {
-        if descriptor.is_standard_square {
+        if descriptor.is_standard_square() {
             handle_face(
                 chunk,
+                neighbours,
                 describe_callback,
                 mesh,
                 transparent_mesh,
                 &descriptor,
                 &position,
-                position.north(),
+                neighbours.block_next_to(chunk, &position, FaceDirection::North),
                 Face::north,
                 texture_callback,
                 FaceDirection::North,
@@ -39,12 +43,13 @@ pub fn handle_block<
             );
             handle_face(
                 chunk,
+                neighbours,
                 describe_callback,
                 mesh,
                 transparent_mesh,
                 &descriptor,
                 &position,
-                position.south(),
+                neighbours.block_next_to(chunk, &position, FaceDirection::South),
                 Face::south,
                 texture_callback,
                 FaceDirection::South,
@@ -52,12 +57,13 @@ pub fn handle_block<
             );
             handle_face(
                 chunk,
+                neighbours,
                 describe_callback,
                 mesh,
                 transparent_mesh,
                 &descriptor,
                 &position,
-                position.west(),
+                neighbours.block_next_to(chunk, &position, FaceDirection::West),
                 Face::west,
                 texture_callback,
                 FaceDirection::West,
@@ -65,12 +71,13 @@ pub fn handle_block<
             );
             handle_face(
                 chunk,
+                neighbours,
                 describe_callback,
                 mesh,
                 transparent_mesh,
                 &descriptor,
                 &position,
-                position.east(),
+                neighbours.block_next_to(chunk, &position, FaceDirection::East),
                 Face::east,
                 texture_callback,
                 FaceDirection::East,
@@ -78,12 +85,13 @@ pub fn handle_block<
             );
             handle_face(
                 chunk,
+                neighbours,
                 describe_callback,
                 mesh,
                 transparent_mesh,
                 &descriptor,
                 &position,
-                position.up(),
+                neighbours.block_next_to(chunk, &position, FaceDirection::Up),
                 Face::up,
                 texture_callback,
                 FaceDirection::Up,
@@ -91,19 +99,30 @@ pub fn handle_block<
             );
             handle_face(
                 chunk,
+                neighbours,
                 describe_callback,
                 mesh,
                 transparent_mesh,
                 &descriptor,
                 &position,
-                position.down(),
+                neighbours.block_next_to(chunk, &position, FaceDirection::Down),
                 Face::down,
                 texture_callback,
                 FaceDirection::Down,
                 block,
             );
         } else {
-            unhandled.push((x, y, z));
+            mesh_shape(
+                chunk,
+                neighbours,
+                describe_callback,
+                texture_callback,
+                shape_mesh,
+                transparent_shape_mesh,
+                &descriptor,
+                &position,
+                block,
+            );
         }
     }
 }
@@ -3,4 +3,4 @@ mod handle_block;
 mod handle_face;
 mod push_face;
 
-pub use self::fast_mesh::fast_mesh;
+pub use self::fast_mesh::{fast_mesh, fast_mesh_with_neighbours};
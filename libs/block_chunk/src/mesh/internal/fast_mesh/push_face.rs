@@ -16,7 +16,7 @@ pub fn push_face<TE: Send + Sync, const SIZE: usize>(
 #[cfg(test)]
 mod tests {
     use crate::mesh::internal::fast_mesh::push_face::push_face;
-    use crate::mesh::{BlockDescriptor, Face};
+    use crate::mesh::{BlockDescriptor, BlockShape, Face};
     use crate::BlockOffset;
 
     #[test]
@@ -24,7 +24,7 @@ mod tests {
         let mut mesh = vec![];
         let mut transparent_mesh = vec![];
         let descriptor = BlockDescriptor {
-            is_standard_square: true,
+            shape: BlockShape::Cube,
             is_transparent: true,
         };
 
@@ -44,7 +44,7 @@ mod tests {
         let mut mesh = vec![];
         let mut transparent_mesh = vec![];
         let descriptor = BlockDescriptor {
-            is_standard_square: true,
+            shape: BlockShape::Cube,
             is_transparent: false,
         };
 
@@ -1,8 +1,10 @@
 use crate::mesh::internal::greedy_mesh::merge_face::merge_face;
-use crate::mesh::{BlockDescriptor, Face, FaceDirection, MeshResult};
+use crate::mesh::internal::shape_mesh::mesh_shape;
+use crate::mesh::{BlockDescriptor, ChunkNeighbours, Face, FaceDirection, MeshResult};
 use crate::{BlockOffset, Chunk};
 use std::fmt::Debug;
 
+/// Meshes the chunk on its own, creating faces on every edge of the chunk
 pub fn greedy_mesh<
     T: Sync + Send + Debug,
     TE: Sync + Send + Clone + PartialEq + Debug,
@@ -13,10 +15,33 @@ pub fn greedy_mesh<
     chunk: &Chunk<T, SIZE>,
     describe_callback: C,
     texture_callback: TEC,
+) -> MeshResult<TE, SIZE> {
+    greedy_mesh_with_neighbours(
+        chunk,
+        &ChunkNeighbours::new(),
+        describe_callback,
+        texture_callback,
+    )
+}
+
+/// Meshes the chunk, culling the faces on the edges of the chunk that are covered by the
+/// neighbouring chunks
+pub fn greedy_mesh_with_neighbours<
+    T: Sync + Send + Debug,
+    TE: Sync + Send + Clone + PartialEq + Debug,
+    C: Send + Sync + Fn(&T) -> Option<BlockDescriptor>,
+    TEC: Send + Sync + Fn(&T, FaceDirection) -> TE,
+    const SIZE: usize,
+>(
+    chunk: &Chunk<T, SIZE>,
+    neighbours: &ChunkNeighbours<T, SIZE>,
+    describe_callback: C,
+    texture_callback: TEC,
 ) -> MeshResult<TE, SIZE> {
     let mut mesh = vec![];
     let mut transparent_mesh = vec![];
-    let mut unhandled = vec![];
+    let mut shape_mesh = vec![];
+    let mut transparent_shape_mesh = vec![];
 
     for x in 0..SIZE {
         let mut rows = vec![];
@@ -35,10 +60,21 @@ pub fn greedy_mesh<
             for z in 0..SIZE {
                 {
                     let position: BlockOffset<SIZE> = (x, y, z).into();
-                    if describe_callback(chunk.get(&position))
-                        .map_or(false, |b| !b.is_standard_square)
+                    let block = chunk.get(&position);
+                    if let Some(descriptor) = describe_callback(block)
+                        .filter(|descriptor| !descriptor.is_standard_square())
                     {
-                        unhandled.push((x, y, z));
+                        mesh_shape(
+                            chunk,
+                            neighbours,
+                            &describe_callback,
+                            &texture_callback,
+                            &mut shape_mesh,
+                            &mut transparent_shape_mesh,
+                            &descriptor,
+                            &position,
+                            block,
+                        );
                     }
                 }
 
@@ -46,11 +82,12 @@ pub fn greedy_mesh<
                     let position: BlockOffset<SIZE> = (z, y, x).into();
                     merge_face(
                         chunk,
+                        neighbours,
                         &describe_callback,
                         &mut lines,
                         &mut lines_transparent,
                         &position,
-                        position.north(),
+                        neighbours.block_next_to(chunk, &position, FaceDirection::North),
                         &mut current_north_face,
                         Face::north,
                         &texture_callback,
@@ -58,11 +95,12 @@ pub fn greedy_mesh<
                     );
                     merge_face(
                         chunk,
+                        neighbours,
                         &describe_callback,
                         &mut lines,
                         &mut lines_transparent,
                         &position,
-                        position.south(),
+                        neighbours.block_next_to(chunk, &position, FaceDirection::South),
                         &mut current_south_face,
                         Face::south,
                         &texture_callback,
@@ -74,11 +112,12 @@ pub fn greedy_mesh<
                     let position: BlockOffset<SIZE> = (x, y, z).into();
                     merge_face(
                         chunk,
+                        neighbours,
                         &describe_callback,
                         &mut lines,
                         &mut lines_transparent,
                         &position,
-                        position.west(),
+                        neighbours.block_next_to(chunk, &position, FaceDirection::West),
                         &mut current_west_face,
                         Face::west,
                         &texture_callback,
@@ -86,11 +125,12 @@ pub fn greedy_mesh<
                     );
                     merge_face(
                         chunk,
+                        neighbours,
                         &describe_callback,
                         &mut lines,
                         &mut lines_transparent,
                         &position,
-                        position.east(),
+                        neighbours.block_next_to(chunk, &position, FaceDirection::East),
                         &mut current_east_face,
                         Face::east,
                         &texture_callback,
@@ -101,11 +141,12 @@ pub fn greedy_mesh<
                     let position: BlockOffset<SIZE> = (z, x, y).into();
                     merge_face(
                         chunk,
+                        neighbours,
                         &describe_callback,
                         &mut lines,
                         &mut lines_transparent,
                         &position,
-                        position.up(),
+                        neighbours.block_next_to(chunk, &position, FaceDirection::Up),
                         &mut current_up_face,
                         Face::up,
                         &texture_callback,
@@ -113,11 +154,12 @@ pub fn greedy_mesh<
                     );
                     merge_face(
                         chunk,
+                        neighbours,
                         &describe_callback,
                         &mut lines,
                         &mut lines_transparent,
                         &position,
-                        position.down(),
+                        neighbours.block_next_to(chunk, &position, FaceDirection::Down),
                         &mut current_down_face,
                         Face::down,
                         &texture_callback,
@@ -126,23 +168,22 @@ pub fn greedy_mesh<
                 }
             }
 
-            if let Some(face) = current_north_face.take() {
-                lines.push(face);
-            }
-            if let Some(face) = current_south_face.take() {
-                lines.push(face);
-            }
-            if let Some(face) = current_west_face.take() {
-                lines.push(face);
-            }
-            if let Some(face) = current_east_face.take() {
-                lines.push(face);
-            }
-            if let Some(face) = current_up_face.take() {
-                lines.push(face);
-            }
-            if let Some(face) = current_down_face.take() {
-                lines.push(face);
+            for face in [
+                current_north_face,
+                current_south_face,
+                current_west_face,
+                current_east_face,
+                current_up_face,
+                current_down_face,
+            ]
+            .into_iter()
+            .flatten()
+            {
+                if face.is_transparent {
+                    lines_transparent.push(face);
+                } else {
+                    lines.push(face);
+                }
             }
 
             for face in lines {
@@ -175,48 +216,159 @@ pub fn greedy_mesh<
     MeshResult {
         mesh,
         transparent_mesh,
-        unhandled,
+        shape_mesh,
+        transparent_shape_mesh,
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::mesh::internal::greedy_mesh;
-    use crate::mesh::BlockDescriptor;
+    use crate::mesh::internal::{greedy_mesh, greedy_mesh_with_neighbours};
+    use crate::mesh::{BlockDescriptor, BlockShape, ChunkNeighbours, Face, FaceDirection};
     use crate::Chunk;
 
     #[test]
     fn it_should_give_correct_amount_of_faces() {
         let chunk = Chunk::<usize, 8>::default();
 
-        let result = greedy_mesh(&chunk, |_| {
-            Some(BlockDescriptor {
-                is_standard_square: true,
-                is_transparent: false,
-            })
-        });
+        let result = greedy_mesh(
+            &chunk,
+            |_| {
+                Some(BlockDescriptor {
+                    shape: BlockShape::Cube,
+                    is_transparent: false,
+                })
+            },
+            |_, _| (),
+        );
 
         assert_eq!(result.mesh.len(), 6);
         assert_eq!(result.transparent_mesh.len(), 0);
-        assert_eq!(result.unhandled.len(), 0);
+        assert_eq!(result.shape_mesh.len(), 0);
     }
 
     #[test]
     fn it_should_give_correct_amount_of_faces_checker() {
         let chunk = Chunk::<usize, 8>::new_checker(0, 1);
 
-        let result = greedy_mesh(&chunk, |val| {
-            if *val == 0 {
-                None
-            } else {
-                Some(BlockDescriptor {
-                    is_standard_square: true,
-                    is_transparent: false,
-                })
-            }
-        });
+        let result = greedy_mesh(
+            &chunk,
+            |val| {
+                if *val == 0 {
+                    None
+                } else {
+                    Some(BlockDescriptor {
+                        shape: BlockShape::Cube,
+                        is_transparent: false,
+                    })
+                }
+            },
+            |_, _| (),
+        );
         assert_eq!(result.mesh.len(), 1536);
         assert_eq!(result.transparent_mesh.len(), 0);
-        assert_eq!(result.unhandled.len(), 0);
+        assert_eq!(result.shape_mesh.len(), 0);
+    }
+
+    #[test]
+    fn it_should_only_merge_faces_with_the_same_ambient_occlusion() {
+        let mut chunk = Chunk::<usize, 8>::default();
+        for x in 0..8 {
+            for z in 0..8 {
+                chunk.set(1, &(x, 0, z).into());
+            }
+        }
+        chunk.set(1, &(3, 1, 3).into());
+
+        let result = greedy_mesh(
+            &chunk,
+            |val| {
+                (*val == 1).then(|| BlockDescriptor {
+                    shape: BlockShape::Cube,
+                    is_transparent: false,
+                })
+            },
+            |_, _| (),
+        );
+
+        let up_faces = result
+            .mesh
+            .iter()
+            .filter(|face| face.direction == FaceDirection::Up)
+            .collect::<Vec<_>>();
+        assert!(up_faces.len() > 2);
+        assert_eq!(
+            up_faces
+                .iter()
+                .map(|face| face.width * face.height)
+                .sum::<usize>(),
+            8 * 8
+        );
+
+        // The floor right next to the pillar is in its shadow
+        let next_to_pillar = up_faces
+            .iter()
+            .find(|face| face.position == (2, 0, 3).into())
+            .unwrap();
+        assert_ne!(
+            next_to_pillar.ambient_occlusion,
+            Face::<(), 8>::NO_OCCLUSION
+        );
+    }
+
+    #[test]
+    fn it_should_cull_faces_covered_by_neighbours() {
+        let chunk = Chunk::<usize, 8>::filled(1);
+        let mut neighbours = ChunkNeighbours::new();
+        for direction in FaceDirection::ALL {
+            neighbours.insert(direction, &chunk);
+        }
+
+        let describe_callback = |val: &usize| {
+            (*val == 1).then(|| BlockDescriptor {
+                shape: BlockShape::Cube,
+                is_transparent: false,
+            })
+        };
+
+        let result = greedy_mesh_with_neighbours(&chunk, &neighbours, describe_callback, |_, _| ());
+        assert!(result.mesh.is_empty());
+
+        // A neighbour of air should not cover anything
+        neighbours.insert(FaceDirection::West, &Chunk::default());
+        let result = greedy_mesh_with_neighbours(&chunk, &neighbours, describe_callback, |_, _| ());
+        assert_eq!(result.mesh.len(), 1);
+        assert_eq!(result.mesh[0].direction, FaceDirection::West);
+    }
+
+    #[test]
+    fn it_should_mesh_shaped_blocks_on_their_own() {
+        let mut chunk = Chunk::<usize, 8>::default();
+        chunk.set(1, &(1, 1, 1).into());
+        chunk.set(2, &(2, 1, 1).into());
+
+        let result = greedy_mesh(
+            &chunk,
+            |val| match val {
+                1 => Some(BlockDescriptor {
+                    shape: BlockShape::Cube,
+                    is_transparent: false,
+                }),
+                2 => Some(BlockDescriptor {
+                    shape: BlockShape::SLAB,
+                    is_transparent: false,
+                }),
+                _ => None,
+            },
+            |_, _| (),
+        );
+
+        // The slab does not cover the side of the cube, but the cube covers the side of the slab
+        assert_eq!(result.mesh.len(), 6);
+        assert_eq!(result.shape_mesh.len(), 5);
+        assert!(result
+            .shape_mesh
+            .iter()
+            .all(|face| face.position == (2, 1, 1).into()));
     }
 }
@@ -1,5 +1,6 @@
+use crate::mesh::internal::ambient_occlusion::ambient_occlusion;
 use crate::mesh::internal::greedy_mesh::should_create_face::should_create_face;
-use crate::mesh::{BlockDescriptor, Face, FaceDirection};
+use crate::mesh::{BlockDescriptor, ChunkNeighbours, Face, FaceDirection};
 use crate::{BlockOffset, Chunk};
 use std::fmt::Debug;
 
@@ -14,31 +15,55 @@ pub fn merge_face<
     const SIZE: usize,
 >(
     chunk: &Chunk<T, SIZE>,
+    neighbours: &ChunkNeighbours<T, SIZE>,
     describe_callback: &C,
     lines: &mut Vec<Face<TE, SIZE>>,
     lines_transparent: &mut Vec<Face<TE, SIZE>>,
     position: &BlockOffset<SIZE>,
-    neighbour_position: Option<BlockOffset<SIZE>>,
-    mut current_face: &mut Option<Face<TE, SIZE>>,
+    neighbour: Option<&T>,
+    current_face: &mut Option<Face<TE, SIZE>>,
     face_callback: FC,
     texture_callback: &TEC,
     face_direction: FaceDirection,
 ) {
     let block = chunk.get(position);
-    if let Some(descriptor) = describe_callback(block) {
-        if should_create_face(chunk, &describe_callback, neighbour_position) {
-            if let Some(face) = &mut current_face {
-                face.extend_row_by_one();
-            } else {
-                current_face.replace(face_callback(
-                    position,
-                    &texture_callback(block, face_direction),
-                    descriptor.is_transparent,
-                ));
+    // Blocks that are not full cubes are meshed on their own
+    if let Some(descriptor) = describe_callback(block).filter(BlockDescriptor::is_standard_square) {
+        if should_create_face(&describe_callback, neighbour) {
+            let face = face_callback(
+                position,
+                &texture_callback(block, face_direction),
+                descriptor.is_transparent,
+            )
+            .with_ambient_occlusion(ambient_occlusion(
+                chunk,
+                neighbours,
+                describe_callback,
+                position,
+                face_direction,
+            ));
+
+            match current_face.take() {
+                Some(mut current) if current.can_merge_row(&face) => {
+                    current.extend_face_row(&face);
+                    current_face.replace(current);
+                }
+                Some(current) => {
+                    // The faces differ, so the current one ends here
+                    if current.is_transparent {
+                        lines_transparent.push(current);
+                    } else {
+                        lines.push(current);
+                    }
+                    current_face.replace(face);
+                }
+                None => {
+                    current_face.replace(face);
+                }
             }
         } else if let Some(face) = current_face.take() {
             // If next block won't have a face in this direction
-            if descriptor.is_transparent {
+            if face.is_transparent {
                 lines_transparent.push(face);
             } else {
                 lines.push(face);
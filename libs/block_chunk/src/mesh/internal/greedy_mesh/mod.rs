@@ -2,4 +2,4 @@ mod greedy_mesh;
 mod merge_face;
 mod should_create_face;
 
-pub use self::greedy_mesh::greedy_mesh;
+pub use self::greedy_mesh::{greedy_mesh, greedy_mesh_with_neighbours};
@@ -1,5 +1,9 @@
+mod ambient_occlusion;
+mod binary_greedy_mesh;
 mod fast_mesh;
 mod greedy_mesh;
+mod shape_mesh;
 
-pub use self::fast_mesh::fast_mesh;
-pub use self::greedy_mesh::greedy_mesh;
+pub use self::binary_greedy_mesh::{binary_greedy_mesh, binary_greedy_mesh_with_neighbours};
+pub use self::fast_mesh::{fast_mesh, fast_mesh_with_neighbours};
+pub use self::greedy_mesh::{greedy_mesh, greedy_mesh_with_neighbours};
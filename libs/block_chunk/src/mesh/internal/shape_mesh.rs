@@ -0,0 +1,323 @@
+use crate::mesh::{
+    BlockDescriptor, BlockShape, ChunkNeighbours, FaceDirection, ShapeBox, ShapeFace,
+};
+use crate::{BlockOffset, Chunk};
+
+/// The two diagonal planes of a [`BlockShape::Cross`]
+const CROSS_PLANES: [[[f32; 3]; 4]; 2] = [
+    [
+        [0.0, 0.0, 0.0],
+        [1.0, 0.0, 1.0],
+        [1.0, 1.0, 1.0],
+        [0.0, 1.0, 0.0],
+    ],
+    [
+        [0.0, 0.0, 1.0],
+        [1.0, 0.0, 0.0],
+        [1.0, 1.0, 0.0],
+        [0.0, 1.0, 1.0],
+    ],
+];
+
+/// Creates the faces for a block that is not a full cube. Sides of boxes that lie against a full
+/// neighbour, or against another box of the same block, are culled. Fences get a rail to every
+/// fence and full block next to them
+pub fn mesh_shape<
+    T: Send + Sync,
+    TE: Send + Sync + Clone,
+    C: Send + Sync + Fn(&T) -> Option<BlockDescriptor>,
+    TEC: Send + Sync + Fn(&T, FaceDirection) -> TE,
+    const SIZE: usize,
+>(
+    chunk: &Chunk<T, SIZE>,
+    neighbours: &ChunkNeighbours<T, SIZE>,
+    describe_callback: &C,
+    texture_callback: &TEC,
+    shape_mesh: &mut Vec<ShapeFace<TE, SIZE>>,
+    transparent_shape_mesh: &mut Vec<ShapeFace<TE, SIZE>>,
+    descriptor: &BlockDescriptor,
+    position: &BlockOffset<SIZE>,
+    block: &T,
+) {
+    let mut push = |vertices: [[f32; 3]; 4], tex_coords: [[f32; 2]; 4], texture: TE| {
+        let face = ShapeFace {
+            position: position.clone(),
+            vertices,
+            tex_coords,
+            texture,
+            is_transparent: descriptor.is_transparent,
+        };
+        if descriptor.is_transparent {
+            transparent_shape_mesh.push(face);
+        } else {
+            shape_mesh.push(face);
+        }
+    };
+
+    match descriptor.shape {
+        BlockShape::Cube => {}
+        BlockShape::Boxes(boxes) => {
+            mesh_boxes(
+                chunk,
+                neighbours,
+                describe_callback,
+                texture_callback,
+                &mut push,
+                position,
+                block,
+                boxes,
+            );
+        }
+        BlockShape::Fence => {
+            let boxes = fence_boxes(chunk, neighbours, describe_callback, position);
+            mesh_boxes(
+                chunk,
+                neighbours,
+                describe_callback,
+                texture_callback,
+                &mut push,
+                position,
+                block,
+                &boxes,
+            );
+        }
+        BlockShape::Cross => {
+            let texture = texture_callback(block, FaceDirection::North);
+            for mut vertices in CROSS_PLANES {
+                // The planes can be seen from both sides
+                for _ in 0..2 {
+                    let tex_coords = vertices.map(|[x, y, _]| [x, y]);
+                    push(vertices, tex_coords, texture.clone());
+                    vertices.reverse();
+                }
+            }
+        }
+    }
+}
+
+fn mesh_boxes<
+    T: Send + Sync,
+    TE: Send + Sync + Clone,
+    C: Send + Sync + Fn(&T) -> Option<BlockDescriptor>,
+    TEC: Send + Sync + Fn(&T, FaceDirection) -> TE,
+    P: FnMut([[f32; 3]; 4], [[f32; 2]; 4], TE),
+    const SIZE: usize,
+>(
+    chunk: &Chunk<T, SIZE>,
+    neighbours: &ChunkNeighbours<T, SIZE>,
+    describe_callback: &C,
+    texture_callback: &TEC,
+    push: &mut P,
+    position: &BlockOffset<SIZE>,
+    block: &T,
+    boxes: &[ShapeBox],
+) {
+    for (index, shape_box) in boxes.iter().enumerate() {
+        for direction in FaceDirection::ALL {
+            if is_side_hidden(
+                chunk,
+                neighbours,
+                describe_callback,
+                position,
+                boxes,
+                index,
+                shape_box,
+                direction,
+            ) {
+                continue;
+            }
+
+            let vertices = shape_box.side(direction);
+            push(
+                vertices,
+                box_tex_coords(direction, &vertices),
+                texture_callback(block, direction),
+            );
+        }
+    }
+}
+
+/// The post of a fence, and the rails to the neighbours it connects to
+fn fence_boxes<
+    T: 'static + Send + Sync,
+    C: Send + Sync + Fn(&T) -> Option<BlockDescriptor>,
+    const SIZE: usize,
+>(
+    chunk: &Chunk<T, SIZE>,
+    neighbours: &ChunkNeighbours<T, SIZE>,
+    describe_callback: &C,
+    position: &BlockOffset<SIZE>,
+) -> Vec<ShapeBox> {
+    let mut boxes = vec![BlockShape::FENCE_POST];
+    for direction in FaceDirection::ALL {
+        let rails = match BlockShape::fence_rails(direction) {
+            Some(rails) => rails,
+            None => continue,
+        };
+        let connects = neighbours
+            .block_next_to(chunk, position, direction)
+            .and_then(describe_callback)
+            .map_or(false, |neighbour_descriptor| {
+                neighbour_descriptor.shape == BlockShape::Fence
+                    || (neighbour_descriptor.is_standard_square()
+                        && !neighbour_descriptor.is_transparent)
+            });
+        if connects {
+            boxes.extend_from_slice(&rails);
+        }
+    }
+    boxes
+}
+
+fn is_side_hidden<
+    T: Send + Sync,
+    C: Send + Sync + Fn(&T) -> Option<BlockDescriptor>,
+    const SIZE: usize,
+>(
+    chunk: &Chunk<T, SIZE>,
+    neighbours: &ChunkNeighbours<T, SIZE>,
+    describe_callback: &C,
+    position: &BlockOffset<SIZE>,
+    boxes: &[ShapeBox],
+    index: usize,
+    shape_box: &ShapeBox,
+    direction: FaceDirection,
+) -> bool {
+    let covered_by_own_box = boxes.iter().enumerate().any(|(other_index, other)| {
+        other_index != index && shape_box.is_side_covered_by(direction, other)
+    });
+
+    let covered_by_neighbour = shape_box.touches_block_side(direction)
+        && neighbours
+            .block_next_to(chunk, position, direction)
+            .and_then(describe_callback)
+            .map_or(false, |neighbour_descriptor| {
+                neighbour_descriptor.is_standard_square() && !neighbour_descriptor.is_transparent
+            });
+
+    covered_by_own_box || covered_by_neighbour
+}
+
+/// Maps the texture on to the side of a box the same way it is mapped on to a full block, so a
+/// slab shows the lower half of the texture on its sides
+fn box_tex_coords(direction: FaceDirection, vertices: &[[f32; 3]; 4]) -> [[f32; 2]; 4] {
+    vertices.map(|[x, y, z]| match direction {
+        FaceDirection::North | FaceDirection::South => [x, y],
+        FaceDirection::West | FaceDirection::East => [z, y],
+        FaceDirection::Up | FaceDirection::Down => [x, z],
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::mesh::internal::shape_mesh::mesh_shape;
+    use crate::mesh::{BlockDescriptor, BlockShape, ChunkNeighbours, FaceDirection};
+    use crate::{BlockOffset, Chunk};
+
+    fn describe(value: &usize) -> Option<BlockDescriptor> {
+        match value {
+            1 => Some(BlockDescriptor {
+                shape: BlockShape::Cube,
+                is_transparent: false,
+            }),
+            2 => Some(BlockDescriptor {
+                shape: BlockShape::SLAB,
+                is_transparent: false,
+            }),
+            3 => Some(BlockDescriptor {
+                shape: BlockShape::STAIRS_SOUTH,
+                is_transparent: false,
+            }),
+            4 => Some(BlockDescriptor {
+                shape: BlockShape::Cross,
+                is_transparent: true,
+            }),
+            5 => Some(BlockDescriptor {
+                shape: BlockShape::Fence,
+                is_transparent: false,
+            }),
+            _ => None,
+        }
+    }
+
+    fn mesh(chunk: &Chunk<usize, 4>, position: BlockOffset<4>) -> (usize, usize) {
+        let mut shape_mesh = vec![];
+        let mut transparent_shape_mesh = vec![];
+        let block = chunk.get(&position);
+        mesh_shape(
+            chunk,
+            &ChunkNeighbours::new(),
+            &describe,
+            &|_, _| (),
+            &mut shape_mesh,
+            &mut transparent_shape_mesh,
+            &describe(block).unwrap(),
+            &position,
+            block,
+        );
+        (shape_mesh.len(), transparent_shape_mesh.len())
+    }
+
+    #[test]
+    fn it_should_cull_the_sides_of_a_slab_touching_full_blocks() {
+        let mut chunk = Chunk::<usize, 4>::default();
+        chunk.set(2, &(1, 1, 1).into());
+        assert_eq!(mesh(&chunk, (1, 1, 1).into()), (6, 0));
+
+        chunk.set(1, &(1, 0, 1).into());
+        chunk.set(1, &(1, 2, 1).into());
+        chunk.set(1, &(0, 1, 1).into());
+        // The top of the slab is below the block above it, so it is still visible
+        assert_eq!(mesh(&chunk, (1, 1, 1).into()), (4, 0));
+    }
+
+    #[test]
+    fn it_should_not_create_the_sides_between_the_boxes_of_a_block() {
+        let mut chunk = Chunk::<usize, 4>::default();
+        chunk.set(3, &(1, 1, 1).into());
+
+        assert_eq!(mesh(&chunk, (1, 1, 1).into()), (11, 0));
+    }
+
+    #[test]
+    fn it_should_create_both_sides_of_both_planes_for_a_cross() {
+        let mut chunk = Chunk::<usize, 4>::default();
+        chunk.set(4, &(1, 1, 1).into());
+        chunk.set(1, &(1, 0, 1).into());
+
+        assert_eq!(mesh(&chunk, (1, 1, 1).into()), (0, 4));
+    }
+
+    #[test]
+    fn it_should_turn_stairs_towards_the_given_direction() {
+        let mut chunk = Chunk::<usize, 4>::default();
+        chunk.set(3, &(1, 1, 1).into());
+        chunk.set(1, &(1, 1, 2).into());
+
+        // The high side of the stairs lies against the block to the south
+        assert_eq!(mesh(&chunk, (1, 1, 1).into()), (10, 0));
+        assert_eq!(
+            BlockShape::stairs(FaceDirection::South),
+            BlockShape::STAIRS_SOUTH
+        );
+        assert_ne!(
+            BlockShape::stairs(FaceDirection::North),
+            BlockShape::STAIRS_SOUTH
+        );
+    }
+
+    #[test]
+    fn it_should_connect_fences_to_fences_and_full_blocks() {
+        let mut chunk = Chunk::<usize, 4>::default();
+        chunk.set(5, &(1, 1, 1).into());
+        assert_eq!(mesh(&chunk, (1, 1, 1).into()), (6, 0));
+
+        chunk.set(5, &(2, 1, 1).into());
+        chunk.set(1, &(1, 1, 0).into());
+        chunk.set(2, &(0, 1, 1).into());
+
+        // Both rails to the fence have five visible sides. The rails to the full block lose their
+        // sides against the block, and nothing connects to the slab
+        assert_eq!(mesh(&chunk, (1, 1, 1).into()), (6 + 2 * 5 + 2 * 4, 0));
+    }
+}
@@ -1,8 +1,9 @@
-use crate::mesh::Face;
+use crate::mesh::{Face, ShapeFace};
 
 #[derive(Debug, Clone)]
 pub struct MeshResult<TE: Send + Sync, const SIZE: usize> {
     pub mesh: Vec<Face<TE, SIZE>>,
     pub transparent_mesh: Vec<Face<TE, SIZE>>,
-    pub unhandled: Vec<(usize, usize, usize)>,
+    pub shape_mesh: Vec<ShapeFace<TE, SIZE>>,
+    pub transparent_shape_mesh: Vec<ShapeFace<TE, SIZE>>,
 }
@@ -1,6 +1,8 @@
-use crate::mesh::internal::fast_mesh;
-use crate::mesh::internal::greedy_mesh;
-use crate::mesh::{BlockDescriptor, FaceDirection, MeshResult};
+use crate::mesh::internal::{
+    binary_greedy_mesh, binary_greedy_mesh_with_neighbours, fast_mesh, fast_mesh_with_neighbours,
+    greedy_mesh, greedy_mesh_with_neighbours,
+};
+use crate::mesh::{BlockDescriptor, ChunkNeighbours, FaceDirection, MeshResult};
 use crate::Chunk;
 use std::fmt::Debug;
 
@@ -16,6 +18,18 @@ pub trait MeshableChunk<T: Send + Sync, TE: Send + Sync, const SIZE: usize>: Sen
         texture_callback: TEC,
     ) -> MeshResult<TE, SIZE>;
 
+    /// Only performs quick culling, also culling the faces on the edges that are covered by the
+    /// neighbouring chunks
+    async fn fast_mesh_with_neighbours<
+        C: Send + Sync + Fn(&T) -> Option<BlockDescriptor>,
+        TEC: Send + Sync + Fn(&T, FaceDirection) -> TE,
+    >(
+        &self,
+        neighbours: &ChunkNeighbours<T, SIZE>,
+        describe_callback: C,
+        texture_callback: TEC,
+    ) -> MeshResult<TE, SIZE>;
+
     /// Applies a greedy mesh algorithm that gives a perfect mesh, might be way slower though
     async fn greedy_mesh<
         C: Send + Sync + Fn(&T) -> Option<BlockDescriptor>,
@@ -25,6 +39,41 @@ pub trait MeshableChunk<T: Send + Sync, TE: Send + Sync, const SIZE: usize>: Sen
         describe_callback: C,
         texture_callback: TEC,
     ) -> MeshResult<TE, SIZE>;
+
+    /// Applies a greedy mesh algorithm, also culling the faces on the edges that are covered by
+    /// the neighbouring chunks
+    async fn greedy_mesh_with_neighbours<
+        C: Send + Sync + Fn(&T) -> Option<BlockDescriptor>,
+        TEC: Send + Sync + Fn(&T, FaceDirection) -> TE,
+    >(
+        &self,
+        neighbours: &ChunkNeighbours<T, SIZE>,
+        describe_callback: C,
+        texture_callback: TEC,
+    ) -> MeshResult<TE, SIZE>;
+
+    /// Gives the same result as the greedy mesh, but works on whole rows of blocks at a time which
+    /// makes it a lot faster
+    async fn binary_greedy_mesh<
+        C: Send + Sync + Fn(&T) -> Option<BlockDescriptor>,
+        TEC: Send + Sync + Fn(&T, FaceDirection) -> TE,
+    >(
+        &self,
+        describe_callback: C,
+        texture_callback: TEC,
+    ) -> MeshResult<TE, SIZE>;
+
+    /// Gives the same result as the greedy mesh with neighbours, but works on whole rows of blocks
+    /// at a time
+    async fn binary_greedy_mesh_with_neighbours<
+        C: Send + Sync + Fn(&T) -> Option<BlockDescriptor>,
+        TEC: Send + Sync + Fn(&T, FaceDirection) -> TE,
+    >(
+        &self,
+        neighbours: &ChunkNeighbours<T, SIZE>,
+        describe_callback: C,
+        texture_callback: TEC,
+    ) -> MeshResult<TE, SIZE>;
 }
 
 #[async_trait::async_trait]
@@ -45,6 +94,18 @@ impl<
         fast_mesh(&self, describe_callback, texture_callback)
     }
 
+    async fn fast_mesh_with_neighbours<
+        C: Send + Sync + Fn(&T) -> Option<BlockDescriptor>,
+        TEC: Send + Sync + Fn(&T, FaceDirection) -> TE,
+    >(
+        &self,
+        neighbours: &ChunkNeighbours<T, SIZE>,
+        describe_callback: C,
+        texture_callback: TEC,
+    ) -> MeshResult<TE, SIZE> {
+        fast_mesh_with_neighbours(&self, neighbours, describe_callback, texture_callback)
+    }
+
     async fn greedy_mesh<
         C: Send + Sync + Fn(&T) -> Option<BlockDescriptor>,
         TEC: Send + Sync + Fn(&T, FaceDirection) -> TE,
@@ -55,6 +116,41 @@ impl<
     ) -> MeshResult<TE, SIZE> {
         greedy_mesh(&self, describe_callback, texture_callback)
     }
+
+    async fn greedy_mesh_with_neighbours<
+        C: Send + Sync + Fn(&T) -> Option<BlockDescriptor>,
+        TEC: Send + Sync + Fn(&T, FaceDirection) -> TE,
+    >(
+        &self,
+        neighbours: &ChunkNeighbours<T, SIZE>,
+        describe_callback: C,
+        texture_callback: TEC,
+    ) -> MeshResult<TE, SIZE> {
+        greedy_mesh_with_neighbours(&self, neighbours, describe_callback, texture_callback)
+    }
+
+    async fn binary_greedy_mesh<
+        C: Send + Sync + Fn(&T) -> Option<BlockDescriptor>,
+        TEC: Send + Sync + Fn(&T, FaceDirection) -> TE,
+    >(
+        &self,
+        describe_callback: C,
+        texture_callback: TEC,
+    ) -> MeshResult<TE, SIZE> {
+        binary_greedy_mesh(&self, describe_callback, texture_callback)
+    }
+
+    async fn binary_greedy_mesh_with_neighbours<
+        C: Send + Sync + Fn(&T) -> Option<BlockDescriptor>,
+        TEC: Send + Sync + Fn(&T, FaceDirection) -> TE,
+    >(
+        &self,
+        neighbours: &ChunkNeighbours<T, SIZE>,
+        describe_callback: C,
+        texture_callback: TEC,
+    ) -> MeshResult<TE, SIZE> {
+        binary_greedy_mesh_with_neighbours(&self, neighbours, describe_callback, texture_callback)
+    }
 }
 
 #[cfg(test)]
@@ -67,7 +163,7 @@ mod tests {
         let chunk = Chunk::<usize, 4>::default();
 
         let _res = chunk
-            .fast_mesh(|_block_id| Option::<BlockDescriptor>::None)
+            .fast_mesh(|_block_id| Option::<BlockDescriptor>::None, |_, _| ())
             .await;
     }
 }
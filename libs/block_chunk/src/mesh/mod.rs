@@ -1,18 +1,30 @@
 //! This module contain some utility methods regarding meshing
 
 mod block_descriptor;
+mod block_shape;
+mod chunk_neighbours;
 mod corner;
 mod face;
 mod face_direction;
 mod internal;
 mod mesh_result;
 mod meshable_chunk;
+mod shape_box;
+mod shape_face;
 
 pub use self::block_descriptor::BlockDescriptor;
+pub use self::block_shape::BlockShape;
+pub use self::chunk_neighbours::ChunkNeighbours;
 pub use self::corner::Corner;
 pub use self::face::Face;
 pub use self::face_direction::FaceDirection;
+pub use self::internal::binary_greedy_mesh;
+pub use self::internal::binary_greedy_mesh_with_neighbours;
 pub use self::internal::fast_mesh;
+pub use self::internal::fast_mesh_with_neighbours;
 pub use self::internal::greedy_mesh;
+pub use self::internal::greedy_mesh_with_neighbours;
 pub use self::mesh_result::MeshResult;
 pub use self::meshable_chunk::MeshableChunk;
+pub use self::shape_box::ShapeBox;
+pub use self::shape_face::ShapeFace;
@@ -0,0 +1,126 @@
+use crate::mesh::FaceDirection;
+
+/// An axis aligned box inside of a block, measured in fractions of a block. A box going from
+/// `[0.0; 3]` to `[1.0; 3]` fills the whole block
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ShapeBox {
+    pub min: [f32; 3],
+    pub max: [f32; 3],
+}
+
+impl ShapeBox {
+    pub const FULL: Self = Self::new([0.0; 3], [1.0; 3]);
+
+    #[must_use]
+    pub const fn new(min: [f32; 3], max: [f32; 3]) -> Self {
+        Self { min, max }
+    }
+
+    /// Whether the side of the box facing the given direction lies on the side of the block
+    #[must_use]
+    pub fn touches_block_side(&self, direction: FaceDirection) -> bool {
+        let (axis, positive) = axis_of(direction);
+        if positive {
+            self.max[axis] >= 1.0
+        } else {
+            self.min[axis] <= 0.0
+        }
+    }
+
+    /// Whether the side of the box facing the given direction is completely hidden behind the
+    /// other box
+    #[must_use]
+    pub fn is_side_covered_by(&self, direction: FaceDirection, other: &Self) -> bool {
+        let (axis, positive) = axis_of(direction);
+        let touches = if positive {
+            (other.min[axis] - self.max[axis]).abs() < f32::EPSILON
+        } else {
+            (other.max[axis] - self.min[axis]).abs() < f32::EPSILON
+        };
+
+        touches
+            && (0..3)
+                .filter(|other_axis| *other_axis != axis)
+                .all(|other_axis| {
+                    other.min[other_axis] <= self.min[other_axis]
+                        && other.max[other_axis] >= self.max[other_axis]
+                })
+    }
+
+    /// The corners of the side facing the given direction, in counter clockwise order when looking
+    /// at the side from the outside
+    #[must_use]
+    pub const fn side(&self, direction: FaceDirection) -> [[f32; 3]; 4] {
+        let [x0, y0, z0] = self.min;
+        let [x1, y1, z1] = self.max;
+
+        match direction {
+            FaceDirection::North => [[x0, y0, z0], [x0, y1, z0], [x1, y1, z0], [x1, y0, z0]],
+            FaceDirection::South => [[x0, y0, z1], [x1, y0, z1], [x1, y1, z1], [x0, y1, z1]],
+            FaceDirection::West => [[x0, y0, z0], [x0, y0, z1], [x0, y1, z1], [x0, y1, z0]],
+            FaceDirection::East => [[x1, y0, z0], [x1, y1, z0], [x1, y1, z1], [x1, y0, z1]],
+            FaceDirection::Up => [[x0, y1, z0], [x0, y1, z1], [x1, y1, z1], [x1, y1, z0]],
+            FaceDirection::Down => [[x0, y0, z0], [x1, y0, z0], [x1, y0, z1], [x0, y0, z1]],
+        }
+    }
+}
+
+/// The axis a direction points along, and whether it points towards the positive end of it
+const fn axis_of(direction: FaceDirection) -> (usize, bool) {
+    match direction {
+        FaceDirection::West => (0, false),
+        FaceDirection::East => (0, true),
+        FaceDirection::Down => (1, false),
+        FaceDirection::Up => (1, true),
+        FaceDirection::North => (2, false),
+        FaceDirection::South => (2, true),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::mesh::{FaceDirection, ShapeBox};
+
+    #[test]
+    fn it_should_know_which_sides_touches_the_block() {
+        let slab = ShapeBox::new([0.0; 3], [1.0, 0.5, 1.0]);
+
+        assert!(slab.touches_block_side(FaceDirection::Down));
+        assert!(slab.touches_block_side(FaceDirection::North));
+        assert!(!slab.touches_block_side(FaceDirection::Up));
+    }
+
+    #[test]
+    fn it_should_only_be_covered_by_boxes_that_hides_the_whole_side() {
+        let lower = ShapeBox::new([0.0; 3], [1.0, 0.5, 0.5]);
+        let upper = ShapeBox::new([0.0, 0.0, 0.5], [1.0; 3]);
+
+        assert!(lower.is_side_covered_by(FaceDirection::South, &upper));
+        assert!(!upper.is_side_covered_by(FaceDirection::North, &lower));
+        assert!(!lower.is_side_covered_by(FaceDirection::Up, &upper));
+    }
+
+    #[test]
+    fn it_should_point_every_side_of_a_box_outwards() {
+        let shape_box = ShapeBox::FULL;
+        for direction in FaceDirection::ALL {
+            let [a, b, c, _] = shape_box.side(direction);
+            let u = [b[0] - a[0], b[1] - a[1], b[2] - a[2]];
+            let v = [c[0] - a[0], c[1] - a[1], c[2] - a[2]];
+            let normal = [
+                u[1] * v[2] - u[2] * v[1],
+                u[2] * v[0] - u[0] * v[2],
+                u[0] * v[1] - u[1] * v[0],
+            ];
+            let expected = match direction {
+                FaceDirection::North => [0.0, 0.0, -1.0],
+                FaceDirection::South => [0.0, 0.0, 1.0],
+                FaceDirection::West => [-1.0, 0.0, 0.0],
+                FaceDirection::East => [1.0, 0.0, 0.0],
+                FaceDirection::Up => [0.0, 1.0, 0.0],
+                FaceDirection::Down => [0.0, -1.0, 0.0],
+            };
+            assert_eq!(normal, expected, "{:?}", direction);
+        }
+    }
+}
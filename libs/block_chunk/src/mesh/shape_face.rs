@@ -0,0 +1,20 @@
+use crate::BlockOffset;
+
+/// A face of a block that is not a full cube. Unlike [`Face`](crate::mesh::Face) it can have any
+/// size and rotation, and is never merged with other faces
+#[derive(Debug, Clone, PartialEq)]
+pub struct ShapeFace<TE, const SIZE: usize> {
+    /// The block this face belongs to
+    pub position: BlockOffset<SIZE>,
+
+    /// The corners of the face relative to the block, in counter clockwise order when looking at
+    /// the front of the face
+    pub vertices: [[f32; 3]; 4],
+
+    /// The texture coordinates for each corner
+    pub tex_coords: [[f32; 2]; 4],
+
+    pub texture: TE,
+
+    pub is_transparent: bool,
+}
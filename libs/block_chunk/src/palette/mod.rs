@@ -0,0 +1,4 @@
+mod packed_indices;
+mod palette_storage;
+
+pub use self::palette_storage::PaletteStorage;
@@ -0,0 +1,228 @@
+use bincode::de::Decoder;
+use bincode::error::DecodeError;
+use bincode::Decode;
+
+/// A fixed length list of small unsigned integers, packed tightly in to 64 bit words.
+///
+/// Every index takes up the same amount of bits. Indices never span two words, so a few bits at
+/// the end of each word might go unused
+#[derive(Clone, Debug, Eq, PartialEq, bincode::Encode)]
+pub struct PackedIndices {
+    bits: u32,
+    len: usize,
+    words: Vec<u64>,
+}
+
+impl PackedIndices {
+    pub const MAX_BITS: u32 = 32;
+
+    /// Creates a list where every index is zero
+    ///
+    /// # Panics
+    /// If `bits` is zero or more than [`PackedIndices::MAX_BITS`], or if the amount of words for
+    /// `len` indices does not fit in a `usize`
+    pub fn new(bits: u32, len: usize) -> Self {
+        assert!(bits > 0 && bits <= Self::MAX_BITS);
+
+        Self {
+            bits,
+            len,
+            words: vec![0; word_count(bits, len).expect("Too many indices")],
+        }
+    }
+
+    /// Decodes a list that was encoded with bincode, which has to hold exactly `len` indices. Every
+    /// length is checked before anything is allocated for it, as the data might not be trusted
+    ///
+    /// # Errors
+    /// If the list could not be decoded, or does not make sense
+    pub fn decode_with_len<D: Decoder>(decoder: &mut D, len: usize) -> Result<Self, DecodeError> {
+        let bits = u32::decode(decoder)?;
+        if bits == 0 || bits > Self::MAX_BITS {
+            return Err(DecodeError::OtherString(format!(
+                "Indices can not take up {} bits",
+                bits
+            )));
+        }
+        let stored_len = usize::decode(decoder)?;
+        if stored_len != len {
+            return Err(DecodeError::OtherString(format!(
+                "Expected {} indices, found {}",
+                len, stored_len
+            )));
+        }
+        let word_count = usize::decode(decoder)?;
+        if Some(word_count) != self::word_count(bits, len) {
+            return Err(DecodeError::OtherString(format!(
+                "{} words do not fit {} indices of {} bits",
+                word_count, len, bits
+            )));
+        }
+
+        let mut words = Vec::with_capacity(word_count);
+        for _ in 0..word_count {
+            words.push(u64::decode(decoder)?);
+        }
+        Ok(Self { bits, len, words })
+    }
+
+    pub const fn bits(&self) -> u32 {
+        self.bits
+    }
+
+    pub const fn len(&self) -> usize {
+        self.len
+    }
+
+    /// The largest index that can be stored with the current amount of bits
+    pub const fn max_value(&self) -> usize {
+        (1 << self.bits) - 1
+    }
+
+    #[inline]
+    pub fn get(&self, index: usize) -> usize {
+        debug_assert!(index < self.len);
+
+        let (word, shift) = self.locate(index);
+        ((self.words[word] >> shift) & self.mask()) as usize
+    }
+
+    #[inline]
+    pub fn set(&mut self, index: usize, value: usize) {
+        debug_assert!(index < self.len);
+        debug_assert!(value <= self.max_value());
+
+        let (word, shift) = self.locate(index);
+        let mask = self.mask();
+        self.words[word] = (self.words[word] & !(mask << shift)) | ((value as u64 & mask) << shift);
+    }
+
+    /// Copies every index over to a new list that uses the given amount of bits per index
+    ///
+    /// # Panics
+    /// If `bits` is zero or more than [`PackedIndices::MAX_BITS`]
+    pub fn with_bits(&self, bits: u32) -> Self {
+        let mut indices = Self::new(bits, self.len);
+        for index in 0..self.len {
+            indices.set(index, self.get(index));
+        }
+        indices
+    }
+
+    /// The amount of heap memory used by the list
+    pub fn byte_size(&self) -> usize {
+        self.words.capacity() * std::mem::size_of::<u64>()
+    }
+
+    /// Whether the list makes sense. Lists that has been decoded from untrusted data should be
+    /// checked before they are used
+    pub fn is_valid(&self) -> bool {
+        self.bits > 0
+            && self.bits <= Self::MAX_BITS
+            && Some(self.words.len()) == word_count(self.bits, self.len)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = usize> + '_ {
+        (0..self.len).map(|index| self.get(index))
+    }
+
+    const fn mask(&self) -> u64 {
+        (1 << self.bits) - 1
+    }
+
+    const fn locate(&self, index: usize) -> (usize, usize) {
+        let per_word = indices_per_word(self.bits);
+        (index / per_word, (index % per_word) * self.bits as usize)
+    }
+}
+
+const fn indices_per_word(bits: u32) -> usize {
+    (u64::BITS / bits) as usize
+}
+
+/// The amount of words that `len` indices of `bits` bits take up, unless that does not fit in a
+/// `usize`
+const fn word_count(bits: u32, len: usize) -> Option<usize> {
+    let per_word = indices_per_word(bits);
+    match len.checked_add(per_word - 1) {
+        Some(rounded_up) => Some(rounded_up / per_word),
+        None => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::palette::packed_indices::PackedIndices;
+    use bincode::config;
+    use bincode::de::read::SliceReader;
+    use bincode::de::DecoderImpl;
+
+    #[test]
+    fn it_should_get_and_set_without_touching_neighbours() {
+        let mut indices = PackedIndices::new(3, 100);
+        for index in 0..100 {
+            indices.set(index, index % 8);
+        }
+        indices.set(50, 0);
+
+        for index in 0..100 {
+            let expected = if index == 50 { 0 } else { index % 8 };
+            assert_eq!(indices.get(index), expected);
+        }
+    }
+
+    #[test]
+    fn it_should_keep_its_values_when_growing() {
+        let mut indices = PackedIndices::new(1, 65);
+        indices.set(0, 1);
+        indices.set(64, 1);
+
+        let indices = indices.with_bits(5);
+        assert!(indices.is_valid());
+
+        assert_eq!(indices.bits(), 5);
+        assert_eq!(indices.get(0), 1);
+        assert_eq!(indices.get(1), 0);
+        assert_eq!(indices.get(64), 1);
+    }
+
+    #[test]
+    fn it_should_decode_what_was_encoded() {
+        let mut indices = PackedIndices::new(3, 100);
+        indices.set(42, 5);
+        let bytes = bincode::encode_to_vec(&indices, config::standard()).unwrap();
+
+        let mut decoder = DecoderImpl::new(SliceReader::new(&bytes), config::standard());
+        let read = PackedIndices::decode_with_len(&mut decoder, 100).unwrap();
+
+        assert_eq!(read, indices);
+    }
+
+    #[test]
+    fn it_should_not_decode_lists_that_do_not_make_sense() {
+        let valid = || PackedIndices::new(3, 100);
+        let too_many_bits = PackedIndices {
+            bits: 33,
+            ..valid()
+        };
+        let too_many_words = PackedIndices {
+            words: vec![0; 1000],
+            ..valid()
+        };
+        let overflowing = PackedIndices {
+            len: usize::MAX,
+            ..valid()
+        };
+
+        for (indices, len) in [
+            (valid(), 99),
+            (too_many_bits, 100),
+            (too_many_words, 100),
+            (overflowing, usize::MAX),
+        ] {
+            let bytes = bincode::encode_to_vec(&indices, config::standard()).unwrap();
+            let mut decoder = DecoderImpl::new(SliceReader::new(&bytes), config::standard());
+            assert!(PackedIndices::decode_with_len(&mut decoder, len).is_err());
+        }
+    }
+}
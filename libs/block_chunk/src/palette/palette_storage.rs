@@ -0,0 +1,320 @@
+use crate::palette::packed_indices::PackedIndices;
+use bincode::de::Decoder;
+use bincode::enc::Encoder;
+use bincode::error::{DecodeError, EncodeError};
+use bincode::{Decode, Encode};
+use std::mem;
+
+const UNIFORM: u8 = 0;
+const PALETTE: u8 = 1;
+
+/// A fixed length list of values, stored as a palette of the distinct values together with a
+/// packed index in to that palette for each position.
+///
+/// A list where every value is the same, like a chunk of nothing but air, is stored as that single
+/// value. The palette only grows as new values are introduced, and the list collapses back to a
+/// single value once every position holds the same value again
+#[derive(Clone, Debug)]
+pub enum PaletteStorage<T> {
+    Uniform {
+        value: T,
+        len: usize,
+    },
+    Palette {
+        entries: Vec<T>,
+        /// The amount of positions that uses each entry. Unused entries are reused for new values
+        ref_counts: Vec<u32>,
+        indices: PackedIndices,
+    },
+}
+
+impl<T> PaletteStorage<T> {
+    pub const fn new(value: T, len: usize) -> Self {
+        Self::Uniform { value, len }
+    }
+
+    pub const fn len(&self) -> usize {
+        match self {
+            Self::Uniform { len, .. } => *len,
+            Self::Palette { indices, .. } => indices.len(),
+        }
+    }
+
+    pub const fn is_uniform(&self) -> bool {
+        matches!(self, Self::Uniform { .. })
+    }
+
+    #[inline]
+    pub fn get(&self, index: usize) -> &T {
+        match self {
+            Self::Uniform { value, .. } => value,
+            Self::Palette {
+                entries, indices, ..
+            } => &entries[indices.get(index)],
+        }
+    }
+
+    /// The amount of heap memory used by the storage, not counting any memory owned by the values
+    /// themselves
+    pub fn byte_size(&self) -> usize {
+        match self {
+            Self::Uniform { .. } => 0,
+            Self::Palette {
+                entries,
+                ref_counts,
+                indices,
+            } => {
+                entries.capacity() * mem::size_of::<T>()
+                    + ref_counts.capacity() * mem::size_of::<u32>()
+                    + indices.byte_size()
+            }
+        }
+    }
+
+    /// Decodes a storage that was encoded with bincode, which has to hold exactly `len` values.
+    /// The values are decoded with `decode_value`. Every length is checked before anything is
+    /// allocated for it, as the data might not be trusted
+    ///
+    /// # Errors
+    /// If the storage could not be decoded, or does not make sense
+    pub fn decode_with_len<D: Decoder, F: FnMut(&mut D) -> Result<T, DecodeError>>(
+        decoder: &mut D,
+        len: usize,
+        mut decode_value: F,
+    ) -> Result<Self, DecodeError> {
+        match u8::decode(decoder)? {
+            UNIFORM => {
+                let stored_len = usize::decode(decoder)?;
+                if stored_len != len {
+                    return Err(DecodeError::OtherString(format!(
+                        "Expected {} values, found {}",
+                        len, stored_len
+                    )));
+                }
+                Ok(Self::new(decode_value(decoder)?, len))
+            }
+            PALETTE => {
+                let entry_count = usize::decode(decoder)?;
+                if entry_count > len {
+                    return Err(DecodeError::OtherString(format!(
+                        "A palette for {} values can not have {} entries",
+                        len, entry_count
+                    )));
+                }
+                let mut entries = Vec::with_capacity(entry_count);
+                for _ in 0..entry_count {
+                    entries.push(decode_value(decoder)?);
+                }
+                Self::from_parts(entries, PackedIndices::decode_with_len(decoder, len)?)
+            }
+            kind => Err(DecodeError::OtherString(format!(
+                "Unknown palette storage kind {}",
+                kind
+            ))),
+        }
+    }
+
+    fn from_parts(entries: Vec<T>, indices: PackedIndices) -> Result<Self, DecodeError> {
+        if !indices.is_valid() || entries.is_empty() || entries.len() > indices.max_value() + 1 {
+            return Err(DecodeError::OtherString(
+                "Palette does not match its indices".to_string(),
+            ));
+        }
+
+        let mut ref_counts = vec![0_u32; entries.len()];
+        for index in indices.iter() {
+            match ref_counts.get_mut(index) {
+                Some(count) => *count += 1,
+                None => {
+                    return Err(DecodeError::OtherString(format!(
+                        "Palette index {} is out of range",
+                        index
+                    )))
+                }
+            }
+        }
+
+        Ok(Self::Palette {
+            entries,
+            ref_counts,
+            indices,
+        })
+    }
+}
+
+impl<T: Clone + PartialEq> PaletteStorage<T> {
+    /// Sets the value at the given position, returning the value that was there before
+    pub fn set(&mut self, index: usize, value: T) -> T {
+        match self {
+            Self::Uniform {
+                value: uniform_value,
+                len,
+            } => {
+                if *uniform_value == value {
+                    return value;
+                }
+
+                let mut indices = PackedIndices::new(1, *len);
+                indices.set(index, 1);
+                let previous = uniform_value.clone();
+                *self = Self::Palette {
+                    entries: vec![previous.clone(), value],
+                    ref_counts: vec![(indices.len() - 1) as u32, 1],
+                    indices,
+                };
+                previous
+            }
+            Self::Palette {
+                entries,
+                ref_counts,
+                indices,
+            } => {
+                let previous_entry = indices.get(index);
+                if entries[previous_entry] == value {
+                    return value;
+                }
+                let previous = entries[previous_entry].clone();
+                ref_counts[previous_entry] -= 1;
+
+                let entry = match entries.iter().position(|entry| *entry == value) {
+                    Some(entry) => entry,
+                    None => match ref_counts.iter().position(|count| *count == 0) {
+                        Some(entry) => {
+                            entries[entry] = value;
+                            entry
+                        }
+                        None => {
+                            entries.push(value);
+                            ref_counts.push(0);
+                            if entries.len() > indices.max_value() + 1 {
+                                *indices = indices.with_bits(indices.bits() + 1);
+                            }
+                            entries.len() - 1
+                        }
+                    },
+                };
+
+                ref_counts[entry] += 1;
+                indices.set(index, entry);
+
+                if ref_counts[entry] as usize == indices.len() {
+                    *self = Self::new(entries[entry].clone(), indices.len());
+                }
+
+                previous
+            }
+        }
+    }
+}
+
+impl<T: 'static + Encode> Encode for PaletteStorage<T> {
+    fn encode<E: Encoder>(&self, encoder: &mut E) -> Result<(), EncodeError> {
+        match self {
+            Self::Uniform { value, len } => {
+                UNIFORM.encode(encoder)?;
+                len.encode(encoder)?;
+                value.encode(encoder)
+            }
+            Self::Palette {
+                entries, indices, ..
+            } => {
+                PALETTE.encode(encoder)?;
+                entries.encode(encoder)?;
+                indices.encode(encoder)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::palette::palette_storage::PaletteStorage;
+    use bincode::config;
+    use bincode::de::read::SliceReader;
+    use bincode::de::DecoderImpl;
+    use bincode::error::DecodeError;
+    use bincode::Decode;
+
+    fn decode(bytes: &[u8], len: usize) -> Result<PaletteStorage<u32>, DecodeError> {
+        let mut decoder = DecoderImpl::new(SliceReader::new(bytes), config::standard());
+        PaletteStorage::decode_with_len(&mut decoder, len, u32::decode)
+    }
+
+    #[test]
+    fn it_should_start_out_uniform() {
+        let storage = PaletteStorage::new(0_u32, 64);
+
+        assert!(storage.is_uniform());
+        assert_eq!(storage.byte_size(), 0);
+        assert_eq!(*storage.get(63), 0);
+    }
+
+    #[test]
+    fn it_should_grow_the_palette_as_new_values_are_set() {
+        let mut storage = PaletteStorage::new(0_u32, 64);
+        for index in 0..64 {
+            assert_eq!(storage.set(index, index as u32), 0);
+        }
+
+        assert!(!storage.is_uniform());
+        for index in 0..64 {
+            assert_eq!(*storage.get(index), index as u32);
+        }
+    }
+
+    #[test]
+    fn it_should_collapse_when_every_value_is_the_same() {
+        let mut storage = PaletteStorage::new(0_u32, 64);
+        storage.set(10, 1);
+        storage.set(20, 2);
+        assert!(!storage.is_uniform());
+
+        for index in 0..64 {
+            storage.set(index, 3);
+        }
+
+        assert!(storage.is_uniform());
+        assert_eq!(*storage.get(10), 3);
+    }
+
+    #[test]
+    fn it_should_reuse_unused_entries() {
+        let mut storage = PaletteStorage::new(0_u32, 64);
+        storage.set(0, 1);
+        storage.set(0, 2);
+
+        match &storage {
+            PaletteStorage::Palette { entries, .. } => assert_eq!(entries, &vec![0, 2]),
+            PaletteStorage::Uniform { .. } => panic!("Storage should not be uniform"),
+        }
+    }
+
+    #[test]
+    fn it_should_reject_indices_outside_of_the_palette() {
+        let mut storage = PaletteStorage::new(0_u32, 64);
+        storage.set(2, 1);
+
+        // Pretend the data was corrupted, leaving an index that points past the palette
+        if let PaletteStorage::Palette { entries, .. } = &mut storage {
+            entries.truncate(1);
+        }
+        let bytes = bincode::encode_to_vec(&storage, config::standard()).unwrap();
+
+        assert!(decode(&bytes, 64).is_err());
+    }
+
+    #[test]
+    fn it_should_reject_palettes_larger_than_the_storage() {
+        let mut storage = PaletteStorage::new(0_u32, 64);
+        storage.set(2, 1);
+        let bytes = bincode::encode_to_vec(&storage, config::standard()).unwrap();
+        assert!(decode(&bytes, 64).is_ok());
+
+        // The kind, followed by a palette length that takes the varint's 8 byte form
+        let mut huge = vec![1, 253];
+        huge.extend_from_slice(&(1_u64 << 40).to_le_bytes());
+
+        assert!(decode(&bytes, 1).is_err());
+        assert!(decode(&huge, 64).is_err());
+    }
+}
@@ -30,6 +30,10 @@ impl Mod for OverWorldDimensionMod {
         "Over World Dimension"
     }
 
+    fn version(&self) -> &str {
+        env!("CARGO_PKG_VERSION")
+    }
+
     async fn register_dimensions(&self, seed: u128) -> Vec<Arc<dyn Dimension>> {
         vec![Arc::new(OverWorldDimension::new(seed)) as Arc<dyn Dimension>]
     }
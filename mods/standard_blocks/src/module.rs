@@ -24,6 +24,10 @@ impl Mod for StandardBlocksMod {
         "Standard Blocks"
     }
 
+    fn version(&self) -> &str {
+        env!("CARGO_PKG_VERSION")
+    }
+
     async fn register_blocks(&self) -> Vec<Arc<dyn Block>> {
         vec![Arc::new(Dirt::new()), Arc::new(Stone::new())]
     }
@@ -1,3 +0,0 @@
-mod block;
-
-pub use self::block::Block;
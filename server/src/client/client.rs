@@ -1,23 +1,29 @@
 use crate::event::WorldEvent;
 use crate::Chunk;
+use crate::CHUNK_SIZE;
 use cgmath::Deg;
 use std::error::Error;
 use std::future::Future;
 use std::sync::Arc;
 use tokio::sync::{broadcast, OwnedRwLockReadGuard};
 use uuid::Uuid;
+use voxelcraft_core::block::BlockPosition;
 use voxelcraft_core::chunk::ChunkPosition;
 use voxelcraft_core::entity::EntityPosition;
+use voxelcraft_id::BlockId;
 
 #[async_trait::async_trait]
 pub trait Client {
     async fn get_world_event_receiver(&self) -> broadcast::Receiver<WorldEvent>;
     /// Tells the server that you are about to join the world
-    async fn begin_joining_world(&self);
+    async fn begin_joining_world(&self) -> Result<(), Box<dyn Error + Send + Sync>>;
 
     /// Tells the server that you are ready to play
     async fn join_world(&self);
 
+    /// Tells the server that you are leaving the world, so that your player can be saved
+    async fn leave_world(&self) -> Result<(), Box<dyn Error + Send + Sync>>;
+
     /// You position
     async fn position(&self) -> EntityPosition;
 
@@ -31,6 +37,23 @@ pub trait Client {
         callback: C,
     ) -> Result<R, Box<dyn Error + Send + Sync>>;
 
+    async fn get_block(
+        &self,
+        position: &BlockPosition<CHUNK_SIZE>,
+    ) -> Result<BlockId, Box<dyn Error + Send + Sync>>;
+
+    /// Replaces a block, returning the block that was there before
+    async fn set_block(
+        &self,
+        position: &BlockPosition<CHUNK_SIZE>,
+        block_id: BlockId,
+    ) -> Result<BlockId, Box<dyn Error + Send + Sync>>;
+
+    async fn set_blocks(
+        &self,
+        blocks: Vec<(BlockPosition<CHUNK_SIZE>, BlockId)>,
+    ) -> Result<(), Box<dyn Error + Send + Sync>>;
+
     async fn start_move_forward(&self);
     async fn start_move_backward(&self);
     async fn start_move_right(&self);
@@ -43,6 +66,7 @@ pub trait Client {
     async fn stop_move_left(&self);
     async fn stop_jump(&self);
     async fn stop_sneak(&self);
+    async fn set_flying(&self, is_flying: bool);
 
     async fn set_pitch_yaw(&self, pitch: Deg<f32>, yaw: Deg<f32>);
 
@@ -1,3 +1,5 @@
 mod player;
+mod player_data;
 
 pub use self::player::Player;
+pub use self::player_data::PlayerData;
@@ -1,9 +1,37 @@
+use crate::entity::PlayerData;
 use crate::event::WorldEvent;
-use cgmath::{vec3, Array, Deg, Euler, Matrix4, Quaternion, Rotation, Vector3, VectorSpace};
+use crate::CHUNK_SIZE;
+use cgmath::{vec3, Deg, Euler, InnerSpace, Quaternion, Rotation, Vector3, VectorSpace};
+use std::error::Error;
 use tokio::sync::broadcast;
 use uuid::Uuid;
-use voxelcraft_core::entity::EntityPosition;
-use voxelcraft_mod::{Entity, LivingEntity};
+use voxelcraft_core::chunk::ChunkPosition;
+use voxelcraft_core::collision::{stop_at_ledges, sweep, BoundingBox};
+use voxelcraft_core::entity::{EntityOffset, EntityPosition};
+use voxelcraft_mod::{CollisionWorld, Entity, LivingEntity};
+
+const WIDTH: f64 = 0.6;
+/// How far the feet are below the position of the player, which is where the eyes are
+const EYE_HEIGHT: f64 = 1.62;
+/// How far the top of the head is above the eyes
+const HEAD_HEIGHT: f64 = 0.18;
+
+/// In blocks per second, squared
+const GRAVITY: f32 = 32.0;
+/// The fastest the player can fall, in blocks per second
+const TERMINAL_VELOCITY: f32 = 78.4;
+/// The upwards velocity given by a jump, which is enough to jump up on a block
+const JUMP_VELOCITY: f32 = 8.4;
+const WALKING_SPEED: f32 = 4.3;
+const SNEAKING_SPEED: f32 = 1.3;
+const FLYING_SPEED: f32 = 10.9;
+/// How big part of the difference to the wanted velocity is made up per second, while standing on
+/// a block with a friction of 1.0
+const GROUND_ACCELERATION: f32 = 12.0;
+const AIR_ACCELERATION: f32 = 2.0;
+const FLYING_ACCELERATION: f32 = 8.0;
+/// Sneaking keeps the player from walking off any ledge deeper than this
+const MAX_SNEAKING_DROP: f64 = 0.6;
 
 #[derive(Debug)]
 pub struct Player {
@@ -20,6 +48,8 @@ pub struct Player {
     is_moving_right: bool,
     is_jumping: bool,
     is_sneaking: bool,
+    is_flying: bool,
+    is_grounded: bool,
 }
 
 impl Player {
@@ -36,6 +66,8 @@ impl Player {
         let is_moving_right = false;
         let is_jumping = false;
         let is_sneaking = false;
+        let is_flying = false;
+        let is_grounded = false;
         let velocity = vec3(0.0, 0.0, 0.0);
         Self {
             id,
@@ -50,6 +82,52 @@ impl Player {
             is_moving_right,
             is_jumping,
             is_sneaking,
+            is_flying,
+            is_grounded,
+        }
+    }
+
+    /// Restores a player from the state kept between sessions
+    pub fn from_data(
+        id: Uuid,
+        data: &PlayerData,
+        world_event_sender: broadcast::Sender<WorldEvent>,
+    ) -> Self {
+        let position = EntityPosition {
+            chunk_position: ChunkPosition {
+                x: data.chunk_position[0],
+                y: data.chunk_position[1],
+                z: data.chunk_position[2],
+                dimension: data.dimension,
+            },
+            offset: EntityOffset {
+                x: data.offset[0],
+                y: data.offset[1],
+                z: data.offset[2],
+            },
+        };
+
+        let mut player = Self::new(id, position, world_event_sender);
+        player.pitch = Deg(data.pitch);
+        player.yaw = Deg(data.yaw);
+        player.velocity = Vector3::from(data.velocity);
+        player.is_flying = data.is_flying;
+        player
+    }
+
+    /// The state of the player that should be kept between sessions
+    pub fn data(&self) -> PlayerData {
+        let chunk_position = &self.position.chunk_position;
+        let offset = &self.position.offset;
+        PlayerData {
+            format_version: PlayerData::FORMAT_VERSION,
+            dimension: chunk_position.dimension,
+            chunk_position: [chunk_position.x, chunk_position.y, chunk_position.z],
+            offset: [offset.x, offset.y, offset.z],
+            pitch: self.pitch.0,
+            yaw: self.yaw.0,
+            velocity: self.velocity.into(),
+            is_flying: self.is_flying,
         }
     }
 
@@ -106,122 +184,431 @@ impl Player {
         self.yaw = yaw;
     }
 
-    fn get_max_horizontal_velocity(&self) -> f32 {
-        10.0
+    pub fn set_flying(&mut self, is_flying: bool) {
+        self.is_flying = is_flying;
     }
 
-    fn get_next_forward_velocity(&self, delta: f64) -> Vector3<f32> {
-        if self.is_moving_forward {
-            vec3(0.0, 0.0, 64.0 * delta as f32)
-        } else {
-            vec3(0.0, 0.0, 0.0)
-        }
+    pub const fn is_flying(&self) -> bool {
+        self.is_flying
     }
 
-    fn get_next_backward_velocity(&self, delta: f64) -> Vector3<f32> {
-        if self.is_moving_backward {
-            vec3(0.0, 0.0, -64.0 * delta as f32)
-        } else {
-            vec3(0.0, 0.0, 0.0)
-        }
+    pub const fn is_grounded(&self) -> bool {
+        self.is_grounded
     }
 
-    fn get_next_right_velocity(&self, delta: f64) -> Vector3<f32> {
-        if self.is_moving_right {
-            vec3(64.0 * delta as f32, 0.0, 0.0)
-        } else {
-            vec3(0.0, 0.0, 0.0)
-        }
+    pub const fn velocity(&self) -> Vector3<f32> {
+        self.velocity
     }
 
-    fn get_next_left_velocity(&self, delta: f64) -> Vector3<f32> {
+    /// The velocity the player is trying to reach, based on the keys held down and where the player
+    /// is looking
+    fn get_wanted_velocity(&self) -> Vector3<f32> {
+        let mut direction = vec3(0.0, 0.0, 0.0);
+        if self.is_moving_forward {
+            direction.z += 1.0;
+        }
+        if self.is_moving_backward {
+            direction.z -= 1.0;
+        }
+        if self.is_moving_right {
+            direction.x += 1.0;
+        }
         if self.is_moving_left {
-            vec3(-64.0 * delta as f32, 0.0, 0.0)
+            direction.x -= 1.0;
+        }
+        if direction.magnitude2() > 0.0 {
+            direction = direction.normalize();
+        }
+
+        let angle = Quaternion::from(Euler::<Deg<f32>> {
+            x: Deg(180.0),
+            y: self.yaw + Deg(180.0),
+            z: Deg(0.0),
+        });
+        let mut velocity = angle.rotate_vector(direction);
+
+        if self.is_flying {
+            velocity *= FLYING_SPEED;
+            if self.is_jumping {
+                velocity.y += FLYING_SPEED;
+            }
+            if self.is_sneaking {
+                velocity.y -= FLYING_SPEED;
+            }
+        } else if self.is_sneaking {
+            velocity *= SNEAKING_SPEED;
         } else {
-            vec3(0.0, 0.0, 0.0)
+            velocity *= WALKING_SPEED;
         }
+
+        velocity
     }
 
-    fn get_next_jump_velocity(&self, delta: f64) -> Vector3<f32> {
-        if self.is_jumping {
-            vec3(0.0, -64.0 * delta as f32, 0.0)
+    fn accelerate(&mut self, delta: f32, friction: f32) {
+        let wanted_velocity = self.get_wanted_velocity();
+
+        if self.is_flying {
+            let amount = (FLYING_ACCELERATION * delta).min(1.0);
+            self.velocity = self.velocity.lerp(wanted_velocity, amount);
+            return;
+        }
+
+        let acceleration = if self.is_grounded {
+            GROUND_ACCELERATION * friction
         } else {
-            vec3(0.0, 0.0, 0.0)
+            AIR_ACCELERATION
+        };
+        let amount = (acceleration * delta).min(1.0);
+        self.velocity.x += (wanted_velocity.x - self.velocity.x) * amount;
+        self.velocity.z += (wanted_velocity.z - self.velocity.z) * amount;
+
+        if self.is_jumping && self.is_grounded {
+            self.velocity.y = JUMP_VELOCITY;
         }
+        self.velocity.y = (self.velocity.y - GRAVITY * delta).max(-TERMINAL_VELOCITY);
+    }
+
+    /// The absolute coordinates of the block below the middle of the feet
+    fn get_block_below(&self) -> [i64; 3] {
+        let bounding_box = self.bounding_box();
+        [
+            ((bounding_box.min[0] + bounding_box.max[0]) / 2.0).floor() as i64,
+            (bounding_box.min[1] - BoundingBox::EPSILON).floor() as i64,
+            ((bounding_box.min[2] + bounding_box.max[2]) / 2.0).floor() as i64,
+        ]
+    }
+}
+
+#[async_trait::async_trait]
+impl Entity for Player {
+    fn id(&self) -> Uuid {
+        self.id
+    }
+
+    fn position(&self) -> &EntityPosition {
+        &self.position
     }
 
-    fn get_next_sneak_velocity(&self, delta: f64) -> Vector3<f32> {
-        if self.is_sneaking {
-            vec3(0.0, 64.0 * delta as f32, 0.0)
+    async fn update_position(
+        &mut self,
+        delta: f64,
+        world: &dyn CollisionWorld,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let dimension = self.position.chunk_position.dimension;
+        let friction = if self.is_grounded && !self.is_flying {
+            world.friction(dimension, self.get_block_below()).await?
         } else {
-            vec3(0.0, 0.0, 0.0)
+            1.0
+        };
+        self.accelerate(delta as f32, friction);
+
+        let bounding_box = self.bounding_box();
+        let mut movement = [
+            f64::from(self.velocity.x) * delta,
+            f64::from(self.velocity.y) * delta,
+            f64::from(self.velocity.z) * delta,
+        ];
+        let region = bounding_box.expanded_towards(movement).expanded_towards([
+            0.0,
+            -MAX_SNEAKING_DROP,
+            0.0,
+        ]);
+        let solid_blocks = world.solid_blocks(dimension, &region).await?;
+        let collision_boxes = |x, y, z| solid_blocks.boxes(x, y, z);
+
+        if self.is_sneaking && self.is_grounded && !self.is_flying {
+            movement = stop_at_ledges(&bounding_box, movement, MAX_SNEAKING_DROP, collision_boxes);
         }
-    }
+        let collision = sweep(&bounding_box, movement, collision_boxes);
+        self.is_grounded = collision.is_grounded;
 
-    fn limit_velocity(&self, mut velocity: Vector3<f32>) -> Vector3<f32> {
-        if velocity.x > self.get_max_horizontal_velocity() {
-            velocity.x = self.get_max_horizontal_velocity()
+        if collision.is_grounded || collision.hits_ceiling {
+            self.velocity.y = 0.0;
+        }
+        if collision.hits_wall_x {
+            self.velocity.x = 0.0;
         }
-        if velocity.x < self.get_max_horizontal_velocity() * -1.0 {
-            velocity.x = self.get_max_horizontal_velocity() * -1.0
+        if collision.hits_wall_z {
+            self.velocity.z = 0.0;
         }
 
-        if velocity.z > self.get_max_horizontal_velocity() {
-            velocity.z = self.get_max_horizontal_velocity()
+        self.position = self.position.translated::<CHUNK_SIZE>(collision.movement);
+        if collision.movement != [0.0; 3] {
+            // Nobody listening is fine
+            let _ = self
+                .world_event_sender
+                .send(WorldEvent::EntityPositionChanged(
+                    self.id,
+                    self.position.clone(),
+                ));
         }
-        if velocity.z < self.get_max_horizontal_velocity() * -1.0 {
-            velocity.z = self.get_max_horizontal_velocity() * -1.0
+
+        Ok(())
+    }
+}
+
+impl LivingEntity for Player {
+    fn bounding_box(&self) -> BoundingBox {
+        BoundingBox::around(
+            self.position.absolute::<CHUNK_SIZE>(),
+            WIDTH,
+            EYE_HEIGHT,
+            HEAD_HEIGHT,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::entity::Player;
+    use crate::CHUNK_SIZE;
+    use pollster::FutureExt;
+    use std::error::Error;
+    use tokio::sync::broadcast;
+    use uuid::Uuid;
+    use voxelcraft_core::chunk::ChunkPosition;
+    use voxelcraft_core::collision::{BoundingBox, SolidBlocks};
+    use voxelcraft_core::entity::{EntityOffset, EntityPosition};
+    use voxelcraft_id::DimensionId;
+    use voxelcraft_mod::{BlockShape, CollisionWorld, Entity, LivingEntity};
+
+    const DELTA: f64 = 1.0 / 60.0;
+
+    struct TestWorld {
+        is_solid: fn(i64, i64, i64) -> bool,
+        friction: f32,
+    }
+
+    #[async_trait::async_trait]
+    impl CollisionWorld for TestWorld {
+        async fn solid_blocks(
+            &self,
+            dimension: DimensionId,
+            region: &BoundingBox,
+        ) -> Result<SolidBlocks, Box<dyn Error + Send + Sync>> {
+            let mut solid_blocks = SolidBlocks::new(region);
+            for chunk_position in solid_blocks.chunk_positions::<CHUNK_SIZE>(dimension) {
+                let base = chunk_position.base_block_position::<CHUNK_SIZE>();
+                solid_blocks.insert_chunk::<CHUNK_SIZE, _>(&chunk_position, |offset| {
+                    let is_solid = (self.is_solid)(
+                        base.absolute_x() + offset.x as i64,
+                        base.absolute_y() + offset.y as i64,
+                        base.absolute_z() + offset.z as i64,
+                    );
+                    if is_solid {
+                        BlockShape::Cube.collision_boxes()
+                    } else {
+                        &[]
+                    }
+                });
+            }
+            Ok(solid_blocks)
         }
 
-        if velocity.y > self.get_max_horizontal_velocity() {
-            velocity.y = self.get_max_horizontal_velocity()
+        async fn friction(
+            &self,
+            _dimension: DimensionId,
+            _position: [i64; 3],
+        ) -> Result<f32, Box<dyn Error + Send + Sync>> {
+            Ok(self.friction)
         }
-        if velocity.y < self.get_max_horizontal_velocity() * -1.0 {
-            velocity.y = self.get_max_horizontal_velocity() * -1.0
+    }
+
+    fn floor(_x: i64, y: i64, _z: i64) -> bool {
+        y < 0
+    }
+
+    /// A player with the feet at the given height
+    fn player_at(y: f32) -> Player {
+        Player::new(
+            Uuid::new_v4(),
+            EntityPosition {
+                chunk_position: ChunkPosition::default(),
+                offset: EntityOffset {
+                    x: 0.5,
+                    y: y + 1.62,
+                    z: 0.5,
+                },
+            },
+            broadcast::channel(1).0,
+        )
+    }
+
+    fn update(player: &mut Player, world: &TestWorld, ticks: usize) {
+        for _ in 0..ticks {
+            player.update_position(DELTA, world).block_on().unwrap();
         }
+    }
 
-        velocity
+    #[test]
+    fn it_should_fall_and_land_on_the_ground() {
+        let world = TestWorld {
+            is_solid: floor,
+            friction: 1.0,
+        };
+        let mut player = player_at(4.0);
+
+        update(&mut player, &world, 60);
+
+        assert!(player.is_grounded());
+        assert_eq!(player.velocity().y, 0.0);
+        assert!(player.bounding_box().min[1].abs() < 1e-4);
     }
 
-    fn deaccelerate_velocity(&mut self, delta: f64) {
-        self.velocity = self.velocity.lerp(vec3(0.0, 0.0, 0.0), delta as f32 * 8.0);
+    #[test]
+    fn it_should_not_fall_faster_than_the_terminal_velocity() {
+        let world = TestWorld {
+            is_solid: |_, _, _| false,
+            friction: 1.0,
+        };
+        let mut player = player_at(0.0);
+
+        update(&mut player, &world, 600);
+
+        assert_eq!(player.velocity().y, -super::TERMINAL_VELOCITY);
     }
-}
 
-#[async_trait::async_trait]
-impl Entity for Player {
-    fn id(&self) -> Uuid {
-        self.id
+    #[test]
+    fn it_should_only_jump_when_grounded() {
+        let world = TestWorld {
+            is_solid: floor,
+            friction: 1.0,
+        };
+        let mut player = player_at(0.0);
+        update(&mut player, &world, 1);
+        assert!(player.is_grounded());
+
+        player.start_jumping();
+        update(&mut player, &world, 1);
+        let velocity_after_jump = player.velocity().y;
+        update(&mut player, &world, 1);
+
+        assert!(velocity_after_jump > 0.0);
+        assert!(!player.is_grounded());
+        assert!(player.velocity().y < velocity_after_jump);
     }
 
-    fn position(&self) -> &EntityPosition {
-        &self.position
+    #[test]
+    fn it_should_jump_up_on_a_block() {
+        let world = TestWorld {
+            is_solid: floor,
+            friction: 1.0,
+        };
+        let mut player = player_at(0.0);
+        update(&mut player, &world, 1);
+        player.start_jumping();
+
+        let mut highest = 0.0;
+        for _ in 0..60 {
+            update(&mut player, &world, 1);
+            highest = player.bounding_box().min[1].max(highest);
+        }
+
+        assert!(highest > 1.0);
     }
 
-    async fn update_position(&mut self, delta: f64) {
-        self.deaccelerate_velocity(delta);
-        let next_velocity = self.get_next_forward_velocity(delta)
-            + self.get_next_backward_velocity(delta)
-            + self.get_next_right_velocity(delta)
-            + self.get_next_left_velocity(delta)
-            + self.get_next_jump_velocity(delta)
-            + self.get_next_sneak_velocity(delta);
-        let next_velocity = self.limit_velocity(next_velocity);
+    #[test]
+    fn it_should_not_sneak_off_a_ledge() {
+        let world = TestWorld {
+            is_solid: |x, y, z| y == -1 && x == 0 && z == 0,
+            friction: 1.0,
+        };
+        let mut player = player_at(0.0);
+        update(&mut player, &world, 1);
+
+        player.start_sneaking();
+        player.start_move_forward();
+        player.start_move_right();
+        update(&mut player, &world, 120);
+
+        let bounding_box = player.bounding_box();
+        assert!(player.is_grounded());
+        assert!(bounding_box.min[0] < 1.0 && bounding_box.max[0] > 0.0);
+        assert!(bounding_box.min[2] < 1.0 && bounding_box.max[2] > 0.0);
+    }
 
-        let angle = Quaternion::from(Euler::<Deg<f32>> {
-            x: Deg(180.0),
-            y: self.yaw + Deg(180.0),
-            z: Deg(0.0),
-        });
+    #[test]
+    fn it_should_walk_off_a_ledge_when_not_sneaking() {
+        let world = TestWorld {
+            is_solid: |x, y, z| y == -1 && x == 0 && z == 0,
+            friction: 1.0,
+        };
+        let mut player = player_at(0.0);
+        update(&mut player, &world, 1);
 
-        let rotated_velocity = angle.rotate_vector(next_velocity);
+        player.start_move_forward();
+        update(&mut player, &world, 120);
 
-        self.velocity += rotated_velocity;
+        assert!(!player.is_grounded());
+        assert!(player.bounding_box().min[1] < -1.0);
+    }
 
-        self.position.offset.x += self.velocity.x * delta as f32;
-        self.position.offset.y += self.velocity.y * delta as f32;
-        self.position.offset.z += self.velocity.z * delta as f32;
+    #[test]
+    fn it_should_speed_up_slower_on_slippery_blocks() {
+        let speed_after_a_tick = |friction| {
+            let world = TestWorld {
+                is_solid: floor,
+                friction,
+            };
+            let mut player = player_at(0.0);
+            update(&mut player, &world, 1);
+            player.start_move_forward();
+            update(&mut player, &world, 1);
+            player.velocity().x.hypot(player.velocity().z)
+        };
+
+        assert!(speed_after_a_tick(0.1) < speed_after_a_tick(1.0));
+    }
+
+    #[test]
+    fn it_should_not_fall_while_flying() {
+        let world = TestWorld {
+            is_solid: floor,
+            friction: 1.0,
+        };
+        let mut player = player_at(4.0);
+        player.set_flying(true);
+
+        update(&mut player, &world, 60);
+        player.start_jumping();
+        update(&mut player, &world, 60);
+
+        assert!(player.bounding_box().min[1] > 4.0);
+    }
+
+    #[test]
+    fn it_should_move_in_to_the_next_chunk() {
+        let world = TestWorld {
+            is_solid: floor,
+            friction: 1.0,
+        };
+        let mut player = player_at(0.0);
+        player.set_flying(true);
+
+        player.start_move_forward();
+        update(&mut player, &world, 300);
+
+        let position = player.position();
+        assert_ne!(position.chunk_position, ChunkPosition::default());
+        for offset in [position.offset.x, position.offset.y, position.offset.z] {
+            assert!((0.0..CHUNK_SIZE as f32).contains(&offset));
+        }
     }
-}
 
-impl LivingEntity for Player {}
+    #[test]
+    fn it_should_move_the_same_way_every_time() {
+        let world = TestWorld {
+            is_solid: floor,
+            friction: 1.0,
+        };
+        let run = || {
+            let mut player = player_at(2.0);
+            player.start_move_forward();
+            player.start_jumping();
+            update(&mut player, &world, 90);
+            player.position().clone()
+        };
+
+        assert_eq!(run(), run());
+    }
+}
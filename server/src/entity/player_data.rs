@@ -0,0 +1,77 @@
+use std::error::Error;
+use voxelcraft_id::DimensionId;
+
+/// The state of a player that is kept between sessions
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct PlayerData {
+    pub format_version: u32,
+    pub dimension: DimensionId,
+    pub chunk_position: [i32; 3],
+    pub offset: [f32; 3],
+    /// In degrees
+    pub pitch: f32,
+    /// In degrees
+    pub yaw: f32,
+    pub velocity: [f32; 3],
+    pub is_flying: bool,
+}
+
+impl PlayerData {
+    pub const FORMAT_VERSION: u32 = 1;
+
+    pub fn encode(&self) -> Result<Vec<u8>, Box<dyn Error + Send + Sync>> {
+        Ok(serde_json::to_vec_pretty(self)?)
+    }
+
+    /// # Errors
+    /// If the bytes are not player data, or if the data was written by a newer version
+    pub fn decode(bytes: &[u8]) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        let data: Self = serde_json::from_slice(bytes)?;
+        if data.format_version > Self::FORMAT_VERSION {
+            return Err(format!(
+                "The player data has format version {}, but only versions up to {} are supported",
+                data.format_version,
+                Self::FORMAT_VERSION
+            )
+            .into());
+        }
+        Ok(data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::entity::PlayerData;
+
+    fn data() -> PlayerData {
+        PlayerData {
+            format_version: PlayerData::FORMAT_VERSION,
+            dimension: Default::default(),
+            chunk_position: [1, -2, 3],
+            offset: [0.5, 12.25, 31.0],
+            pitch: -30.0,
+            yaw: 90.0,
+            velocity: [0.0, -4.0, 1.5],
+            is_flying: true,
+        }
+    }
+
+    #[test]
+    fn it_should_decode_what_was_encoded() {
+        let data = data();
+
+        let decoded = PlayerData::decode(&data.encode().unwrap()).unwrap();
+
+        assert_eq!(decoded, data);
+    }
+
+    #[test]
+    fn it_should_refuse_newer_format_versions() {
+        let data = PlayerData {
+            format_version: PlayerData::FORMAT_VERSION + 1,
+            ..data()
+        };
+
+        assert!(PlayerData::decode(&data.encode().unwrap()).is_err());
+    }
+}
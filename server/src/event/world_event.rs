@@ -1,7 +1,12 @@
+use crate::CHUNK_SIZE;
 use uuid::Uuid;
+use voxelcraft_core::block::BlockPosition;
 use voxelcraft_core::entity::EntityPosition;
+use voxelcraft_id::BlockId;
 
 #[derive(Debug, Clone)]
 pub enum WorldEvent {
     EntityPositionChanged(Uuid, EntityPosition),
+    /// A block was replaced with a different one
+    BlockChanged(BlockPosition<CHUNK_SIZE>, BlockId),
 }
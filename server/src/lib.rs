@@ -1,11 +1,12 @@
-mod block;
 mod chunk;
 pub mod client;
 mod entity;
 pub mod event;
 pub mod local;
-mod remote;
-mod storage;
-mod world;
+pub mod remote;
+pub mod storage;
+#[cfg(test)]
+mod test_util;
+pub mod world;
 
 pub use self::chunk::*;
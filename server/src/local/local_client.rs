@@ -5,12 +5,14 @@ use std::error::Error;
 use std::sync::Arc;
 use tokio::sync::{broadcast, OwnedRwLockReadGuard};
 
-use crate::Chunk;
+use crate::{Chunk, CHUNK_SIZE};
 use cgmath::Deg;
 use std::future::Future;
 use uuid::Uuid;
+use voxelcraft_core::block::BlockPosition;
 use voxelcraft_core::chunk::ChunkPosition;
 use voxelcraft_core::entity::EntityPosition;
+use voxelcraft_id::BlockId;
 use voxelcraft_mod::Entity;
 
 #[derive(Debug)]
@@ -34,14 +36,18 @@ impl Client for LocalClient {
         self.world.get_event_receiver()
     }
 
-    async fn begin_joining_world(&self) {
-        self.world.load_player(self.player_id).await;
+    async fn begin_joining_world(&self) -> Result<(), Box<dyn Error + Send + Sync>> {
+        self.world.load_player(self.player_id).await
     }
 
     async fn join_world(&self) {
         todo!()
     }
 
+    async fn leave_world(&self) -> Result<(), Box<dyn Error + Send + Sync>> {
+        self.world.unload_player(self.player_id).await
+    }
+
     async fn position(&self) -> EntityPosition {
         self.world
             .borrow_player(
@@ -64,6 +70,28 @@ impl Client for LocalClient {
         self.world.get_chunk(chunk_position, callback).await
     }
 
+    async fn get_block(
+        &self,
+        position: &BlockPosition<CHUNK_SIZE>,
+    ) -> Result<BlockId, Box<dyn Error + Send + Sync>> {
+        self.world.get_block(position).await
+    }
+
+    async fn set_block(
+        &self,
+        position: &BlockPosition<CHUNK_SIZE>,
+        block_id: BlockId,
+    ) -> Result<BlockId, Box<dyn Error + Send + Sync>> {
+        self.world.set_block(position, block_id).await
+    }
+
+    async fn set_blocks(
+        &self,
+        blocks: Vec<(BlockPosition<CHUNK_SIZE>, BlockId)>,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        self.world.set_blocks(blocks).await
+    }
+
     async fn start_move_forward(&self) {
         self.world
             .borrow_player(self.player_id, |mut player| async move {
@@ -173,6 +201,15 @@ impl Client for LocalClient {
             .unwrap()
     }
 
+    async fn set_flying(&self, is_flying: bool) {
+        self.world
+            .borrow_player(self.player_id, |mut player| async move {
+                player.set_flying(is_flying);
+            })
+            .await
+            .unwrap()
+    }
+
     async fn set_pitch_yaw(&self, pitch: Deg<f32>, yaw: Deg<f32>) {
         self.world
             .borrow_player(self.player_id, |mut player| async move {
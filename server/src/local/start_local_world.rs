@@ -1,14 +1,29 @@
 use crate::local::local_client::LocalClient;
-use crate::storage::FileStorage;
-use crate::world::World;
+use crate::storage::Storage;
+use crate::world::{World, WorldHandle};
 use pollster::FutureExt;
+use std::error::Error;
 use std::sync::Arc;
+use std::time::Duration;
 use uuid::Uuid;
 use voxelcraft_mod::ModPack;
 
-pub fn new_local_world(_seed: u64, player_id: Uuid, mod_pack: &Arc<dyn ModPack>) -> LocalClient {
-    let storage = FileStorage::new();
-    let world = Arc::new(World::new(storage, Arc::clone(mod_pack)).block_on());
-    world.start_update_loop();
-    LocalClient::new(&world, player_id)
+/// Starts the world kept in the storage, creating it with the given name and seed if the storage
+/// is empty. The world keeps running until it is shut down through the returned handle
+pub fn new_local_world<S: Storage + 'static>(
+    name: &str,
+    seed: u64,
+    player_id: Uuid,
+    mod_pack: &Arc<dyn ModPack>,
+    storage: S,
+    autosave_interval: Option<Duration>,
+) -> Result<(LocalClient, WorldHandle), Box<dyn Error + Send + Sync>> {
+    let world = if storage.load_metadata().block_on()?.is_some() {
+        World::load(storage, Arc::clone(mod_pack)).block_on()?
+    } else {
+        World::create(storage, Arc::clone(mod_pack), name, seed).block_on()?
+    };
+    let world = Arc::new(world);
+    let handle = world.start_update_loop(autosave_interval);
+    Ok((LocalClient::new(&world, player_id), handle))
 }
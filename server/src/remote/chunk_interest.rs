@@ -0,0 +1,252 @@
+use std::collections::{HashSet, VecDeque};
+use voxelcraft_core::chunk::ChunkPosition;
+
+/// How far a client sees, in chunks, until it asks for another view distance
+pub const DEFAULT_VIEW_DISTANCE: u8 = 2;
+
+/// The furthest a client can see, in chunks, so that a single client can not make the server
+/// load the whole world
+pub const MAX_VIEW_DISTANCE: u8 = 8;
+
+/// Whether the chunk is in the same dimension as the center, and at most the view distance away on
+/// every axis
+pub fn is_in_view(center: &ChunkPosition, position: &ChunkPosition, view_distance: u8) -> bool {
+    let view_distance = view_distance as i32;
+    position.dimension == center.dimension
+        && (position.x - center.x).abs() <= view_distance
+        && (position.y - center.y).abs() <= view_distance
+        && (position.z - center.z).abs() <= view_distance
+}
+
+/// Keeps track of the chunks a client has, and of the ones it still needs as its player moves.
+///
+/// A chunk is in range when it is in the same dimension as the player, and at most the view
+/// distance away on every axis. That is the same cube as [`ChunkPosition::surrounding_chunks`]
+#[derive(Debug)]
+pub struct ChunkInterest {
+    view_distance: u8,
+    /// The chunk the player is in, once it is known
+    center: Option<ChunkPosition>,
+    /// The chunks in range that were sent, which the client has or is about to get
+    loaded: HashSet<ChunkPosition>,
+    /// The chunks in range that the client does not have yet, nearest first
+    pending: VecDeque<ChunkPosition>,
+}
+
+impl ChunkInterest {
+    pub fn new(view_distance: u8) -> Self {
+        Self {
+            view_distance: view_distance.min(MAX_VIEW_DISTANCE),
+            center: None,
+            loaded: HashSet::new(),
+            pending: VecDeque::new(),
+        }
+    }
+
+    /// Changes the view distance, up to [`MAX_VIEW_DISTANCE`], and returns the chunks that went
+    /// out of range
+    pub fn set_view_distance(&mut self, view_distance: u8) -> Vec<ChunkPosition> {
+        let view_distance = view_distance.min(MAX_VIEW_DISTANCE);
+        if view_distance == self.view_distance {
+            return Vec::new();
+        }
+
+        self.view_distance = view_distance;
+        self.refresh()
+    }
+
+    /// Centers the range on the chunk the player is in, and returns the chunks that went out of
+    /// range
+    pub fn move_to(&mut self, center: ChunkPosition) -> Vec<ChunkPosition> {
+        if self.center == Some(center) {
+            return Vec::new();
+        }
+
+        self.center = Some(center);
+        self.refresh()
+    }
+
+    /// The nearest chunk the client still needs. It only counts as loaded once it is passed to
+    /// [`Self::mark_loaded`], a chunk that could not be sent goes back with [`Self::retry_later`]
+    pub fn next_chunk(&mut self) -> Option<ChunkPosition> {
+        self.pending.pop_front()
+    }
+
+    /// Remembers that the chunk was sent, so that it is not handed out again while it stays in
+    /// range
+    pub fn mark_loaded(&mut self, position: ChunkPosition) {
+        if self.is_in_range(&position) {
+            self.pending.retain(|pending| *pending != position);
+            self.loaded.insert(position);
+        }
+    }
+
+    /// Hands the chunk out again after the other chunks the client still needs
+    pub fn retry_later(&mut self, position: ChunkPosition) {
+        if self.is_in_range(&position)
+            && !self.loaded.contains(&position)
+            && !self.pending.contains(&position)
+        {
+            self.pending.push_back(position);
+        }
+    }
+
+    /// Whether the chunk is in view of the player
+    pub fn is_in_range(&self, position: &ChunkPosition) -> bool {
+        self.center.map_or(false, |center| {
+            is_in_view(&center, position, self.view_distance)
+        })
+    }
+
+    fn refresh(&mut self) -> Vec<ChunkPosition> {
+        let center = match self.center {
+            Some(center) => center,
+            None => return Vec::new(),
+        };
+
+        let unloaded = self
+            .loaded
+            .iter()
+            .filter(|position| !self.is_in_range(position))
+            .copied()
+            .collect::<Vec<_>>();
+        for position in &unloaded {
+            self.loaded.remove(position);
+        }
+
+        let mut pending = center
+            .surrounding_chunks(self.view_distance as usize)
+            .into_iter()
+            .filter(|position| !self.loaded.contains(position))
+            .collect::<Vec<_>>();
+        pending.sort_by_key(|position| {
+            let (x, y, z) = (
+                position.x - center.x,
+                position.y - center.y,
+                position.z - center.z,
+            );
+            x * x + y * y + z * z
+        });
+        self.pending = pending.into();
+
+        unloaded
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::remote::chunk_interest::{ChunkInterest, MAX_VIEW_DISTANCE};
+    use std::collections::HashSet;
+    use voxelcraft_core::chunk::ChunkPosition;
+    use voxelcraft_id::DimensionId;
+
+    fn chunk(x: i32, y: i32, z: i32) -> ChunkPosition {
+        ChunkPosition {
+            x,
+            y,
+            z,
+            dimension: DimensionId::default(),
+        }
+    }
+
+    /// Hands out every chunk, as if they were all sent
+    fn drain(interest: &mut ChunkInterest) -> Vec<ChunkPosition> {
+        std::iter::from_fn(|| {
+            let position = interest.next_chunk()?;
+            interest.mark_loaded(position);
+            Some(position)
+        })
+        .collect()
+    }
+
+    #[test]
+    fn it_should_hand_out_the_nearest_chunks_first() {
+        let mut interest = ChunkInterest::new(1);
+        interest.move_to(chunk(4, 0, -2));
+
+        let chunks = drain(&mut interest);
+
+        assert_eq!(chunks.len(), 27);
+        assert_eq!(chunks[0], chunk(4, 0, -2));
+        assert!(chunks[1..7].contains(&chunk(5, 0, -2)));
+        assert!(chunks[1..7].contains(&chunk(4, -1, -2)));
+        assert_eq!(chunks.last().map(|last| (last.x - 4).abs()), Some(1));
+        assert_eq!(interest.next_chunk(), None);
+    }
+
+    #[test]
+    fn it_should_unload_chunks_that_went_out_of_range() {
+        let mut interest = ChunkInterest::new(1);
+        interest.move_to(chunk(0, 0, 0));
+        drain(&mut interest);
+
+        let unloaded = interest.move_to(chunk(1, 0, 0));
+
+        assert_eq!(
+            unloaded.into_iter().collect::<HashSet<_>>(),
+            chunk(-1, 0, 0)
+                .surrounding_chunks(1)
+                .into_iter()
+                .filter(|position| position.x == -1)
+                .collect::<HashSet<_>>()
+        );
+        let loaded = drain(&mut interest);
+        assert_eq!(loaded.len(), 9);
+        assert!(loaded.iter().all(|position| position.x == 2));
+    }
+
+    #[test]
+    fn it_should_limit_the_view_distance() {
+        let mut interest = ChunkInterest::new(u8::MAX);
+        interest.move_to(chunk(0, 0, 0));
+        let size = MAX_VIEW_DISTANCE as usize * 2 + 1;
+        assert_eq!(drain(&mut interest).len(), size * size * size);
+
+        let unloaded = interest.set_view_distance(0);
+
+        assert_eq!(unloaded.len(), size * size * size - 1);
+        assert!(!unloaded.contains(&chunk(0, 0, 0)));
+        assert_eq!(interest.next_chunk(), None);
+    }
+
+    #[test]
+    fn it_should_hand_out_a_chunk_again_when_it_was_not_sent() {
+        let mut interest = ChunkInterest::new(1);
+        interest.move_to(chunk(0, 0, 0));
+        let first = interest.next_chunk().unwrap();
+
+        interest.retry_later(first);
+
+        let chunks = drain(&mut interest);
+        assert_eq!(chunks.len(), 27);
+        assert_eq!(chunks.last(), Some(&first));
+    }
+
+    #[test]
+    fn it_should_not_hand_out_a_chunk_that_was_sent_on_request() {
+        let mut interest = ChunkInterest::new(1);
+        interest.move_to(chunk(0, 0, 0));
+
+        interest.mark_loaded(chunk(1, 1, 1));
+        interest.mark_loaded(chunk(5, 0, 0));
+
+        let chunks = drain(&mut interest);
+        assert_eq!(chunks.len(), 26);
+        assert!(!chunks.contains(&chunk(1, 1, 1)));
+        assert!(interest.move_to(chunk(3, 0, 0)).contains(&chunk(1, 1, 1)));
+    }
+
+    #[test]
+    fn it_should_unload_everything_when_changing_dimension() {
+        let mut interest = ChunkInterest::new(0);
+        interest.move_to(chunk(0, 0, 0));
+        drain(&mut interest);
+        let other = ChunkPosition {
+            dimension: DimensionId::from_u128(7),
+            ..chunk(0, 0, 0)
+        };
+
+        assert_eq!(interest.move_to(other), vec![chunk(0, 0, 0)]);
+        assert_eq!(interest.next_chunk(), Some(other));
+    }
+}
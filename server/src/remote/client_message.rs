@@ -0,0 +1,54 @@
+use crate::remote::{ModManifest, Movement};
+use crate::CHUNK_SIZE;
+use voxelcraft_core::block::BlockPosition;
+use voxelcraft_core::chunk::ChunkPosition;
+use voxelcraft_id::BlockId;
+
+/// The most blocks a client can set with a single message
+pub const MAX_BLOCKS_PER_MESSAGE: usize = 4096;
+
+/// The messages a client sends to the server
+#[derive(Debug, Clone, PartialEq, bincode::Encode, bincode::Decode)]
+pub enum ClientMessage {
+    /// The first message on every connection
+    Handshake {
+        protocol_version: u32,
+    },
+    /// The mods of the client, sent once the handshake was accepted
+    Manifest(ModManifest),
+    /// Joins the world as the player, once the manifest was accepted
+    Login {
+        player_id: u128,
+    },
+    KeepAlive,
+    StartMoving(Movement),
+    StopMoving(Movement),
+    SetFlying(bool),
+    /// In degrees
+    HeadRotation {
+        pitch: f32,
+        yaw: f32,
+    },
+    /// Replaces the blocks, which the server broadcasts as [`ServerMessage::BlockChanged`]. The
+    /// server answers with [`ServerMessage::BlocksRefused`] when there are more than
+    /// [`MAX_BLOCKS_PER_MESSAGE`], or when any of them is out of view of the player
+    ///
+    /// [`ServerMessage::BlockChanged`]: crate::remote::ServerMessage::BlockChanged
+    /// [`ServerMessage::BlocksRefused`]: crate::remote::ServerMessage::BlocksRefused
+    /// [`MAX_BLOCKS_PER_MESSAGE`]: crate::remote::MAX_BLOCKS_PER_MESSAGE
+    SetBlocks(Vec<(BlockPosition<CHUNK_SIZE>, BlockId)>),
+    /// Asks the server to send the chunk, which it answers with [`ServerMessage::ChunkData`], or
+    /// with [`ServerMessage::ChunkUnavailable`] when the chunk is out of view of the player or could
+    /// not be loaded
+    ///
+    /// [`ServerMessage::ChunkData`]: crate::remote::ServerMessage::ChunkData
+    /// [`ServerMessage::ChunkUnavailable`]: crate::remote::ServerMessage::ChunkUnavailable
+    RequestChunk(ChunkPosition),
+    /// How far the player wants to see, in chunks, which the server limits to
+    /// [`MAX_VIEW_DISTANCE`]
+    ///
+    /// [`MAX_VIEW_DISTANCE`]: crate::remote::MAX_VIEW_DISTANCE
+    SetViewDistance(u8),
+    /// Leaves the world, after which the server closes the connection
+    Disconnect,
+}
@@ -0,0 +1,534 @@
+use crate::entity::Player;
+use crate::remote::chunk_interest::{
+    is_in_view, ChunkInterest, DEFAULT_VIEW_DISTANCE, MAX_VIEW_DISTANCE,
+};
+use crate::remote::protocol::{
+    read_message, write_message, KEEP_ALIVE_INTERVAL, KEEP_ALIVE_TIMEOUT, PROTOCOL_VERSION,
+};
+use crate::remote::{
+    ClientMessage, IncompatibleModsError, ModManifest, Movement, ServerMessage,
+    MAX_BLOCKS_PER_MESSAGE,
+};
+use crate::world::World;
+use crate::CHUNK_SIZE;
+use cgmath::Deg;
+use std::error::Error;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::tcp::{OwnedReadHalf, OwnedWriteHalf};
+use tokio::net::TcpStream;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::{mpsc, watch, Semaphore};
+use tokio::time::{interval, timeout};
+use uuid::Uuid;
+use voxelcraft_core::block::BlockPosition;
+use voxelcraft_core::chunk::ChunkPosition;
+use voxelcraft_id::BlockId;
+
+/// The number of messages that can be waiting to be written to a client
+const CHANNEL_SIZE: usize = 1024;
+
+/// The number of chunks that can be waiting to be written to a client, so that a slow client
+/// does not pile them up on the server
+const MAX_QUEUED_CHUNKS: usize = 16;
+
+/// The number of chunk requests that can wait to be served, after which reading from the client
+/// waits as well
+const MAX_REQUESTED_CHUNKS: usize = 64;
+
+/// How often the chunks around a player are checked
+const STREAM_INTERVAL: Duration = Duration::from_millis(50);
+
+/// How many bytes of chunks are streamed to a client per second, at most
+const STREAM_BYTES_PER_SECOND: usize = 4 * 1024 * 1024;
+
+/// A client connected to the server, from the handshake until it leaves
+#[derive(Debug)]
+pub struct Connection {
+    world: Arc<World>,
+    /// The mods of the server, which the client has to match
+    manifest: Arc<ModManifest>,
+    address: SocketAddr,
+}
+
+impl Connection {
+    pub fn new(world: Arc<World>, manifest: Arc<ModManifest>, address: SocketAddr) -> Self {
+        Self {
+            world,
+            manifest,
+            address,
+        }
+    }
+
+    /// Serves the client until it disconnects. Its player is saved and taken out of the world
+    /// before the connection is closed
+    pub async fn run(self, stream: TcpStream) {
+        log::info!("{} connected", self.address);
+        let (mut reader, mut writer) = stream.into_split();
+
+        let player_id = match self.join(&mut reader, &mut writer).await {
+            Ok(player_id) => player_id,
+            Err(error) => {
+                log::warn!("{} could not join: {}", self.address, error);
+                return;
+            }
+        };
+
+        if let Err(error) = self.play(player_id, reader, writer).await {
+            log::warn!("Connection with {} failed: {}", self.address, error);
+        }
+        log::info!("{} disconnected", self.address);
+    }
+
+    /// Does the handshake, compares the mods and logs the player in to the world
+    async fn join(
+        &self,
+        reader: &mut OwnedReadHalf,
+        writer: &mut OwnedWriteHalf,
+    ) -> Result<Uuid, Box<dyn Error + Send + Sync>> {
+        match Self::read(reader).await? {
+            ClientMessage::Handshake { protocol_version }
+                if protocol_version == PROTOCOL_VERSION =>
+            {
+                write_message(writer, &ServerMessage::HandshakeAccepted).await?
+            }
+            ClientMessage::Handshake { protocol_version } => {
+                let reason = format!(
+                    "The server speaks protocol version {}, but the client speaks version {}",
+                    PROTOCOL_VERSION, protocol_version
+                );
+                return Self::refuse(writer, reason).await;
+            }
+            message => {
+                return Self::refuse(writer, format!("Expected a handshake, got {:?}", message))
+                    .await
+            }
+        }
+
+        let manifest = match Self::read(reader).await? {
+            ClientMessage::Manifest(manifest) => manifest,
+            message => {
+                return Self::refuse(writer, format!("Expected a manifest, got {:?}", message))
+                    .await
+            }
+        };
+        let mismatches = self.manifest.mismatches(&manifest);
+        if !mismatches.is_empty() {
+            write_message(writer, &ServerMessage::ModsMismatched(mismatches.clone())).await?;
+            return Err(IncompatibleModsError { mismatches }.into());
+        }
+        write_message(
+            writer,
+            &ServerMessage::ManifestAccepted(self.manifest.as_ref().clone()),
+        )
+        .await?;
+
+        // The client may take its time to log in, as long as it keeps the connection alive
+        let player_id = loop {
+            match Self::read(reader).await? {
+                ClientMessage::Login { player_id } => break Uuid::from_u128(player_id),
+                ClientMessage::KeepAlive => {}
+                message => {
+                    return Self::refuse(writer, format!("Expected a login, got {:?}", message))
+                        .await
+                }
+            }
+        };
+        if let Err(error) = self.world.load_player(player_id).await {
+            return Self::refuse(writer, format!("Failed to load the player: {}", error)).await;
+        }
+
+        // A player that is left in the world could not join again, so they leave when this fails
+        if let Err(error) = self.send_logged_in(writer, player_id).await {
+            if let Err(error) = self.world.unload_player(player_id).await {
+                log::error!("Failed to unload player {}: {}", player_id, error);
+            }
+            return Err(error);
+        }
+        log::info!("Player {} joined from {}", player_id, self.address);
+        Ok(player_id)
+    }
+
+    /// Tells the client where its player is in the world
+    async fn send_logged_in(
+        &self,
+        writer: &mut OwnedWriteHalf,
+        player_id: Uuid,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let position = self
+            .world
+            .get_player_position(player_id)
+            .await
+            .ok_or("The player left the world while joining")?;
+        write_message(writer, &ServerMessage::LoggedIn { position }).await
+    }
+
+    /// Handles the messages of a player that is in the world, while forwarding world events and
+    /// keep alives to it
+    async fn play(
+        &self,
+        player_id: Uuid,
+        mut reader: OwnedReadHalf,
+        writer: OwnedWriteHalf,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let (sender, receiver) = mpsc::channel(CHANNEL_SIZE);
+        let (view_distance_sender, view_distance_receiver) = watch::channel(DEFAULT_VIEW_DISTANCE);
+        let (request_sender, request_receiver) = mpsc::channel(MAX_REQUESTED_CHUNKS);
+        let chunk_permits = Arc::new(Semaphore::new(MAX_QUEUED_CHUNKS));
+        let tasks = [
+            tokio::spawn(Self::write_messages(
+                writer,
+                receiver,
+                Arc::clone(&chunk_permits),
+            )),
+            tokio::spawn(Self::forward_world_events(
+                Arc::clone(&self.world),
+                sender.clone(),
+            )),
+            tokio::spawn(Self::send_keep_alives(sender.clone())),
+            tokio::spawn(Self::stream_chunks(
+                Arc::clone(&self.world),
+                player_id,
+                view_distance_receiver,
+                request_receiver,
+                chunk_permits,
+                sender.clone(),
+            )),
+        ];
+
+        let result = self
+            .handle_messages(
+                player_id,
+                &mut reader,
+                &sender,
+                &request_sender,
+                &view_distance_sender,
+            )
+            .await;
+
+        // The player is saved before the connection closes, so a client that sees it close can
+        // rely on that
+        if let Err(error) = self.world.unload_player(player_id).await {
+            log::error!("Failed to unload player {}: {}", player_id, error);
+        }
+        for task in tasks {
+            task.abort();
+        }
+        result
+    }
+
+    async fn handle_messages(
+        &self,
+        player_id: Uuid,
+        reader: &mut OwnedReadHalf,
+        sender: &mpsc::Sender<ServerMessage>,
+        request_sender: &mpsc::Sender<ChunkPosition>,
+        view_distance_sender: &watch::Sender<u8>,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        loop {
+            match Self::read(reader).await? {
+                ClientMessage::KeepAlive => {}
+                ClientMessage::StartMoving(movement) => {
+                    self.world
+                        .borrow_player(player_id, |mut player| async move {
+                            Self::apply_movement(&mut player, movement, true)
+                        })
+                        .await?
+                }
+                ClientMessage::StopMoving(movement) => {
+                    self.world
+                        .borrow_player(player_id, |mut player| async move {
+                            Self::apply_movement(&mut player, movement, false)
+                        })
+                        .await?
+                }
+                ClientMessage::SetFlying(is_flying) => {
+                    self.world
+                        .borrow_player(player_id, |mut player| async move {
+                            player.set_flying(is_flying)
+                        })
+                        .await?
+                }
+                ClientMessage::SetBlocks(blocks) => {
+                    let view_distance = *view_distance_sender.borrow();
+                    if let Err(error) = self.set_blocks(player_id, blocks, view_distance).await {
+                        log::warn!("Refused blocks from player {}: {}", player_id, error);
+                        sender
+                            .send(ServerMessage::BlocksRefused {
+                                reason: error.to_string(),
+                            })
+                            .await
+                            .map_err(|_| "The connection is closed")?;
+                    }
+                }
+                ClientMessage::HeadRotation { pitch, yaw } => {
+                    self.world
+                        .borrow_player(player_id, |mut player| async move {
+                            player.set_head_rotation(Deg(pitch), Deg(yaw))
+                        })
+                        .await?
+                }
+                ClientMessage::RequestChunk(position) => {
+                    // Served along with the streamed chunks, so they share the same limits
+                    request_sender
+                        .send(position)
+                        .await
+                        .map_err(|_| "Chunks are not being sent anymore")?
+                }
+                ClientMessage::SetViewDistance(view_distance) => {
+                    // The streaming stops with the connection, after which nobody is listening
+                    let _ = view_distance_sender.send(view_distance);
+                }
+                ClientMessage::Disconnect => return Ok(()),
+                message @ (ClientMessage::Handshake { .. }
+                | ClientMessage::Manifest(_)
+                | ClientMessage::Login { .. }) => {
+                    return Err(format!("Unexpected {:?} while in the world", message).into())
+                }
+            }
+        }
+    }
+
+    /// Sets the blocks of the player, as long as there are not too many of them and they are all in
+    /// view of the player. That keeps a client from making the server generate chunks nobody sees
+    async fn set_blocks(
+        &self,
+        player_id: Uuid,
+        blocks: Vec<(BlockPosition<CHUNK_SIZE>, BlockId)>,
+        view_distance: u8,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        if blocks.len() > MAX_BLOCKS_PER_MESSAGE {
+            return Err(format!(
+                "At most {} blocks can be set at once, got {}",
+                MAX_BLOCKS_PER_MESSAGE,
+                blocks.len()
+            )
+            .into());
+        }
+        let center = self
+            .world
+            .get_player_position(player_id)
+            .await
+            .ok_or("Player not found")?
+            .chunk_position;
+        let view_distance = view_distance.min(MAX_VIEW_DISTANCE);
+        let out_of_view = blocks
+            .iter()
+            .find(|(position, _)| !is_in_view(&center, &position.chunk_position, view_distance));
+        if let Some((position, _)) = out_of_view {
+            return Err(format!(
+                "Chunk {} is out of view of the player",
+                position.chunk_position
+            )
+            .into());
+        }
+
+        self.world.set_blocks(blocks).await
+    }
+
+    fn apply_movement(player: &mut Player, movement: Movement, is_started: bool) {
+        match (movement, is_started) {
+            (Movement::Forward, true) => player.start_move_forward(),
+            (Movement::Forward, false) => player.stop_move_forward(),
+            (Movement::Backward, true) => player.start_move_backward(),
+            (Movement::Backward, false) => player.stop_move_backward(),
+            (Movement::Left, true) => player.start_move_left(),
+            (Movement::Left, false) => player.stop_move_left(),
+            (Movement::Right, true) => player.start_move_right(),
+            (Movement::Right, false) => player.stop_move_right(),
+            (Movement::Jump, true) => player.start_jumping(),
+            (Movement::Jump, false) => player.stop_jumping(),
+            (Movement::Sneak, true) => player.start_sneaking(),
+            (Movement::Sneak, false) => player.stop_sneaking(),
+        }
+    }
+
+    /// Reads the next message, failing when the client closed the connection or stayed silent
+    /// for too long
+    async fn read(
+        reader: &mut OwnedReadHalf,
+    ) -> Result<ClientMessage, Box<dyn Error + Send + Sync>> {
+        timeout(KEEP_ALIVE_TIMEOUT, read_message(reader))
+            .await
+            .map_err(|_| "The client timed out")??
+            .ok_or_else(|| "The client closed the connection".into())
+    }
+
+    /// Tells the client why it can not join, and returns that as the error
+    async fn refuse<T>(
+        writer: &mut OwnedWriteHalf,
+        reason: String,
+    ) -> Result<T, Box<dyn Error + Send + Sync>> {
+        write_message(
+            writer,
+            &ServerMessage::Disconnect {
+                reason: reason.clone(),
+            },
+        )
+        .await?;
+        Err(reason.into())
+    }
+
+    /// Compresses the chunk and queues it for the client, waiting while too many chunks are
+    /// queued already. Returns the size of the compressed chunk
+    async fn send_chunk(
+        world: &World,
+        position: ChunkPosition,
+        chunk_permits: &Semaphore,
+        sender: &mpsc::Sender<ServerMessage>,
+    ) -> Result<usize, Box<dyn Error + Send + Sync>> {
+        let permit = chunk_permits.acquire().await?;
+        let bytes = world
+            .get_chunk(position, |chunk| async move { chunk.compress() })
+            .await??;
+        let size = bytes.len();
+        sender
+            .send(ServerMessage::ChunkData { position, bytes })
+            .await
+            .map_err(|_| "The connection is closed")?;
+        // Given back by the writer once the chunk is written
+        permit.forget();
+        Ok(size)
+    }
+
+    async fn write_messages(
+        mut writer: OwnedWriteHalf,
+        mut receiver: mpsc::Receiver<ServerMessage>,
+        chunk_permits: Arc<Semaphore>,
+    ) {
+        while let Some(message) = receiver.recv().await {
+            if let Err(error) = write_message(&mut writer, &message).await {
+                log::warn!("Failed to write to the client: {}", error);
+                return;
+            }
+            if let ServerMessage::ChunkData { .. } = message {
+                chunk_permits.add_permits(1);
+            }
+        }
+    }
+
+    /// Sends the chunks the client requested, and the chunks that come into view of the player,
+    /// nearest first, and tells the client about the chunks that went out of view. At most
+    /// [`STREAM_BYTES_PER_SECOND`] of chunks are streamed, so that streaming does not crowd out
+    /// the other messages
+    async fn stream_chunks(
+        world: Arc<World>,
+        player_id: Uuid,
+        view_distance_receiver: watch::Receiver<u8>,
+        mut request_receiver: mpsc::Receiver<ChunkPosition>,
+        chunk_permits: Arc<Semaphore>,
+        sender: mpsc::Sender<ServerMessage>,
+    ) {
+        let mut interest = ChunkInterest::new(*view_distance_receiver.borrow());
+        let budget_per_interval =
+            (STREAM_BYTES_PER_SECOND as f64 * STREAM_INTERVAL.as_secs_f64()) as i64;
+        let mut budget = 0;
+        let mut interval = interval(STREAM_INTERVAL);
+        loop {
+            interval.tick().await;
+            let position = match world.get_player_position(player_id).await {
+                Some(position) => position,
+                None => return,
+            };
+
+            let mut unloaded = interest.set_view_distance(*view_distance_receiver.borrow());
+            unloaded.append(&mut interest.move_to(position.chunk_position));
+            for position in unloaded {
+                if sender
+                    .send(ServerMessage::UnloadChunk(position))
+                    .await
+                    .is_err()
+                {
+                    return;
+                }
+            }
+
+            // A large chunk can overdraw the budget, which is paid back in the next intervals.
+            // Budget that is left over is not saved up, so standing still does not lead to a burst
+            budget = (budget + budget_per_interval).min(budget_per_interval);
+
+            // The client is waiting on the chunks it requested, so those go first. Requests that
+            // do not fit in the budget wait for the next interval
+            while budget > 0 {
+                let position = match request_receiver.try_recv() {
+                    Ok(position) => position,
+                    Err(_) => break,
+                };
+                // Only chunks in view are sent, so that a client can not make the server generate
+                // the whole world
+                let result = if interest.is_in_range(&position) {
+                    Self::send_chunk(&world, position, &chunk_permits, &sender).await
+                } else {
+                    Err("The chunk is out of view".into())
+                };
+                match result {
+                    Ok(size) => {
+                        budget -= size as i64;
+                        interest.mark_loaded(position);
+                    }
+                    Err(error) => {
+                        log::warn!("Did not send chunk {}: {}", position, error);
+                        // So the client stops waiting for it
+                        if sender
+                            .send(ServerMessage::ChunkUnavailable(position))
+                            .await
+                            .is_err()
+                        {
+                            return;
+                        }
+                    }
+                }
+            }
+
+            while budget > 0 {
+                let position = match interest.next_chunk() {
+                    Some(position) => position,
+                    None => break,
+                };
+                match Self::send_chunk(&world, position, &chunk_permits, &sender).await {
+                    Ok(size) => {
+                        budget -= size as i64;
+                        interest.mark_loaded(position);
+                    }
+                    Err(error) => {
+                        log::error!("Failed to stream chunk {}: {}", position, error);
+                        if sender.is_closed() {
+                            return;
+                        }
+                        // Tried again in the next interval, after the other chunks
+                        interest.retry_later(position);
+                        break;
+                    }
+                }
+            }
+        }
+    }
+
+    async fn forward_world_events(world: Arc<World>, sender: mpsc::Sender<ServerMessage>) {
+        let mut receiver = world.get_event_receiver();
+        loop {
+            match receiver.recv().await {
+                Ok(event) => {
+                    if sender.send(ServerMessage::from(event)).await.is_err() {
+                        return;
+                    }
+                }
+                Err(RecvError::Lagged(skipped)) => {
+                    log::warn!("The client fell behind, skipped {} world events", skipped)
+                }
+                Err(RecvError::Closed) => return,
+            }
+        }
+    }
+
+    async fn send_keep_alives(sender: mpsc::Sender<ServerMessage>) {
+        let mut interval = interval(KEEP_ALIVE_INTERVAL);
+        loop {
+            interval.tick().await;
+            if sender.send(ServerMessage::KeepAlive).await.is_err() {
+                return;
+            }
+        }
+    }
+}
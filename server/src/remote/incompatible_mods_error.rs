@@ -0,0 +1,22 @@
+use crate::remote::ManifestMismatch;
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+
+/// The client and the server do not have the same mods, so they can not play together
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IncompatibleModsError {
+    /// As seen from the server
+    pub mismatches: Vec<ManifestMismatch>,
+}
+
+impl Display for IncompatibleModsError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "The mods do not match those of the server:")?;
+        for mismatch in &self.mismatches {
+            write!(f, "\n  {}", mismatch)?;
+        }
+        Ok(())
+    }
+}
+
+impl Error for IncompatibleModsError {}
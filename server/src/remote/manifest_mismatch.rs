@@ -0,0 +1,57 @@
+use std::fmt::{Display, Formatter};
+use voxelcraft_id::{BlockId, DimensionId, ModId};
+
+/// A single difference between the mod manifests of two sides of a connection, seen from one of
+/// the sides
+#[derive(Debug, Clone, PartialEq, Eq, bincode::Encode, bincode::Decode)]
+pub enum ManifestMismatch {
+    /// This side has the mod, the other side does not
+    MissingMod {
+        id: ModId,
+        name: String,
+        version: String,
+    },
+    /// The other side has the mod, this side does not
+    UnexpectedMod {
+        id: ModId,
+        name: String,
+        version: String,
+    },
+    ModVersion {
+        id: ModId,
+        name: String,
+        version: String,
+        other_version: String,
+    },
+    MissingBlock(BlockId),
+    UnexpectedBlock(BlockId),
+    MissingDimension(DimensionId),
+    UnexpectedDimension(DimensionId),
+}
+
+impl Display for ManifestMismatch {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::MissingMod { id, name, version } => {
+                write!(f, "Mod '{}' {} ({:?}) is missing", name, version, id)
+            }
+            Self::UnexpectedMod { id, name, version } => {
+                write!(f, "Mod '{}' {} ({:?}) is not expected", name, version, id)
+            }
+            Self::ModVersion {
+                id,
+                name,
+                version,
+                other_version,
+            } => write!(
+                f,
+                "Mod '{}' ({:?}) has version {}, but version {} is expected",
+                name, id, other_version, version
+            ),
+            Self::MissingBlock(id) => write!(f, "Block {:?} is missing", id),
+            Self::UnexpectedBlock(id) => write!(f, "Block {:?} is not expected", id),
+            Self::MissingDimension(id) => write!(f, "Dimension {} is missing", id),
+            Self::UnexpectedDimension(id) => write!(f, "Dimension {} is not expected", id),
+        }
+    }
+}
@@ -1 +1,25 @@
+mod chunk_interest;
+mod client_message;
+mod connection;
+mod incompatible_mods_error;
+mod manifest_mismatch;
+mod mod_manifest;
+mod movement;
+mod protocol;
+mod remote_client;
+mod server;
+mod server_message;
 
+pub use self::chunk_interest::{DEFAULT_VIEW_DISTANCE, MAX_VIEW_DISTANCE};
+pub use self::client_message::{ClientMessage, MAX_BLOCKS_PER_MESSAGE};
+pub use self::incompatible_mods_error::IncompatibleModsError;
+pub use self::manifest_mismatch::ManifestMismatch;
+pub use self::mod_manifest::ModManifest;
+pub use self::movement::Movement;
+pub use self::protocol::{
+    read_message, write_message, KEEP_ALIVE_INTERVAL, KEEP_ALIVE_TIMEOUT, MAX_FRAME_SIZE,
+    PROTOCOL_VERSION,
+};
+pub use self::remote_client::RemoteClient;
+pub use self::server::Server;
+pub use self::server_message::ServerMessage;
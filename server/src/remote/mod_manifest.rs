@@ -0,0 +1,175 @@
+use crate::remote::ManifestMismatch;
+use std::collections::{BTreeMap, BTreeSet};
+use voxelcraft_id::{BlockId, DimensionId, ModId};
+use voxelcraft_mod::ModPack;
+
+/// Everything about a mod pack that the client and the server have to agree on to play together
+#[derive(Debug, Clone, PartialEq, Eq, bincode::Encode, bincode::Decode)]
+pub struct ModManifest {
+    /// The mods ordered by id, with their names and versions
+    pub mods: Vec<(ModId, String, String)>,
+    pub blocks: Vec<BlockId>,
+    pub dimensions: Vec<DimensionId>,
+}
+
+impl ModManifest {
+    /// Collects the mods of the pack, and the blocks and dimensions they register
+    pub async fn new(mod_pack: &dyn ModPack) -> Self {
+        let mut mods = BTreeMap::new();
+        let mut blocks = BTreeSet::new();
+        let mut dimensions = BTreeSet::new();
+        for module in mod_pack.mods() {
+            mods.insert(
+                module.id().clone(),
+                (module.name().to_string(), module.version().to_string()),
+            );
+            for block in module.register_blocks().await {
+                blocks.insert(*block.block_id());
+            }
+            // Only the ids are needed, so the seed does not matter
+            for dimension in module.register_dimensions(0).await {
+                dimensions.insert(*dimension.id());
+            }
+        }
+
+        Self {
+            mods: mods
+                .into_iter()
+                .map(|(id, (name, version))| (id, name, version))
+                .collect(),
+            blocks: blocks.into_iter().collect(),
+            dimensions: dimensions.into_iter().collect(),
+        }
+    }
+
+    /// Everything that differs between this manifest and the manifest of the other side, from
+    /// the point of view of this side
+    pub fn mismatches(&self, other: &Self) -> Vec<ManifestMismatch> {
+        let mut mismatches = vec![];
+
+        for (id, name, version) in &self.mods {
+            match other.mods.iter().find(|(other_id, _, _)| other_id == id) {
+                None => mismatches.push(ManifestMismatch::MissingMod {
+                    id: id.clone(),
+                    name: name.clone(),
+                    version: version.clone(),
+                }),
+                Some((_, _, other_version)) if other_version != version => {
+                    mismatches.push(ManifestMismatch::ModVersion {
+                        id: id.clone(),
+                        name: name.clone(),
+                        version: version.clone(),
+                        other_version: other_version.clone(),
+                    })
+                }
+                Some(_) => {}
+            }
+        }
+        for (id, name, version) in &other.mods {
+            if !self.mods.iter().any(|(own_id, _, _)| own_id == id) {
+                mismatches.push(ManifestMismatch::UnexpectedMod {
+                    id: id.clone(),
+                    name: name.clone(),
+                    version: version.clone(),
+                });
+            }
+        }
+
+        let own_blocks = self.blocks.iter().collect::<BTreeSet<_>>();
+        let other_blocks = other.blocks.iter().collect::<BTreeSet<_>>();
+        for block_id in own_blocks.difference(&other_blocks) {
+            mismatches.push(ManifestMismatch::MissingBlock(**block_id));
+        }
+        for block_id in other_blocks.difference(&own_blocks) {
+            mismatches.push(ManifestMismatch::UnexpectedBlock(**block_id));
+        }
+
+        let own_dimensions = self.dimensions.iter().collect::<BTreeSet<_>>();
+        let other_dimensions = other.dimensions.iter().collect::<BTreeSet<_>>();
+        for dimension_id in own_dimensions.difference(&other_dimensions) {
+            mismatches.push(ManifestMismatch::MissingDimension(**dimension_id));
+        }
+        for dimension_id in other_dimensions.difference(&own_dimensions) {
+            mismatches.push(ManifestMismatch::UnexpectedDimension(**dimension_id));
+        }
+
+        mismatches
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::remote::{ManifestMismatch, ModManifest};
+    use voxelcraft_id::{BlockId, DimensionId, ModId};
+
+    fn manifest() -> ModManifest {
+        ModManifest {
+            mods: vec![
+                (
+                    ModId::from_u128(1),
+                    "Blocks".to_string(),
+                    "1.0.0".to_string(),
+                ),
+                (
+                    ModId::from_u128(2),
+                    "World".to_string(),
+                    "0.3.1".to_string(),
+                ),
+            ],
+            blocks: vec![BlockId::from_u128(10), BlockId::from_u128(11)],
+            dimensions: vec![DimensionId::from_u128(0)],
+        }
+    }
+
+    #[test]
+    fn it_should_find_no_mismatches_between_equal_manifests() {
+        assert_eq!(manifest().mismatches(&manifest()), vec![]);
+    }
+
+    #[test]
+    fn it_should_list_every_mismatch() {
+        let other = ModManifest {
+            mods: vec![
+                (
+                    ModId::from_u128(2),
+                    "World".to_string(),
+                    "0.4.0".to_string(),
+                ),
+                (
+                    ModId::from_u128(3),
+                    "Extra".to_string(),
+                    "2.0.0".to_string(),
+                ),
+            ],
+            blocks: vec![BlockId::from_u128(11), BlockId::from_u128(12)],
+            dimensions: vec![DimensionId::from_u128(0), DimensionId::from_u128(5)],
+        };
+
+        let mismatches = manifest().mismatches(&other);
+
+        assert_eq!(
+            mismatches,
+            vec![
+                ManifestMismatch::MissingMod {
+                    id: ModId::from_u128(1),
+                    name: "Blocks".to_string(),
+                    version: "1.0.0".to_string(),
+                },
+                ManifestMismatch::ModVersion {
+                    id: ModId::from_u128(2),
+                    name: "World".to_string(),
+                    version: "0.3.1".to_string(),
+                    other_version: "0.4.0".to_string(),
+                },
+                ManifestMismatch::UnexpectedMod {
+                    id: ModId::from_u128(3),
+                    name: "Extra".to_string(),
+                    version: "2.0.0".to_string(),
+                },
+                ManifestMismatch::MissingBlock(BlockId::from_u128(10)),
+                ManifestMismatch::UnexpectedBlock(BlockId::from_u128(12)),
+                ManifestMismatch::UnexpectedDimension(DimensionId::from_u128(5)),
+            ]
+        );
+    }
+}
@@ -0,0 +1,10 @@
+/// The ways a player can move, which are started and stopped by the client
+#[derive(Debug, Clone, Copy, PartialEq, Eq, bincode::Encode, bincode::Decode)]
+pub enum Movement {
+    Forward,
+    Backward,
+    Left,
+    Right,
+    Jump,
+    Sneak,
+}
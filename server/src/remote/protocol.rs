@@ -0,0 +1,132 @@
+use bincode::config;
+use bincode::{Decode, Encode};
+use std::error::Error;
+use std::io::ErrorKind;
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+/// Has to match between the client and the server, and is bumped whenever a message changes
+pub const PROTOCOL_VERSION: u32 = 5;
+
+/// The largest frame that is accepted, which leaves plenty of room for a compressed chunk
+pub const MAX_FRAME_SIZE: usize = 4 * 1024 * 1024;
+
+/// How often a keep alive is sent, so the other side knows the connection is still there
+pub const KEEP_ALIVE_INTERVAL: Duration = Duration::from_secs(5);
+
+/// How long the other side may stay silent before the connection is dropped
+pub const KEEP_ALIVE_TIMEOUT: Duration = Duration::from_secs(20);
+
+/// Writes the message as a single frame: the length of the message as a big endian `u32`,
+/// followed by the message encoded with bincode
+pub async fn write_message<W: AsyncWrite + Unpin, M: Encode>(
+    writer: &mut W,
+    message: &M,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let bytes = bincode::encode_to_vec(message, config::standard())?;
+    if bytes.len() > MAX_FRAME_SIZE {
+        return Err(format!(
+            "The message is {} bytes, but frames can be at most {} bytes",
+            bytes.len(),
+            MAX_FRAME_SIZE
+        )
+        .into());
+    }
+
+    writer.write_u32(bytes.len() as u32).await?;
+    writer.write_all(&bytes).await?;
+    writer.flush().await?;
+    Ok(())
+}
+
+/// Reads a single frame written by [`write_message`], or returns `None` if the other side closed
+/// the connection
+pub async fn read_message<R: AsyncRead + Unpin, M: Decode>(
+    reader: &mut R,
+) -> Result<Option<M>, Box<dyn Error + Send + Sync>> {
+    let length = match reader.read_u32().await {
+        Ok(length) => length as usize,
+        Err(error) if error.kind() == ErrorKind::UnexpectedEof => return Ok(None),
+        Err(error) => return Err(error.into()),
+    };
+    if length > MAX_FRAME_SIZE {
+        return Err(format!(
+            "Received a frame of {} bytes, but frames can be at most {} bytes",
+            length, MAX_FRAME_SIZE
+        )
+        .into());
+    }
+
+    let mut bytes = vec![0; length];
+    reader.read_exact(&mut bytes).await?;
+    // The limit keeps lengths in the message from allocating more than the frame could hold
+    let config = config::standard().with_limit::<MAX_FRAME_SIZE>();
+    let (message, read) = bincode::decode_from_slice(&bytes, config)?;
+    if read != length {
+        return Err(format!(
+            "Only {} of the {} bytes in the frame were read",
+            read, length
+        )
+        .into());
+    }
+    Ok(Some(message))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::remote::protocol::{read_message, write_message, MAX_FRAME_SIZE};
+    use crate::remote::{ClientMessage, Movement};
+    use bincode::config;
+    use pollster::FutureExt;
+
+    #[test]
+    fn it_should_read_back_the_messages_that_were_written() {
+        let messages = vec![
+            ClientMessage::Handshake {
+                protocol_version: 3,
+            },
+            ClientMessage::StartMoving(Movement::Jump),
+            ClientMessage::HeadRotation {
+                pitch: -12.5,
+                yaw: 270.0,
+            },
+        ];
+        let mut bytes = vec![];
+        for message in &messages {
+            write_message(&mut bytes, message).block_on().unwrap();
+        }
+
+        let mut reader = bytes.as_slice();
+        for message in messages {
+            let read: Option<ClientMessage> = read_message(&mut reader).block_on().unwrap();
+            assert_eq!(read, Some(message));
+        }
+        let end: Option<ClientMessage> = read_message(&mut reader).block_on().unwrap();
+        assert_eq!(end, None);
+    }
+
+    #[test]
+    fn it_should_refuse_frames_that_are_too_large() {
+        let bytes = ((MAX_FRAME_SIZE + 1) as u32).to_be_bytes();
+
+        let read = read_message::<_, ClientMessage>(&mut bytes.as_slice()).block_on();
+
+        assert!(read.is_err());
+    }
+
+    #[test]
+    fn it_should_refuse_messages_that_claim_more_than_the_frame_can_hold() {
+        let mut message =
+            bincode::encode_to_vec(ClientMessage::SetBlocks(vec![]), config::standard()).unwrap();
+        // Replaces the empty length with a length that takes the varint's 8 byte form
+        assert_eq!(message.pop(), Some(0));
+        message.push(253);
+        message.extend_from_slice(&(1u64 << 40).to_le_bytes());
+        let mut bytes = (message.len() as u32).to_be_bytes().to_vec();
+        bytes.extend(message);
+
+        let read = read_message::<_, ClientMessage>(&mut bytes.as_slice()).block_on();
+
+        assert!(read.is_err());
+    }
+}
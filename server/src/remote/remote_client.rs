@@ -0,0 +1,555 @@
+use crate::client::Client;
+use crate::event::WorldEvent;
+use crate::remote::protocol::{
+    read_message, write_message, KEEP_ALIVE_INTERVAL, KEEP_ALIVE_TIMEOUT, PROTOCOL_VERSION,
+};
+use crate::remote::{ClientMessage, IncompatibleModsError, ModManifest, Movement, ServerMessage};
+use crate::{Chunk, CHUNK_SIZE};
+use cgmath::Deg;
+use std::collections::HashMap;
+use std::error::Error;
+use std::future::Future;
+use std::sync::Arc;
+use tokio::net::tcp::{OwnedReadHalf, OwnedWriteHalf};
+use tokio::net::{TcpStream, ToSocketAddrs};
+use tokio::sync::{broadcast, mpsc, oneshot, watch, Mutex, OwnedRwLockReadGuard, RwLock};
+use tokio::task::JoinHandle;
+use tokio::time::{interval, timeout};
+use uuid::Uuid;
+use voxelcraft_core::block::BlockPosition;
+use voxelcraft_core::chunk::ChunkPosition;
+use voxelcraft_core::entity::EntityPosition;
+use voxelcraft_id::BlockId;
+use voxelcraft_mod::ModPack;
+
+const CHANNEL_SIZE: usize = 1024;
+
+/// Answers a caller waiting for a chunk
+type ChunkWaiter = oneshot::Sender<Result<Arc<RwLock<Chunk>>, Box<dyn Error + Send + Sync>>>;
+/// Answers the caller waiting to join the world
+type LoginWaiter = oneshot::Sender<Result<(), Box<dyn Error + Send + Sync>>>;
+
+/// Plays in a world on a [`Server`], through the same [`Client`] trait as a local world.
+///
+/// The server streams the chunks around the player to the client, and tells it when they went
+/// out of view again. Those chunks are kept in between, so that reading them does not need a
+/// round trip
+///
+/// [`Server`]: crate::remote::Server
+#[derive(Debug)]
+pub struct RemoteClient {
+    player_id: Uuid,
+    state: Arc<State>,
+    message_sender: mpsc::Sender<ClientMessage>,
+    /// Turns `true` once the connection is closed
+    closed_receiver: watch::Receiver<bool>,
+    tasks: Vec<JoinHandle<()>>,
+}
+
+/// What the client knows about the world, shared with the task that reads from the server
+#[derive(Debug)]
+struct State {
+    player_id: Uuid,
+    position: Mutex<Option<EntityPosition>>,
+    chunks: RwLock<HashMap<ChunkPosition, Arc<RwLock<Chunk>>>>,
+    /// The callers waiting for a chunk that was requested from the server
+    chunk_waiters: Mutex<HashMap<ChunkPosition, Vec<ChunkWaiter>>>,
+    login_waiter: Mutex<Option<LoginWaiter>>,
+    event_sender: broadcast::Sender<WorldEvent>,
+}
+
+impl RemoteClient {
+    /// Connects to the server, does the handshake and makes sure the mods match. The player
+    /// joins the world with [`Client::begin_joining_world`]
+    ///
+    /// # Errors
+    /// An [`IncompatibleModsError`] if the mods do not match those of the server
+    pub async fn connect<A: ToSocketAddrs>(
+        address: A,
+        player_id: Uuid,
+        mod_pack: &dyn ModPack,
+    ) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        let stream = TcpStream::connect(address).await?;
+        stream.set_nodelay(true)?;
+        let (mut reader, mut writer) = stream.into_split();
+
+        write_message(
+            &mut writer,
+            &ClientMessage::Handshake {
+                protocol_version: PROTOCOL_VERSION,
+            },
+        )
+        .await?;
+        match Self::read(&mut reader).await? {
+            Some(ServerMessage::HandshakeAccepted) => {}
+            Some(ServerMessage::Disconnect { reason }) => {
+                return Err(format!("The server refused the connection: {}", reason).into())
+            }
+            Some(message) => return Err(format!("Expected a handshake, got {:?}", message).into()),
+            None => return Err("The server closed the connection".into()),
+        }
+
+        let manifest = ModManifest::new(mod_pack).await;
+        write_message(&mut writer, &ClientMessage::Manifest(manifest.clone())).await?;
+        match Self::read(&mut reader).await? {
+            Some(ServerMessage::ManifestAccepted(server_manifest)) => {
+                let mismatches = server_manifest.mismatches(&manifest);
+                if !mismatches.is_empty() {
+                    return Err(IncompatibleModsError { mismatches }.into());
+                }
+            }
+            Some(ServerMessage::ModsMismatched(mismatches)) => {
+                return Err(IncompatibleModsError { mismatches }.into())
+            }
+            Some(ServerMessage::Disconnect { reason }) => {
+                return Err(format!("The server refused the connection: {}", reason).into())
+            }
+            Some(message) => return Err(format!("Expected a manifest, got {:?}", message).into()),
+            None => return Err("The server closed the connection".into()),
+        }
+
+        let (event_sender, _) = broadcast::channel(CHANNEL_SIZE);
+        let state = Arc::new(State {
+            player_id,
+            position: Mutex::new(None),
+            chunks: RwLock::new(HashMap::new()),
+            chunk_waiters: Mutex::new(HashMap::new()),
+            login_waiter: Mutex::new(None),
+            event_sender,
+        });
+        let (message_sender, message_receiver) = mpsc::channel(CHANNEL_SIZE);
+        let (closed_sender, closed_receiver) = watch::channel(false);
+        let tasks = vec![
+            tokio::spawn(Self::read_messages(
+                reader,
+                Arc::clone(&state),
+                closed_sender,
+            )),
+            tokio::spawn(Self::write_messages(writer, message_receiver)),
+            tokio::spawn(Self::send_keep_alives(message_sender.clone())),
+        ];
+
+        Ok(Self {
+            player_id,
+            state,
+            message_sender,
+            closed_receiver,
+            tasks,
+        })
+    }
+
+    pub fn is_connected(&self) -> bool {
+        !*self.closed_receiver.borrow()
+    }
+
+    /// How far the player wants to see, in chunks. The server limits this to
+    /// [`MAX_VIEW_DISTANCE`], and starts out at [`DEFAULT_VIEW_DISTANCE`]
+    ///
+    /// [`MAX_VIEW_DISTANCE`]: crate::remote::MAX_VIEW_DISTANCE
+    /// [`DEFAULT_VIEW_DISTANCE`]: crate::remote::DEFAULT_VIEW_DISTANCE
+    pub async fn set_view_distance(
+        &self,
+        view_distance: u8,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        self.send(ClientMessage::SetViewDistance(view_distance))
+            .await
+    }
+
+    /// Forgets a chunk that was received earlier, it is requested again the next time it is
+    /// needed
+    pub async fn forget_chunk(&self, position: &ChunkPosition) {
+        self.state.chunks.write().await.remove(position);
+    }
+
+    async fn send(&self, message: ClientMessage) -> Result<(), Box<dyn Error + Send + Sync>> {
+        self.message_sender
+            .send(message)
+            .await
+            .map_err(|_| "Not connected to the server".into())
+    }
+
+    /// For the calls of the [`Client`] trait that can not return an error
+    async fn send_or_log(&self, message: ClientMessage) {
+        if let Err(error) = self.send(message).await {
+            log::warn!("Failed to send to the server: {}", error);
+        }
+    }
+
+    async fn chunk(
+        &self,
+        position: ChunkPosition,
+    ) -> Result<Arc<RwLock<Chunk>>, Box<dyn Error + Send + Sync>> {
+        let receiver = {
+            // Chunks are only added while holding the waiters, so a chunk can not arrive between
+            // looking for it and waiting for it
+            let mut waiters = self.state.chunk_waiters.lock().await;
+            if let Some(chunk) = self.state.chunks.read().await.get(&position) {
+                return Ok(Arc::clone(chunk));
+            }
+
+            let (sender, receiver) = oneshot::channel();
+            let waiting = waiters.entry(position).or_default();
+            waiting.push(sender);
+            if waiting.len() == 1 {
+                self.send(ClientMessage::RequestChunk(position)).await?;
+            }
+            receiver
+        };
+
+        receiver
+            .await
+            .map_err(|_| "The connection closed before the chunk arrived")?
+    }
+
+    async fn read(
+        reader: &mut OwnedReadHalf,
+    ) -> Result<Option<ServerMessage>, Box<dyn Error + Send + Sync>> {
+        timeout(KEEP_ALIVE_TIMEOUT, read_message(reader))
+            .await
+            .map_err(|_| "The server timed out")?
+    }
+
+    async fn read_messages(
+        mut reader: OwnedReadHalf,
+        state: Arc<State>,
+        closed_sender: watch::Sender<bool>,
+    ) {
+        loop {
+            match Self::read(&mut reader).await {
+                Ok(Some(message)) => {
+                    if let Err(error) = state.handle_message(message).await {
+                        log::warn!("Lost the connection to the server: {}", error);
+                        break;
+                    }
+                }
+                Ok(None) => {
+                    log::info!("The server closed the connection");
+                    break;
+                }
+                Err(error) => {
+                    log::warn!("Lost the connection to the server: {}", error);
+                    break;
+                }
+            }
+        }
+
+        // Whoever is still waiting will not get an answer anymore
+        state.chunk_waiters.lock().await.clear();
+        if let Some(waiter) = state.login_waiter.lock().await.take() {
+            let _ = waiter.send(Err("The server closed the connection".into()));
+        }
+        let _ = closed_sender.send(true);
+    }
+
+    async fn write_messages(
+        mut writer: OwnedWriteHalf,
+        mut receiver: mpsc::Receiver<ClientMessage>,
+    ) {
+        while let Some(message) = receiver.recv().await {
+            if let Err(error) = write_message(&mut writer, &message).await {
+                log::warn!("Failed to write to the server: {}", error);
+                return;
+            }
+        }
+    }
+
+    async fn send_keep_alives(sender: mpsc::Sender<ClientMessage>) {
+        let mut interval = interval(KEEP_ALIVE_INTERVAL);
+        loop {
+            interval.tick().await;
+            if sender.send(ClientMessage::KeepAlive).await.is_err() {
+                return;
+            }
+        }
+    }
+}
+
+impl State {
+    /// Fails when the server ends the connection
+    async fn handle_message(
+        &self,
+        message: ServerMessage,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        if let Some(event) = message.world_event() {
+            match &event {
+                WorldEvent::EntityPositionChanged(entity_id, position) => {
+                    if *entity_id == self.player_id {
+                        *self.position.lock().await = Some(position.clone());
+                    }
+                }
+                WorldEvent::BlockChanged(position, block_id) => {
+                    self.apply_block_changes(&[(position.clone(), *block_id)])
+                        .await
+                }
+            }
+            // Nobody listening is fine
+            let _ = self.event_sender.send(event);
+            return Ok(());
+        }
+
+        match message {
+            ServerMessage::LoggedIn { position } => {
+                *self.position.lock().await = Some(position);
+                if let Some(waiter) = self.login_waiter.lock().await.take() {
+                    let _ = waiter.send(Ok(()));
+                }
+            }
+            ServerMessage::ChunkData { position, bytes } => {
+                let chunk = Arc::new(RwLock::new(Chunk::from_compressed(&bytes)?));
+                let mut waiters = self.chunk_waiters.lock().await;
+                self.chunks
+                    .write()
+                    .await
+                    .insert(position, Arc::clone(&chunk));
+                for waiter in waiters.remove(&position).unwrap_or_default() {
+                    let _ = waiter.send(Ok(Arc::clone(&chunk)));
+                }
+            }
+            ServerMessage::ChunkUnavailable(position) => {
+                let reason = format!("The server did not send chunk {}", position);
+                let waiters = self.chunk_waiters.lock().await.remove(&position);
+                for waiter in waiters.unwrap_or_default() {
+                    let _ = waiter.send(Err(reason.clone().into()));
+                }
+            }
+            ServerMessage::UnloadChunk(position) => {
+                self.chunks.write().await.remove(&position);
+            }
+            ServerMessage::BlocksRefused { reason } => {
+                log::warn!("The server refused to set blocks: {}", reason)
+            }
+            ServerMessage::Disconnect { reason } => {
+                if let Some(waiter) = self.login_waiter.lock().await.take() {
+                    let _ = waiter.send(Err(reason.clone().into()));
+                }
+                return Err(format!("The server disconnected us: {}", reason).into());
+            }
+            ServerMessage::KeepAlive => {}
+            message => log::warn!("Unexpected message from the server: {:?}", message),
+        }
+        Ok(())
+    }
+
+    /// Applies block changes to the chunks that were received, so they match the server
+    async fn apply_block_changes(&self, blocks: &[(BlockPosition<CHUNK_SIZE>, BlockId)]) {
+        let chunks = self.chunks.read().await;
+        for (position, block_id) in blocks {
+            if let Some(chunk) = chunks.get(&position.chunk_position) {
+                chunk.write().await.set(*block_id, &position.offset);
+            }
+        }
+    }
+}
+
+impl Drop for RemoteClient {
+    fn drop(&mut self) {
+        for task in &self.tasks {
+            task.abort();
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Client for RemoteClient {
+    async fn get_world_event_receiver(&self) -> broadcast::Receiver<WorldEvent> {
+        self.state.event_sender.subscribe()
+    }
+
+    async fn begin_joining_world(&self) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let (sender, receiver) = oneshot::channel();
+        *self.state.login_waiter.lock().await = Some(sender);
+        self.send(ClientMessage::Login {
+            player_id: self.player_id.as_u128(),
+        })
+        .await?;
+        receiver
+            .await
+            .map_err(|_| "The connection closed while logging in")?
+    }
+
+    async fn join_world(&self) {}
+
+    /// Waits until the server has saved the player and closed the connection
+    async fn leave_world(&self) -> Result<(), Box<dyn Error + Send + Sync>> {
+        self.send(ClientMessage::Disconnect).await?;
+        let mut closed_receiver = self.closed_receiver.clone();
+        timeout(KEEP_ALIVE_TIMEOUT, async move {
+            while !*closed_receiver.borrow() {
+                if closed_receiver.changed().await.is_err() {
+                    break;
+                }
+            }
+        })
+        .await
+        .map_err(|_| "The server did not close the connection".into())
+    }
+
+    async fn position(&self) -> EntityPosition {
+        self.state
+            .position
+            .lock()
+            .await
+            .clone()
+            // Only before joining the world
+            .unwrap_or_else(|| {
+                EntityPosition::from_absolute::<CHUNK_SIZE>(0.0, 0.0, 0.0, Default::default())
+            })
+    }
+
+    async fn get_chunk<
+        C: Send + Sync + FnOnce(OwnedRwLockReadGuard<Chunk>) -> FR,
+        FR: Future<Output = R> + Send,
+        R: Send + Sync,
+    >(
+        &self,
+        chunk_position: ChunkPosition,
+        callback: C,
+    ) -> Result<R, Box<dyn Error + Send + Sync>> {
+        let chunk = self.chunk(chunk_position).await?;
+        let guard = chunk.read_owned().await;
+        Ok(callback(guard).await)
+    }
+
+    async fn get_block(
+        &self,
+        position: &BlockPosition<CHUNK_SIZE>,
+    ) -> Result<BlockId, Box<dyn Error + Send + Sync>> {
+        let chunk = self.chunk(position.chunk_position).await?;
+        let block_id = *chunk.read().await.get(&position.offset);
+        Ok(block_id)
+    }
+
+    async fn set_block(
+        &self,
+        position: &BlockPosition<CHUNK_SIZE>,
+        block_id: BlockId,
+    ) -> Result<BlockId, Box<dyn Error + Send + Sync>> {
+        let previous = self.get_block(position).await?;
+        self.set_blocks(vec![(position.clone(), block_id)]).await?;
+        Ok(previous)
+    }
+
+    async fn set_blocks(
+        &self,
+        blocks: Vec<(BlockPosition<CHUNK_SIZE>, BlockId)>,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        // Applied right away, the server confirms them later with a broadcast
+        self.state.apply_block_changes(&blocks).await;
+        self.send(ClientMessage::SetBlocks(blocks)).await
+    }
+
+    async fn start_move_forward(&self) {
+        self.send_or_log(ClientMessage::StartMoving(Movement::Forward))
+            .await
+    }
+
+    async fn start_move_backward(&self) {
+        self.send_or_log(ClientMessage::StartMoving(Movement::Backward))
+            .await
+    }
+
+    async fn start_move_right(&self) {
+        self.send_or_log(ClientMessage::StartMoving(Movement::Right))
+            .await
+    }
+
+    async fn start_move_left(&self) {
+        self.send_or_log(ClientMessage::StartMoving(Movement::Left))
+            .await
+    }
+
+    async fn start_jump(&self) {
+        self.send_or_log(ClientMessage::StartMoving(Movement::Jump))
+            .await
+    }
+
+    async fn start_sneak(&self) {
+        self.send_or_log(ClientMessage::StartMoving(Movement::Sneak))
+            .await
+    }
+
+    async fn stop_move_forward(&self) {
+        self.send_or_log(ClientMessage::StopMoving(Movement::Forward))
+            .await
+    }
+
+    async fn stop_move_backward(&self) {
+        self.send_or_log(ClientMessage::StopMoving(Movement::Backward))
+            .await
+    }
+
+    async fn stop_move_right(&self) {
+        self.send_or_log(ClientMessage::StopMoving(Movement::Right))
+            .await
+    }
+
+    async fn stop_move_left(&self) {
+        self.send_or_log(ClientMessage::StopMoving(Movement::Left))
+            .await
+    }
+
+    async fn stop_jump(&self) {
+        self.send_or_log(ClientMessage::StopMoving(Movement::Jump))
+            .await
+    }
+
+    async fn stop_sneak(&self) {
+        self.send_or_log(ClientMessage::StopMoving(Movement::Sneak))
+            .await
+    }
+
+    async fn set_flying(&self, is_flying: bool) {
+        self.send_or_log(ClientMessage::SetFlying(is_flying)).await
+    }
+
+    async fn set_pitch_yaw(&self, pitch: Deg<f32>, yaw: Deg<f32>) {
+        self.send_or_log(ClientMessage::HeadRotation {
+            pitch: pitch.0,
+            yaw: yaw.0,
+        })
+        .await
+    }
+
+    fn player_id(&self) -> Uuid {
+        self.player_id
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::remote::remote_client::State;
+    use crate::remote::ServerMessage;
+    use pollster::FutureExt;
+    use std::collections::HashMap;
+    use tokio::sync::{broadcast, oneshot, Mutex, RwLock};
+    use uuid::Uuid;
+    use voxelcraft_core::chunk::ChunkPosition;
+
+    #[test]
+    fn it_should_fail_the_callers_waiting_for_a_chunk_that_is_unavailable() {
+        let (event_sender, _) = broadcast::channel(1);
+        let state = State {
+            player_id: Uuid::new_v4(),
+            position: Mutex::new(None),
+            chunks: RwLock::new(HashMap::new()),
+            chunk_waiters: Mutex::new(HashMap::new()),
+            login_waiter: Mutex::new(None),
+            event_sender,
+        };
+        let position = ChunkPosition::default();
+        let (sender, receiver) = oneshot::channel();
+        state
+            .chunk_waiters
+            .lock()
+            .block_on()
+            .insert(position, vec![sender]);
+
+        state
+            .handle_message(ServerMessage::ChunkUnavailable(position))
+            .block_on()
+            .unwrap();
+
+        assert!(receiver.block_on().unwrap().is_err());
+        assert!(state.chunk_waiters.lock().block_on().is_empty());
+    }
+}
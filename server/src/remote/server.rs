@@ -0,0 +1,57 @@
+use crate::remote::connection::Connection;
+use crate::remote::ModManifest;
+use crate::world::World;
+use std::error::Error;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tokio::net::{TcpListener, ToSocketAddrs};
+
+/// Lets clients play in a shared world over TCP.
+///
+/// The server does not tick the world, that is up to whoever started it
+#[derive(Debug)]
+pub struct Server {
+    listener: TcpListener,
+    world: Arc<World>,
+    manifest: Arc<ModManifest>,
+}
+
+impl Server {
+    pub async fn bind<A: ToSocketAddrs>(
+        address: A,
+        world: Arc<World>,
+    ) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        let listener = TcpListener::bind(address).await?;
+        log::info!("Listening on {}", listener.local_addr()?);
+        let manifest = Arc::new(ModManifest::new(world.mod_pack().as_ref()).await);
+        Ok(Self {
+            listener,
+            world,
+            manifest,
+        })
+    }
+
+    pub fn local_address(&self) -> Result<SocketAddr, Box<dyn Error + Send + Sync>> {
+        Ok(self.listener.local_addr()?)
+    }
+
+    /// Accepts clients until the future is dropped
+    pub async fn run(self) {
+        loop {
+            match self.listener.accept().await {
+                Ok((stream, address)) => {
+                    if let Err(error) = stream.set_nodelay(true) {
+                        log::warn!("Failed to disable Nagle for {}: {}", address, error);
+                    }
+                    let connection = Connection::new(
+                        Arc::clone(&self.world),
+                        Arc::clone(&self.manifest),
+                        address,
+                    );
+                    tokio::spawn(connection.run(stream));
+                }
+                Err(error) => log::error!("Failed to accept a client: {}", error),
+            }
+        }
+    }
+}
@@ -0,0 +1,84 @@
+use crate::event::WorldEvent;
+use crate::remote::{ManifestMismatch, ModManifest};
+use crate::CHUNK_SIZE;
+use uuid::Uuid;
+use voxelcraft_core::block::BlockPosition;
+use voxelcraft_core::chunk::ChunkPosition;
+use voxelcraft_core::entity::EntityPosition;
+use voxelcraft_id::BlockId;
+
+/// The messages the server sends to a client
+#[derive(Debug, Clone, PartialEq, bincode::Encode, bincode::Decode)]
+pub enum ServerMessage {
+    HandshakeAccepted,
+    /// The mods of the client match those of the server, which are sent along
+    ManifestAccepted(ModManifest),
+    /// The mods of the client do not match those of the server, which closes the connection
+    /// after this message
+    ModsMismatched(Vec<ManifestMismatch>),
+    /// The player is in the world, at the given position
+    LoggedIn {
+        position: EntityPosition,
+    },
+    /// The server closes the connection after this message
+    Disconnect {
+        reason: String,
+    },
+    KeepAlive,
+    /// The chunk, compressed the same way it is stored. Sent when it was requested, and when it
+    /// comes into view of the player
+    ChunkData {
+        position: ChunkPosition,
+        bytes: Vec<u8>,
+    },
+    /// The chunk went out of view of the player, so the client can forget it
+    UnloadChunk(ChunkPosition),
+    /// The chunk that was requested is out of view of the player or could not be loaded, so it is
+    /// not coming
+    ChunkUnavailable(ChunkPosition),
+    /// None of the blocks the client sent were set, for the given reason
+    BlocksRefused {
+        reason: String,
+    },
+    EntityPositionChanged {
+        entity_id: u128,
+        position: EntityPosition,
+    },
+    BlockChanged {
+        position: BlockPosition<CHUNK_SIZE>,
+        block_id: BlockId,
+    },
+}
+
+impl ServerMessage {
+    /// The world event the message carries, if any
+    pub fn world_event(&self) -> Option<WorldEvent> {
+        match self {
+            Self::EntityPositionChanged {
+                entity_id,
+                position,
+            } => Some(WorldEvent::EntityPositionChanged(
+                Uuid::from_u128(*entity_id),
+                position.clone(),
+            )),
+            Self::BlockChanged { position, block_id } => {
+                Some(WorldEvent::BlockChanged(position.clone(), *block_id))
+            }
+            _ => None,
+        }
+    }
+}
+
+impl From<WorldEvent> for ServerMessage {
+    fn from(event: WorldEvent) -> Self {
+        match event {
+            WorldEvent::EntityPositionChanged(entity_id, position) => Self::EntityPositionChanged {
+                entity_id: entity_id.as_u128(),
+                position,
+            },
+            WorldEvent::BlockChanged(position, block_id) => {
+                Self::BlockChanged { position, block_id }
+            }
+        }
+    }
+}
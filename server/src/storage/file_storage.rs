@@ -1,14 +1,137 @@
+use crate::storage::region_file::RegionFile;
+use crate::storage::region_position::RegionPosition;
 use crate::storage::Storage;
 use block_chunk::ChunkStorage;
+use std::collections::HashMap;
 use std::error::Error;
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+use uuid::Uuid;
 use voxelcraft_core::chunk::ChunkPosition;
 
+/// Keeps the world in a directory on disk.
+///
+/// The chunks are grouped in to region files, with a directory of region files per dimension. All
+/// files are written in a way that a crash never leaves them half written
 #[derive(Debug)]
-pub struct FileStorage {}
+pub struct FileStorage {
+    directory: PathBuf,
+    /// The region files that have been opened so far, each behind its own lock so that different
+    /// regions can be read and written at the same time
+    regions: Mutex<HashMap<RegionPosition, Arc<std::sync::Mutex<RegionFile>>>>,
+}
 
 impl FileStorage {
-    pub fn new() -> Self {
-        Self {}
+    pub fn new<P: Into<PathBuf>>(directory: P) -> Self {
+        Self {
+            directory: directory.into(),
+            regions: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn directory(&self) -> &Path {
+        &self.directory
+    }
+
+    fn region_path(&self, position: &RegionPosition) -> PathBuf {
+        self.directory
+            .join("dimensions")
+            .join(position.dimension.to_string())
+            .join(position.file_name())
+    }
+
+    /// Runs the closure on the region file that holds the chunk, on a thread where blocking is
+    /// fine
+    async fn with_region<
+        R: 'static + Send,
+        F: 'static + Send + FnOnce(&mut RegionFile, usize) -> std::io::Result<R>,
+    >(
+        &self,
+        position: &ChunkPosition,
+        callback: F,
+    ) -> Result<R, Box<dyn Error + Send + Sync>> {
+        let region_position = RegionPosition::of(position);
+        let index = RegionPosition::chunk_index(position);
+        let region = self
+            .regions
+            .lock()
+            .await
+            .get(&region_position)
+            .map(Arc::clone);
+
+        let region = match region {
+            Some(region) => region,
+            None => {
+                let path = self.region_path(&region_position);
+                let opened = tokio::task::spawn_blocking(move || {
+                    if let Some(parent) = path.parent() {
+                        std::fs::create_dir_all(parent)?;
+                    }
+                    RegionFile::open(&path)
+                })
+                .await??;
+                // Someone else might have opened the same region in the meantime
+                Arc::clone(
+                    self.regions
+                        .lock()
+                        .await
+                        .entry(region_position)
+                        .or_insert_with(|| Arc::new(std::sync::Mutex::new(opened))),
+                )
+            }
+        };
+
+        let result = tokio::task::spawn_blocking(move || {
+            let mut region = region
+                .lock()
+                .map_err(|_| std::io::Error::other("Region file lock is poisoned"))?;
+            callback(&mut region, index)
+        })
+        .await??;
+        Ok(result)
+    }
+
+    fn metadata_path(&self) -> PathBuf {
+        self.directory.join("level")
+    }
+
+    fn player_path(&self, player_id: &Uuid) -> PathBuf {
+        self.directory
+            .join("players")
+            .join(format!("{}.player", player_id))
+    }
+
+    /// Writes the file through a temporary file that is then renamed, so the file is never left
+    /// half written
+    async fn write(path: &Path, bytes: Vec<u8>) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let path = path.to_path_buf();
+        tokio::task::spawn_blocking(move || {
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            let mut temporary_name = path.file_name().unwrap_or_default().to_os_string();
+            temporary_name.push(".tmp");
+            let temporary_path = path.with_file_name(temporary_name);
+            {
+                let mut file = std::fs::File::create(&temporary_path)?;
+                file.write_all(&bytes)?;
+                file.sync_all()?;
+            }
+            std::fs::rename(&temporary_path, &path)
+        })
+        .await??;
+        Ok(())
+    }
+
+    /// Reads the file, or returns `None` if it does not exist
+    async fn read(path: &Path) -> Result<Option<Vec<u8>>, Box<dyn Error + Send + Sync>> {
+        match tokio::fs::read(path).await {
+            Ok(bytes) => Ok(Some(bytes)),
+            Err(error) if error.kind() == ErrorKind::NotFound => Ok(None),
+            Err(error) => Err(error.into()),
+        }
     }
 }
 
@@ -19,31 +142,133 @@ impl ChunkStorage<ChunkPosition> for FileStorage {
         position: &ChunkPosition,
         bytes: Vec<u8>,
     ) -> Result<(), Box<dyn Error + Send + Sync>> {
-        todo!()
+        self.with_region(position, move |region, index| region.write(index, &bytes))
+            .await
     }
 
     async fn load(
         &self,
         position: &ChunkPosition,
     ) -> Result<Option<Vec<u8>>, Box<dyn Error + Send + Sync>> {
-        Ok(None)
+        // Looking for a chunk should not create an empty region file for it
+        let region_position = RegionPosition::of(position);
+        let is_open = self.regions.lock().await.contains_key(&region_position);
+        if !is_open {
+            match tokio::fs::metadata(self.region_path(&region_position)).await {
+                Err(error) if error.kind() == ErrorKind::NotFound => return Ok(None),
+                Err(error) => return Err(error.into()),
+                Ok(_) => {}
+            }
+        }
+
+        self.with_region(position, |region, index| region.read(index))
+            .await
     }
 }
 
 #[async_trait::async_trait]
 impl Storage for FileStorage {
-    async fn save_chunk_blocks(
+    async fn store_metadata(&self, bytes: Vec<u8>) -> Result<(), Box<dyn Error + Send + Sync>> {
+        Self::write(&self.metadata_path(), bytes).await
+    }
+
+    async fn load_metadata(&self) -> Result<Option<Vec<u8>>, Box<dyn Error + Send + Sync>> {
+        Self::read(&self.metadata_path()).await
+    }
+
+    async fn store_player(
         &self,
-        _encoded_blocks: &[u8],
-        _chunk_position: ChunkPosition,
-    ) -> Result<(), Box<dyn Error>> {
-        todo!()
+        player_id: &Uuid,
+        bytes: Vec<u8>,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        Self::write(&self.player_path(player_id), bytes).await
     }
 
-    async fn load_chunk_blocks(
+    async fn load_player(
         &self,
-        _chunk_position: ChunkPosition,
-    ) -> Result<Vec<u8>, Box<dyn Error>> {
-        todo!()
+        player_id: &Uuid,
+    ) -> Result<Option<Vec<u8>>, Box<dyn Error + Send + Sync>> {
+        Self::read(&self.player_path(player_id)).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::storage::{FileStorage, Storage};
+    use crate::test_util::TempDirectory;
+    use block_chunk::ChunkStorage;
+    use uuid::Uuid;
+    use voxelcraft_core::chunk::ChunkPosition;
+    use voxelcraft_id::DimensionId;
+
+    #[test]
+    fn it_should_read_back_chunks_in_different_regions_and_dimensions() {
+        let directory = TempDirectory::new();
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        let positions = [(0, 0, 0, 0), (15, -1, 3, 0), (16, 0, 0, 0), (-40, 7, 99, 1)].map(
+            |(x, y, z, dimension)| ChunkPosition {
+                x,
+                y,
+                z,
+                dimension: DimensionId::from_u128(dimension),
+            },
+        );
+
+        runtime.block_on(async {
+            let storage = FileStorage::new(directory.path());
+            for (index, position) in positions.iter().enumerate() {
+                storage
+                    .store(position, vec![index as u8; 5000])
+                    .await
+                    .unwrap();
+            }
+
+            let storage = FileStorage::new(directory.path());
+            for (index, position) in positions.iter().enumerate() {
+                assert_eq!(
+                    storage.load(position).await.unwrap(),
+                    Some(vec![index as u8; 5000])
+                );
+            }
+        });
+
+        assert!(directory.path().join("dimensions").join("1").is_dir());
+    }
+
+    #[test]
+    fn it_should_read_back_what_was_stored() {
+        let directory = TempDirectory::new();
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+
+        runtime.block_on(async {
+            let storage = FileStorage::new(directory.path());
+            let position = ChunkPosition {
+                x: -1,
+                y: 2,
+                z: 3,
+                dimension: Default::default(),
+            };
+            let player_id = Uuid::new_v4();
+
+            assert_eq!(storage.load(&position).await.unwrap(), None);
+            assert_eq!(storage.load_metadata().await.unwrap(), None);
+            assert_eq!(storage.load_player(&player_id).await.unwrap(), None);
+
+            storage.store(&position, vec![1, 2, 3]).await.unwrap();
+            storage.store_metadata(vec![4]).await.unwrap();
+            storage.store_player(&player_id, vec![5, 6]).await.unwrap();
+
+            let storage = FileStorage::new(directory.path());
+            assert_eq!(storage.load(&position).await.unwrap(), Some(vec![1, 2, 3]));
+            assert_eq!(storage.load_metadata().await.unwrap(), Some(vec![4]));
+            assert_eq!(
+                storage.load_player(&player_id).await.unwrap(),
+                Some(vec![5, 6])
+            );
+        });
     }
 }
@@ -0,0 +1,69 @@
+use crate::storage::Storage;
+use block_chunk::ChunkStorage;
+use std::collections::HashMap;
+use std::error::Error;
+use tokio::sync::Mutex;
+use uuid::Uuid;
+use voxelcraft_core::chunk::ChunkPosition;
+
+/// Keeps everything in memory, and forgets it when dropped. Useful for tests and for worlds that
+/// should not be saved
+#[derive(Debug, Default)]
+pub struct MemoryStorage {
+    chunks: Mutex<HashMap<ChunkPosition, Vec<u8>>>,
+    metadata: Mutex<Option<Vec<u8>>>,
+    players: Mutex<HashMap<Uuid, Vec<u8>>>,
+}
+
+impl MemoryStorage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait::async_trait]
+impl ChunkStorage<ChunkPosition> for MemoryStorage {
+    async fn store(
+        &self,
+        position: &ChunkPosition,
+        bytes: Vec<u8>,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        self.chunks.lock().await.insert(*position, bytes);
+        Ok(())
+    }
+
+    async fn load(
+        &self,
+        position: &ChunkPosition,
+    ) -> Result<Option<Vec<u8>>, Box<dyn Error + Send + Sync>> {
+        Ok(self.chunks.lock().await.get(position).cloned())
+    }
+}
+
+#[async_trait::async_trait]
+impl Storage for MemoryStorage {
+    async fn store_metadata(&self, bytes: Vec<u8>) -> Result<(), Box<dyn Error + Send + Sync>> {
+        *self.metadata.lock().await = Some(bytes);
+        Ok(())
+    }
+
+    async fn load_metadata(&self) -> Result<Option<Vec<u8>>, Box<dyn Error + Send + Sync>> {
+        Ok(self.metadata.lock().await.clone())
+    }
+
+    async fn store_player(
+        &self,
+        player_id: &Uuid,
+        bytes: Vec<u8>,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        self.players.lock().await.insert(*player_id, bytes);
+        Ok(())
+    }
+
+    async fn load_player(
+        &self,
+        player_id: &Uuid,
+    ) -> Result<Option<Vec<u8>>, Box<dyn Error + Send + Sync>> {
+        Ok(self.players.lock().await.get(player_id).cloned())
+    }
+}
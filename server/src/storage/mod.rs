@@ -1,5 +1,9 @@
 mod file_storage;
+mod memory_storage;
+mod region_file;
+mod region_position;
 mod storage;
 
 pub use self::file_storage::FileStorage;
+pub use self::memory_storage::MemoryStorage;
 pub use self::storage::Storage;
@@ -0,0 +1,359 @@
+use crate::storage::region_position::RegionPosition;
+use std::fs::{File, OpenOptions};
+use std::io::{self, ErrorKind, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+const MAGIC: &[u8; 4] = b"VXRG";
+const VERSION: u32 = 1;
+const SECTOR_SIZE: usize = 4096;
+/// Every chunk has an entry of the first sector and the length in bytes
+const ENTRY_SIZE: usize = 8;
+const HEADER_SIZE: usize = 8 + RegionPosition::CHUNK_COUNT * ENTRY_SIZE;
+const HEADER_SECTORS: usize = HEADER_SIZE.div_ceil(SECTOR_SIZE);
+const JOURNAL_SIZE: usize = 16;
+
+/// Where a chunk is stored within the region file. A first sector of 0 means that the chunk is
+/// not stored, since that sector belongs to the header
+#[derive(Debug, Default, Copy, Clone, PartialEq)]
+struct Entry {
+    sector: u32,
+    length: u32,
+}
+
+impl Entry {
+    const fn sector_count(self) -> usize {
+        (self.length as usize).div_ceil(SECTOR_SIZE)
+    }
+
+    const fn is_stored(self) -> bool {
+        self.sector != 0
+    }
+}
+
+/// A file holding all the chunks of a region.
+///
+/// The file starts with a header holding an entry per chunk, followed by the chunks themselves,
+/// each taking up a whole number of sectors. Sectors that are no longer used are reused by later
+/// writes.
+///
+/// A chunk is never written over in place. Its new bytes are written to free sectors first, and
+/// only then is its header entry updated. The header update is journaled, so a crash at any point
+/// leaves either the old or the new version of the chunk
+#[derive(Debug)]
+pub struct RegionFile {
+    file: File,
+    journal_path: PathBuf,
+    entries: Vec<Entry>,
+    used_sectors: Vec<bool>,
+}
+
+impl RegionFile {
+    /// Opens the region file, creating it if it does not exist. A journal left behind by a crash
+    /// is replayed
+    pub fn open(path: &Path) -> io::Result<Self> {
+        if !path.exists() {
+            Self::create(path)?;
+        }
+        let mut file = OpenOptions::new().read(true).write(true).open(path)?;
+        let entries = Self::read_header(&mut file)?;
+
+        let mut region_file = Self {
+            file,
+            journal_path: path.with_extension("journal"),
+            entries,
+            used_sectors: vec![],
+        };
+        region_file.replay_journal()?;
+        region_file.used_sectors = region_file.find_used_sectors();
+        Ok(region_file)
+    }
+
+    /// Writes an empty region file. The header is written to a temporary file that is then
+    /// renamed, so a crash never leaves a region file without a complete header
+    fn create(path: &Path) -> io::Result<()> {
+        let temporary_path = path.with_extension("region.tmp");
+        {
+            let mut file = File::create(&temporary_path)?;
+            file.write_all(&Self::encode_header(&vec![
+                Entry::default();
+                RegionPosition::CHUNK_COUNT
+            ]))?;
+            file.sync_all()?;
+        }
+        std::fs::rename(&temporary_path, path)
+    }
+
+    pub fn read(&mut self, index: usize) -> io::Result<Option<Vec<u8>>> {
+        let entry = self.entries[index];
+        if !entry.is_stored() {
+            return Ok(None);
+        }
+
+        let mut bytes = vec![0; entry.length as usize];
+        self.file
+            .seek(SeekFrom::Start(entry.sector as u64 * SECTOR_SIZE as u64))?;
+        self.file.read_exact(&mut bytes)?;
+        Ok(Some(bytes))
+    }
+
+    pub fn write(&mut self, index: usize, bytes: &[u8]) -> io::Result<()> {
+        let length = u32::try_from(bytes.len())
+            .map_err(|_| io::Error::new(ErrorKind::InvalidInput, "Chunk is too large"))?;
+        let sector_count = bytes.len().div_ceil(SECTOR_SIZE);
+
+        // The old sectors are still in use until the header points to the new ones
+        let sector = self.allocate(sector_count.max(1));
+        self.file
+            .seek(SeekFrom::Start(sector as u64 * SECTOR_SIZE as u64))?;
+        self.file.write_all(bytes)?;
+        self.file.sync_data()?;
+
+        let entry = Entry {
+            sector: sector as u32,
+            length,
+        };
+        self.write_journal(index, entry)?;
+        let previous = self.commit_entry(index, entry)?;
+        self.mark_sectors(previous, false);
+        self.mark_sectors(entry, true);
+
+        // The header is up to date already, a journal that is left behind replays the same entry
+        if let Err(error) = std::fs::remove_file(&self.journal_path) {
+            log::warn!(
+                "Failed to remove journal {}: {}",
+                self.journal_path.display(),
+                error
+            );
+        }
+        Ok(())
+    }
+
+    /// Finds the first run of free sectors that is long enough, growing the file if there is none
+    fn allocate(&mut self, sector_count: usize) -> usize {
+        let mut run_start = HEADER_SECTORS;
+        for sector in HEADER_SECTORS..self.used_sectors.len() {
+            if self.used_sectors[sector] {
+                run_start = sector + 1;
+            } else if sector + 1 - run_start == sector_count {
+                return run_start;
+            }
+        }
+        let start = run_start.max(self.used_sectors.len());
+        let end = start + sector_count;
+        if self.used_sectors.len() < end {
+            self.used_sectors.resize(end, false);
+        }
+        start
+    }
+
+    fn mark_sectors(&mut self, entry: Entry, is_used: bool) {
+        if !entry.is_stored() {
+            return;
+        }
+        let start = entry.sector as usize;
+        let end = start + entry.sector_count().max(1);
+        if self.used_sectors.len() < end {
+            self.used_sectors.resize(end, false);
+        }
+        for sector in start..end {
+            self.used_sectors[sector] = is_used;
+        }
+    }
+
+    fn find_used_sectors(&mut self) -> Vec<bool> {
+        self.used_sectors = vec![true; HEADER_SECTORS];
+        for entry in self.entries.clone() {
+            self.mark_sectors(entry, true);
+        }
+        std::mem::take(&mut self.used_sectors)
+    }
+
+    /// Writes the entry to the header, returning the entry it replaced
+    fn commit_entry(&mut self, index: usize, entry: Entry) -> io::Result<Entry> {
+        self.file
+            .seek(SeekFrom::Start((8 + index * ENTRY_SIZE) as u64))?;
+        self.file.write_all(&Self::encode_entry(entry))?;
+        self.file.sync_data()?;
+        Ok(std::mem::replace(&mut self.entries[index], entry))
+    }
+
+    /// Records the header update that is about to happen. The journal is written to a temporary
+    /// file first and then renamed, so it is either complete or missing
+    fn write_journal(&self, index: usize, entry: Entry) -> io::Result<()> {
+        let mut bytes = Vec::with_capacity(JOURNAL_SIZE);
+        bytes.extend_from_slice(&(index as u32).to_le_bytes());
+        bytes.extend_from_slice(&Self::encode_entry(entry));
+        bytes.extend_from_slice(&checksum(&bytes).to_le_bytes());
+
+        let temporary_path = self.journal_path.with_extension("journal.tmp");
+        {
+            let mut file = File::create(&temporary_path)?;
+            file.write_all(&bytes)?;
+            file.sync_all()?;
+        }
+        std::fs::rename(&temporary_path, &self.journal_path)
+    }
+
+    fn replay_journal(&mut self) -> io::Result<()> {
+        let bytes = match std::fs::read(&self.journal_path) {
+            Ok(bytes) => bytes,
+            Err(error) if error.kind() == ErrorKind::NotFound => return Ok(()),
+            Err(error) => return Err(error),
+        };
+
+        if bytes.len() == JOURNAL_SIZE && read_u32(&bytes, 12) == checksum(&bytes[..12]) {
+            let index = read_u32(&bytes, 0) as usize;
+            let entry = Self::decode_entry(&bytes[4..12]);
+            if index < RegionPosition::CHUNK_COUNT {
+                log::info!("Replaying region journal {:?}", self.journal_path);
+                self.commit_entry(index, entry)?;
+            }
+        }
+        std::fs::remove_file(&self.journal_path)
+    }
+
+    fn read_header(file: &mut File) -> io::Result<Vec<Entry>> {
+        let mut header = vec![0; HEADER_SIZE];
+        file.seek(SeekFrom::Start(0))?;
+        file.read_exact(&mut header)?;
+
+        if &header[0..4] != MAGIC {
+            return Err(io::Error::new(ErrorKind::InvalidData, "Not a region file"));
+        }
+        let version = read_u32(&header, 4);
+        if version != VERSION {
+            return Err(io::Error::new(
+                ErrorKind::InvalidData,
+                format!("Unsupported region file version {}", version),
+            ));
+        }
+
+        Ok(header[8..]
+            .chunks_exact(ENTRY_SIZE)
+            .map(Self::decode_entry)
+            .collect())
+    }
+
+    fn encode_header(entries: &[Entry]) -> Vec<u8> {
+        let mut header = Vec::with_capacity(HEADER_SECTORS * SECTOR_SIZE);
+        header.extend_from_slice(MAGIC);
+        header.extend_from_slice(&VERSION.to_le_bytes());
+        for entry in entries {
+            header.extend_from_slice(&Self::encode_entry(*entry));
+        }
+        header.resize(HEADER_SECTORS * SECTOR_SIZE, 0);
+        header
+    }
+
+    fn encode_entry(entry: Entry) -> [u8; ENTRY_SIZE] {
+        let mut bytes = [0; ENTRY_SIZE];
+        bytes[0..4].copy_from_slice(&entry.sector.to_le_bytes());
+        bytes[4..8].copy_from_slice(&entry.length.to_le_bytes());
+        bytes
+    }
+
+    fn decode_entry(bytes: &[u8]) -> Entry {
+        Entry {
+            sector: read_u32(bytes, 0),
+            length: read_u32(bytes, 4),
+        }
+    }
+}
+
+fn read_u32(bytes: &[u8], start: usize) -> u32 {
+    let mut value = [0; 4];
+    value.copy_from_slice(&bytes[start..start + 4]);
+    u32::from_le_bytes(value)
+}
+
+/// FNV-1a, which is plenty for telling a complete journal from garbage
+fn checksum(bytes: &[u8]) -> u32 {
+    bytes.iter().fold(0x811c_9dc5, |hash, byte| {
+        (hash ^ u32::from(*byte)).wrapping_mul(0x0100_0193)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::storage::region_file::{Entry, RegionFile, HEADER_SECTORS, SECTOR_SIZE};
+    use crate::test_util::TempDirectory;
+    use std::path::PathBuf;
+
+    /// A path for a region file in a new directory, which is removed together with the directory
+    fn temporary_path() -> (TempDirectory, PathBuf) {
+        let directory = TempDirectory::new();
+        std::fs::create_dir_all(directory.path()).unwrap();
+        let path = directory.path().join("0.0.0.region");
+        (directory, path)
+    }
+
+    #[test]
+    fn it_should_read_back_chunks_after_reopening() {
+        let (_directory, path) = temporary_path();
+        {
+            let mut region_file = RegionFile::open(&path).unwrap();
+            region_file.write(0, &[1; 10]).unwrap();
+            region_file.write(4095, &[2; SECTOR_SIZE * 2 + 1]).unwrap();
+        }
+
+        let mut region_file = RegionFile::open(&path).unwrap();
+
+        assert_eq!(region_file.read(0).unwrap(), Some(vec![1; 10]));
+        assert_eq!(
+            region_file.read(4095).unwrap(),
+            Some(vec![2; SECTOR_SIZE * 2 + 1])
+        );
+        assert_eq!(region_file.read(1).unwrap(), None);
+    }
+
+    #[test]
+    fn it_should_reuse_freed_sectors() {
+        let (_directory, path) = temporary_path();
+        let mut region_file = RegionFile::open(&path).unwrap();
+
+        region_file.write(0, &[1; SECTOR_SIZE]).unwrap();
+        region_file.write(1, &[2; SECTOR_SIZE]).unwrap();
+        for value in 3..10 {
+            region_file.write(0, &[value; SECTOR_SIZE]).unwrap();
+        }
+
+        // Chunk 0 alternates between the sector before and after chunk 1
+        let size = std::fs::metadata(&path).unwrap().len() as usize;
+        assert!(size <= (HEADER_SECTORS + 3) * SECTOR_SIZE);
+        assert_eq!(region_file.read(0).unwrap(), Some(vec![9; SECTOR_SIZE]));
+        assert_eq!(region_file.read(1).unwrap(), Some(vec![2; SECTOR_SIZE]));
+    }
+
+    #[test]
+    fn it_should_replay_a_journal_left_behind_by_a_crash() {
+        let (_directory, path) = temporary_path();
+        let entry = {
+            let mut region_file = RegionFile::open(&path).unwrap();
+            region_file.write(0, &[1; 10]).unwrap();
+            let entry = region_file.entries[0];
+
+            // Crash after writing the journal, but before the header is updated
+            region_file.write_journal(7, entry).unwrap();
+            entry
+        };
+
+        let mut region_file = RegionFile::open(&path).unwrap();
+
+        assert_eq!(region_file.entries[7], entry);
+        assert_eq!(region_file.read(7).unwrap(), Some(vec![1; 10]));
+        assert!(!path.with_extension("journal").exists());
+    }
+
+    #[test]
+    fn it_should_not_free_sectors_of_unstored_chunks() {
+        let (_directory, path) = temporary_path();
+        let mut region_file = RegionFile::open(&path).unwrap();
+
+        region_file.write(0, &[]).unwrap();
+        region_file.write(1, &[1]).unwrap();
+
+        assert_ne!(region_file.entries[0], Entry::default());
+        assert_eq!(region_file.read(0).unwrap(), Some(vec![]));
+        assert_eq!(region_file.read(1).unwrap(), Some(vec![1]));
+    }
+}
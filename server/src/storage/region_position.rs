@@ -0,0 +1,65 @@
+use voxelcraft_core::chunk::ChunkPosition;
+use voxelcraft_id::DimensionId;
+
+/// The position of a region, which is a cube of [`RegionPosition::SIZE`] chunks along each axis
+/// that are stored together in the same file
+#[derive(Debug, Copy, Clone, Hash, Eq, PartialEq)]
+pub struct RegionPosition {
+    pub x: i32,
+    pub y: i32,
+    pub z: i32,
+    pub dimension: DimensionId,
+}
+
+impl RegionPosition {
+    pub const SIZE: usize = 16;
+    pub const CHUNK_COUNT: usize = Self::SIZE * Self::SIZE * Self::SIZE;
+
+    /// The region containing the chunk
+    pub const fn of(position: &ChunkPosition) -> Self {
+        let size = Self::SIZE as i32;
+        Self {
+            x: position.x.div_euclid(size),
+            y: position.y.div_euclid(size),
+            z: position.z.div_euclid(size),
+            dimension: position.dimension,
+        }
+    }
+
+    /// Where within the region the chunk is stored
+    pub const fn chunk_index(position: &ChunkPosition) -> usize {
+        let size = Self::SIZE as i32;
+        let x = position.x.rem_euclid(size) as usize;
+        let y = position.y.rem_euclid(size) as usize;
+        let z = position.z.rem_euclid(size) as usize;
+        (x * Self::SIZE + y) * Self::SIZE + z
+    }
+
+    pub fn file_name(&self) -> String {
+        format!("r.{}.{}.{}.region", self.x, self.y, self.z)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::storage::region_position::RegionPosition;
+    use voxelcraft_core::chunk::ChunkPosition;
+
+    #[test]
+    fn chunks_with_negative_coordinates_should_belong_to_negative_regions() {
+        let position = ChunkPosition {
+            x: -1,
+            y: 16,
+            z: -17,
+            dimension: Default::default(),
+        };
+
+        let region = RegionPosition::of(&position);
+
+        assert_eq!((region.x, region.y, region.z), (-1, 1, -2));
+        assert_eq!(
+            RegionPosition::chunk_index(&position),
+            (15 * RegionPosition::SIZE) * RegionPosition::SIZE + 15
+        );
+    }
+}
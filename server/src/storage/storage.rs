@@ -1,17 +1,22 @@
 use block_chunk::ChunkStorage;
 use std::error::Error;
 use std::fmt::Debug;
+use uuid::Uuid;
 use voxelcraft_core::chunk::ChunkPosition;
 
+/// Where a world is kept. Besides the chunks, it holds the metadata of the world and the data of
+/// every player that has been in it
 #[async_trait::async_trait]
 pub trait Storage: ChunkStorage<ChunkPosition> + Send + Sync + Debug {
-    async fn save_chunk_blocks(
+    async fn store_metadata(&self, bytes: Vec<u8>) -> Result<(), Box<dyn Error + Send + Sync>>;
+    async fn load_metadata(&self) -> Result<Option<Vec<u8>>, Box<dyn Error + Send + Sync>>;
+    async fn store_player(
         &self,
-        encoded_blocks: &[u8],
-        chunk_position: ChunkPosition,
-    ) -> Result<(), Box<dyn Error>>;
-    async fn load_chunk_blocks(
+        player_id: &Uuid,
+        bytes: Vec<u8>,
+    ) -> Result<(), Box<dyn Error + Send + Sync>>;
+    async fn load_player(
         &self,
-        chunk_position: ChunkPosition,
-    ) -> Result<Vec<u8>, Box<dyn Error>>;
+        player_id: &Uuid,
+    ) -> Result<Option<Vec<u8>>, Box<dyn Error + Send + Sync>>;
 }
@@ -0,0 +1,52 @@
+// Fixtures shared by the tests of the server. The integration tests include this file as well, so
+// it can only refer to other crates
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use uuid::Uuid;
+use voxelcraft_id::DimensionId;
+use voxelcraft_mod::{Mod, ModPack};
+
+/// A mod pack without any mods, which is all most tests need
+#[derive(Debug)]
+pub struct EmptyModPack;
+
+impl ModPack for EmptyModPack {
+    fn name(&self) -> &str {
+        "Empty"
+    }
+
+    fn mods(&self) -> &[Arc<dyn Mod>] {
+        &[]
+    }
+
+    fn default_dimension(&self) -> &'static DimensionId {
+        static DIMENSION: DimensionId = DimensionId::from_u128(0);
+        &DIMENSION
+    }
+}
+
+/// A unique directory in the temporary directory, which is removed again when this is dropped.
+/// The directory itself is not created, so tests can check what happens when it is missing
+#[derive(Debug)]
+pub struct TempDirectory {
+    path: PathBuf,
+}
+
+impl TempDirectory {
+    pub fn new() -> Self {
+        Self {
+            path: std::env::temp_dir().join(format!("voxelcraft-{}", Uuid::new_v4())),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for TempDirectory {
+    fn drop(&mut self) {
+        // The test might not have created anything
+        let _ = std::fs::remove_dir_all(&self.path);
+    }
+}
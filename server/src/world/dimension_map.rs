@@ -13,10 +13,10 @@ pub struct DimensionMap {
 }
 
 impl DimensionMap {
-    pub async fn new(mod_pack: &Arc<dyn ModPack>) -> Self {
+    pub async fn new(mod_pack: &Arc<dyn ModPack>, seed: u128) -> Self {
         let mut dimensions = HashMap::new();
         for module in mod_pack.mods() {
-            for dim in module.register_dimensions(0).await {
+            for dim in module.register_dimensions(seed).await {
                 log::info!(
                     "Registering dimension: '{}', with id: '{}'",
                     dim.name(),
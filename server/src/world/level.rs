@@ -0,0 +1,114 @@
+use std::error::Error;
+use std::time::{SystemTime, UNIX_EPOCH};
+use voxelcraft_id::{DimensionId, ModId};
+use voxelcraft_mod::ModPack;
+
+/// The metadata of a world, which is stored next to its chunks
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Level {
+    pub format_version: u32,
+    pub name: String,
+    pub seed: u64,
+    /// Seconds since the unix epoch
+    pub created_at: u64,
+    pub mod_pack: String,
+    /// The mods the world was created with, which all have to be present to load it again
+    pub mods: Vec<ModId>,
+    pub default_dimension: DimensionId,
+    /// The absolute position new players start at, within the default dimension
+    pub spawn: [f64; 3],
+    /// The number of ticks the world has been running for
+    pub game_time: u64,
+}
+
+impl Level {
+    pub const FORMAT_VERSION: u32 = 1;
+
+    pub fn new(name: &str, seed: u64, mod_pack: &dyn ModPack) -> Self {
+        let created_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |duration| duration.as_secs());
+
+        Self {
+            format_version: Self::FORMAT_VERSION,
+            name: name.to_string(),
+            seed,
+            created_at,
+            mod_pack: mod_pack.name().to_string(),
+            mods: mod_pack
+                .mods()
+                .iter()
+                .map(|module| module.id().clone())
+                .collect(),
+            default_dimension: *mod_pack.default_dimension(),
+            spawn: [0.0, 1.7, 0.0],
+            game_time: 0,
+        }
+    }
+
+    pub fn encode(&self) -> Result<Vec<u8>, Box<dyn Error + Send + Sync>> {
+        Ok(serde_json::to_vec_pretty(self)?)
+    }
+
+    /// # Errors
+    /// If the bytes are not a level, or if the level was written by a newer version
+    pub fn decode(bytes: &[u8]) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        let level: Self = serde_json::from_slice(bytes)?;
+        if level.format_version > Self::FORMAT_VERSION {
+            return Err(format!(
+                "The level has format version {}, but only versions up to {} are supported",
+                level.format_version,
+                Self::FORMAT_VERSION
+            )
+            .into());
+        }
+        Ok(level)
+    }
+
+    /// The mods the world needs that are not part of the mod pack
+    pub fn missing_mods(&self, mod_pack: &dyn ModPack) -> Vec<ModId> {
+        self.mods
+            .iter()
+            .filter(|id| !mod_pack.mods().iter().any(|module| module.id() == *id))
+            .cloned()
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::world::level::Level;
+
+    fn level() -> Level {
+        Level {
+            format_version: Level::FORMAT_VERSION,
+            name: "Test".to_string(),
+            seed: 42,
+            created_at: 1_600_000_000,
+            mod_pack: "Pack".to_string(),
+            mods: vec![],
+            default_dimension: Default::default(),
+            spawn: [0.5, 64.0, -3.5],
+            game_time: 1200,
+        }
+    }
+
+    #[test]
+    fn it_should_decode_what_was_encoded() {
+        let level = level();
+
+        let decoded = Level::decode(&level.encode().unwrap()).unwrap();
+
+        assert_eq!(decoded, level);
+    }
+
+    #[test]
+    fn it_should_refuse_newer_format_versions() {
+        let level = Level {
+            format_version: Level::FORMAT_VERSION + 1,
+            ..level()
+        };
+
+        assert!(Level::decode(&level.encode().unwrap()).is_err());
+    }
+}
@@ -1,4 +1,11 @@
 mod dimension_map;
+mod level;
+mod tick_statistics;
+mod tick_timer;
 mod world;
+mod world_handle;
 
+pub use self::level::Level;
+pub use self::tick_statistics::TickStatistics;
 pub use self::world::World;
+pub use self::world_handle::WorldHandle;
@@ -0,0 +1,8 @@
+/// How well the world keeps up with its tick rate
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct TickStatistics {
+    /// The number of ticks that ran in the last second
+    pub ticks_per_second: f64,
+    /// The average time a tick took in the last second, in milliseconds
+    pub milliseconds_per_tick: f64,
+}
@@ -0,0 +1,79 @@
+use crate::world::TickStatistics;
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// Keeps track of the ticks that ran in the last second
+#[derive(Debug, Default)]
+pub struct TickTimer {
+    /// When each tick started and how long it took, oldest first
+    ticks: VecDeque<(Instant, Duration)>,
+}
+
+const WINDOW: Duration = Duration::from_secs(1);
+
+impl TickTimer {
+    pub fn record(&mut self, started_at: Instant, duration: Duration) {
+        self.ticks.push_back((started_at, duration));
+        self.forget_before(started_at);
+    }
+
+    pub fn statistics(&self, now: Instant) -> TickStatistics {
+        let recent = self
+            .ticks
+            .iter()
+            .filter(|(started_at, _)| now.saturating_duration_since(*started_at) < WINDOW)
+            .map(|(_, duration)| *duration)
+            .collect::<Vec<_>>();
+        if recent.is_empty() {
+            return TickStatistics::default();
+        }
+
+        let total = recent.iter().sum::<Duration>();
+        TickStatistics {
+            ticks_per_second: recent.len() as f64 / WINDOW.as_secs_f64(),
+            milliseconds_per_tick: total.as_secs_f64() * 1000.0 / recent.len() as f64,
+        }
+    }
+
+    fn forget_before(&mut self, now: Instant) {
+        while let Some((started_at, _)) = self.ticks.front() {
+            if now.saturating_duration_since(*started_at) < WINDOW {
+                break;
+            }
+            self.ticks.pop_front();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::world::tick_timer::TickTimer;
+    use crate::world::TickStatistics;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn it_should_report_nothing_without_ticks() {
+        let timer = TickTimer::default();
+
+        assert_eq!(timer.statistics(Instant::now()), TickStatistics::default());
+    }
+
+    #[test]
+    fn it_should_average_the_ticks_of_the_last_second() {
+        let mut timer = TickTimer::default();
+        let start = Instant::now();
+        // An old, slow tick that should no longer count
+        timer.record(start, Duration::from_millis(500));
+        for tick in 0..20 {
+            timer.record(
+                start + Duration::from_millis(1000 + tick * 50),
+                Duration::from_millis(4),
+            );
+        }
+
+        let statistics = timer.statistics(start + Duration::from_millis(1990));
+
+        assert_eq!(statistics.ticks_per_second, 20.0);
+        assert!((statistics.milliseconds_per_tick - 4.0).abs() < 1e-9);
+    }
+}
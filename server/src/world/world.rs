@@ -1,34 +1,45 @@
-use crate::block::Block;
-use crate::entity::Player;
+use crate::entity::{Player, PlayerData};
 use crate::event::WorldEvent;
 use crate::storage::{FileStorage, Storage};
 use crate::world::dimension_map::DimensionMap;
+use crate::world::level::Level;
+use crate::world::tick_timer::TickTimer;
+use crate::world::{TickStatistics, WorldHandle};
 use crate::{Chunk, CHUNK_SIZE};
 use block_chunk::ChunkCache;
+use std::collections::hash_map::Entry;
 use std::collections::HashMap;
 use std::error::Error;
 use std::future::Future;
-use std::sync::Arc;
-use std::time::Duration;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, PoisonError};
+use std::time::{Duration, Instant};
 use tokio::sync::mpsc::channel;
 use tokio::sync::mpsc::Receiver;
 use tokio::sync::mpsc::Sender;
-use tokio::sync::{broadcast, MappedMutexGuard, Mutex, MutexGuard, OwnedRwLockReadGuard, RwLock};
-use tokio::time::interval;
+use tokio::sync::{broadcast, Mutex, OwnedMutexGuard, OwnedRwLockReadGuard, RwLock};
 use uuid::Uuid;
+use voxelcraft_core::block::BlockPosition;
 use voxelcraft_core::chunk::ChunkPosition;
-use voxelcraft_core::entity::{EntityOffset, EntityPosition};
+use voxelcraft_core::collision::{BoundingBox, SolidBlocks};
+use voxelcraft_core::entity::EntityPosition;
+use voxelcraft_core::raycast::{RayHit, Raycast};
 use voxelcraft_id::{BlockId, DimensionId};
-use voxelcraft_mod::{Dimension, Entity, ModPack};
+use voxelcraft_mod::{Block, BlockShape, CollisionWorld, Dimension, Entity, ModPack};
 
 #[derive(Debug)]
 pub struct World {
     storage: Arc<dyn Storage>,
     chunk_cache: Arc<ChunkCache<ChunkPosition, BlockId, CHUNK_SIZE>>,
-    name: String,
-    players: Mutex<HashMap<Uuid, Player>>,
+    level: Level,
+    game_time: AtomicU64,
+    tick_timer: std::sync::Mutex<TickTimer>,
+    /// Every player has a lock of their own, so that moving one player, which can wait on chunks
+    /// being loaded, does not hold up looking up the others
+    players: Mutex<HashMap<Uuid, Arc<Mutex<Player>>>>,
     dimensions: Arc<DimensionMap>,
-    block_list: Arc<HashMap<u32, Block>>,
+    blocks: Arc<HashMap<BlockId, Arc<dyn Block>>>,
     incoming_events_receiver: Mutex<Receiver<WorldEvent>>,
     incoming_events_sender: Sender<WorldEvent>,
     outgoing_events_sender: broadcast::Sender<WorldEvent>,
@@ -36,15 +47,70 @@ pub struct World {
 }
 
 const CHANNEL_SIZE: usize = 10_000;
+const MAX_IN_MEMORY_CHUNK_BYTES: usize = 512 * 1024 * 1024;
+const MAX_COMPRESSED_CHUNK_BYTES: usize = 128 * 1024 * 1024;
 
 impl World {
-    pub async fn new<S: Storage + 'static>(storage: S, mod_pack: Arc<dyn ModPack>) -> Self {
-        let dimensions = Arc::new(DimensionMap::new(&mod_pack).await);
-        let block_list = Arc::new(HashMap::new());
+    /// Creates a new world in the storage, writing its level metadata
+    pub async fn create<S: Storage + 'static>(
+        storage: S,
+        mod_pack: Arc<dyn ModPack>,
+        name: &str,
+        seed: u64,
+    ) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        let level = Level::new(name, seed, mod_pack.as_ref());
+        storage.store_metadata(level.encode()?).await?;
+        Ok(Self::new(storage, mod_pack, level).await)
+    }
+
+    /// Loads an existing world from the storage
+    ///
+    /// # Errors
+    /// If the storage holds no world, or if the world needs mods that are missing from the mod pack
+    pub async fn load<S: Storage + 'static>(
+        storage: S,
+        mod_pack: Arc<dyn ModPack>,
+    ) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        let bytes = storage
+            .load_metadata()
+            .await?
+            .ok_or("There is no world in the storage")?;
+        let level = Level::decode(&bytes)?;
+
+        let missing_mods = level.missing_mods(mod_pack.as_ref());
+        if !missing_mods.is_empty() {
+            return Err(format!(
+                "The world '{}' needs mods that are missing from the mod pack '{}': {:?}",
+                level.name,
+                mod_pack.name(),
+                missing_mods
+            )
+            .into());
+        }
+
+        Ok(Self::new(storage, mod_pack, level).await)
+    }
+
+    /// Loads an existing world from the given directory, see [`World::load`]
+    pub async fn open<P: Into<PathBuf>>(
+        path: P,
+        mod_pack: Arc<dyn ModPack>,
+    ) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        Self::load(FileStorage::new(path), mod_pack).await
+    }
+
+    async fn new<S: Storage + 'static>(
+        storage: S,
+        mod_pack: Arc<dyn ModPack>,
+        level: Level,
+    ) -> Self {
+        let storage = Arc::new(storage);
+        let dimensions = Arc::new(DimensionMap::new(&mod_pack, u128::from(level.seed)).await);
+        let blocks = Arc::new(Self::register_blocks(&mod_pack).await);
         let chunk_cache = Arc::new(ChunkCache::new(
-            1000,
-            1000,
-            Arc::new(FileStorage::new()),
+            MAX_IN_MEMORY_CHUNK_BYTES,
+            MAX_COMPRESSED_CHUNK_BYTES,
+            Arc::clone(&storage),
             Arc::clone(&dimensions),
         ));
 
@@ -54,12 +120,14 @@ impl World {
         let incoming_events_receiver = Mutex::new(incoming_events_receiver);
 
         Self {
-            storage: Arc::new(storage),
+            storage,
             chunk_cache,
-            name: "".to_string(),
+            game_time: AtomicU64::new(level.game_time),
+            tick_timer: std::sync::Mutex::new(TickTimer::default()),
+            level,
             players: Mutex::new(HashMap::new()),
             dimensions,
-            block_list,
+            blocks,
             incoming_events_sender,
             incoming_events_receiver,
             outgoing_events_sender,
@@ -67,7 +135,45 @@ impl World {
         }
     }
 
+    async fn register_blocks(mod_pack: &Arc<dyn ModPack>) -> HashMap<BlockId, Arc<dyn Block>> {
+        let mut blocks = HashMap::new();
+        for module in mod_pack.mods() {
+            for block in module.register_blocks().await {
+                blocks.insert(*block.block_id(), block);
+            }
+        }
+        log::info!("Registered a total of {} blocks", blocks.len());
+        blocks
+    }
+
+    /// The number of times the world is updated per second
+    pub const TICKS_PER_SECOND: u32 = 60;
+
+    /// The simulated time that passes in a single tick
+    pub fn tick_duration() -> Duration {
+        Duration::from_secs(1) / Self::TICKS_PER_SECOND
+    }
+
+    /// Runs a single tick, in which `delta` seconds pass in the world
     pub async fn update(&self, delta: f64) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let started_at = Instant::now();
+        let result = self.tick(delta).await;
+        self.tick_timer
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .record(started_at, started_at.elapsed());
+        result
+    }
+
+    /// How many ticks ran in the last second, and how long they took
+    pub fn tick_statistics(&self) -> TickStatistics {
+        self.tick_timer
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .statistics(Instant::now())
+    }
+
+    async fn tick(&self, delta: f64) -> Result<(), Box<dyn Error + Send + Sync>> {
         log::debug!("Pulling new events");
         {
             let mut lock = self.incoming_events_receiver.lock().await;
@@ -82,18 +188,35 @@ impl World {
         let chunks_to_update = self.get_chunks_that_should_update();
 
         log::debug!("Updating {} chunks", chunks_to_update.len());
+        // A chunk or player that fails to update is skipped, so that it does not hold back the
+        // rest of the world
         for position in chunks_to_update {
-            self.chunk_cache
+            let result = self
+                .chunk_cache
                 .borrow_chunk(&position, |chunk| async move {
                     // TODO
                 })
-                .await?;
+                .await;
+            if let Err(error) = result {
+                log::error!("Failed to update chunk {}: {}", position, error);
+            }
         }
 
-        for player in self.players.lock().await.values_mut() {
-            player.update_position(delta).await;
+        let players = self
+            .players
+            .lock()
+            .await
+            .iter()
+            .map(|(player_id, player)| (*player_id, Arc::clone(player)))
+            .collect::<Vec<_>>();
+        for (player_id, player) in players {
+            if let Err(error) = player.lock().await.update_position(delta, self).await {
+                log::error!("Failed to update player {}: {}", player_id, error);
+            }
         }
 
+        self.game_time.fetch_add(1, Ordering::Relaxed);
+
         Ok(())
     }
 
@@ -107,21 +230,9 @@ impl World {
         vec![chunk_position]
     }
 
-    pub fn start_update_loop(self: &Arc<Self>) {
-        let world = Arc::clone(self);
-        tokio::spawn(async move {
-            let mut interval = interval(Duration::from_millis(16));
-            let mut previous_instant = interval.tick().await;
-            loop {
-                let instant = interval.tick().await;
-
-                let delta = instant.duration_since(previous_instant).as_secs_f64();
-
-                world.update(delta).await.unwrap();
-
-                previous_instant = instant;
-            }
-        });
+    /// Starts ticking the world, saving it every `autosave_interval` if one is given
+    pub fn start_update_loop(self: &Arc<Self>, autosave_interval: Option<Duration>) -> WorldHandle {
+        WorldHandle::start(Arc::clone(self), autosave_interval)
     }
 
     pub fn get_event_receiver(&self) -> broadcast::Receiver<WorldEvent> {
@@ -142,55 +253,625 @@ impl World {
             .await
     }
 
+    pub fn name(&self) -> &str {
+        &self.level.name
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.level.seed
+    }
+
+    pub fn mod_pack(&self) -> &Arc<dyn ModPack> {
+        &self.mod_pack
+    }
+
+    /// The number of ticks the world has been running for, including earlier sessions
+    pub fn game_time(&self) -> u64 {
+        self.game_time.load(Ordering::Relaxed)
+    }
+
+    /// Writes the level metadata, the players and every chunk that has changed to storage
+    pub async fn save(&self) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let level = Level {
+            game_time: self.game_time(),
+            ..self.level.clone()
+        };
+        self.storage.store_metadata(level.encode()?).await?;
+        self.save_players().await?;
+        self.chunk_cache.flush_all().await
+    }
+
+    pub async fn get_block(
+        &self,
+        position: &BlockPosition<CHUNK_SIZE>,
+    ) -> Result<BlockId, Box<dyn Error + Send + Sync>> {
+        self.chunk_cache
+            .borrow_chunk(&position.chunk_position, |chunk| {
+                let block_id = *chunk.get(&position.offset);
+                async move { block_id }
+            })
+            .await
+    }
+
+    /// Replaces the block at the given position, returning the block that was there before. A
+    /// [`WorldEvent::BlockChanged`] is broadcast if the block changed
+    pub async fn set_block(
+        &self,
+        position: &BlockPosition<CHUNK_SIZE>,
+        block_id: BlockId,
+    ) -> Result<BlockId, Box<dyn Error + Send + Sync>> {
+        let previous = self
+            .chunk_cache
+            .borrow_chunk_mut(&position.chunk_position, |mut chunk| {
+                let previous = chunk.set(block_id, &position.offset);
+                async move { previous }
+            })
+            .await?;
+
+        if previous != block_id {
+            self.broadcast(WorldEvent::BlockChanged(position.clone(), block_id));
+        }
+        Ok(previous)
+    }
+
+    /// Replaces many blocks at once. The blocks are grouped by chunk, so that every chunk is only
+    /// borrowed once. A [`WorldEvent::BlockChanged`] is broadcast for every block that changed
+    pub async fn set_blocks(
+        &self,
+        blocks: Vec<(BlockPosition<CHUNK_SIZE>, BlockId)>,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let mut blocks_per_chunk = HashMap::<_, Vec<_>>::new();
+        for (position, block_id) in blocks {
+            blocks_per_chunk
+                .entry(position.chunk_position)
+                .or_default()
+                .push((position.offset, block_id));
+        }
+
+        for (chunk_position, blocks) in blocks_per_chunk {
+            let changed = self
+                .chunk_cache
+                .borrow_chunk_mut(&chunk_position, |mut chunk| {
+                    let changed = blocks
+                        .into_iter()
+                        .filter(|(offset, block_id)| chunk.set(*block_id, offset) != *block_id)
+                        .collect::<Vec<_>>();
+                    async move { changed }
+                })
+                .await?;
+
+            for (offset, block_id) in changed {
+                self.broadcast(WorldEvent::BlockChanged(
+                    BlockPosition {
+                        chunk_position,
+                        offset,
+                    },
+                    block_id,
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn broadcast(&self, event: WorldEvent) {
+        // Sending only fails when no one is listening, which is fine
+        let _ = self.outgoing_events_sender.send(event);
+    }
+
+    /// Finds the first block along the ray for which `is_solid` returns true. The chunks along the
+    /// ray are borrowed one at a time
+    pub async fn raycast<F: Send + Sync + Fn(&BlockId) -> bool>(
+        &self,
+        origin: &EntityPosition,
+        direction: [f32; 3],
+        max_distance: f32,
+        is_solid: F,
+    ) -> Result<Option<RayHit<CHUNK_SIZE>>, Box<dyn Error + Send + Sync>> {
+        let mut ray = Raycast::new(origin, direction, max_distance);
+        while let Some(chunk_position) = ray.chunk_position() {
+            let hit = self
+                .chunk_cache
+                .borrow_chunk(&chunk_position, |chunk| {
+                    let hit = ray.walk_chunk(|offset| is_solid(chunk.get(offset)));
+                    async move { hit }
+                })
+                .await?;
+            if hit.is_some() {
+                return Ok(hit);
+            }
+        }
+        Ok(None)
+    }
+
     pub async fn get_player_position(&self, player_id: Uuid) -> Option<EntityPosition> {
-        let players = self.players.lock().await;
-        players.get(&player_id).map(|p| p.position().clone())
+        let player = self.players.lock().await.get(&player_id).cloned()?;
+        let position = player.lock().await.position().clone();
+        Some(position)
     }
 
     pub async fn borrow_player<
-        'a,
-        C: FnOnce(MappedMutexGuard<'a, Player>) -> FR,
-        FR: Future<Output = R> + Send + 'a,
+        C: FnOnce(OwnedMutexGuard<Player>) -> FR,
+        FR: Future<Output = R> + Send,
         R: Send + Sync,
     >(
-        &'a self,
+        &self,
         player_id: Uuid,
         callback: C,
     ) -> Result<R, Box<dyn Error + Send + Sync>> {
-        let players = self.players.lock().await;
-
-        match MutexGuard::try_map(players, |p| match p.get_mut(&player_id) {
-            None => None,
-            Some(player) => Some(player),
-        }) {
-            Ok(player) => Ok(callback(player).await),
-            Err(_) => Err("Player not found".into()),
+        let player = self.players.lock().await.get(&player_id).cloned();
+        match player {
+            Some(player) => Ok(callback(player.lock_owned().await).await),
+            None => Err("Player not found".into()),
         }
     }
 
-    pub async fn load_player(&self, player_id: Uuid) {
+    /// Brings the player in to the world, where they were when they last left it. Players that
+    /// have never been in the world start at the spawn. Fails if the player is in the world already
+    pub async fn load_player(&self, player_id: Uuid) -> Result<(), Box<dyn Error + Send + Sync>> {
         log::info!("Welcoming player {} to the world", player_id);
         let world_event_sender = self.outgoing_events_sender.clone();
-        let mut players = self.players.lock().await;
-        players.insert(
-            player_id,
-            Player::new(
+        let player = match self.storage.load_player(&player_id).await? {
+            Some(bytes) => {
+                Player::from_data(player_id, &PlayerData::decode(&bytes)?, world_event_sender)
+            }
+            None => Player::new(
                 player_id,
-                EntityPosition {
-                    chunk_position: ChunkPosition {
-                        x: 0,
-                        y: 0,
-                        z: 0,
-                        dimension: self.mod_pack.default_dimension().clone(),
-                    },
-                    offset: EntityOffset {
-                        x: 0.0,
-                        y: 1.7,
-                        z: 0.0,
-                    },
-                },
+                EntityPosition::from_absolute::<CHUNK_SIZE>(
+                    self.level.spawn[0],
+                    self.level.spawn[1],
+                    self.level.spawn[2],
+                    self.level.default_dimension,
+                ),
                 world_event_sender,
             ),
+        };
+
+        match self.players.lock().await.entry(player_id) {
+            Entry::Occupied(_) => Err("The player is already in the world".into()),
+            Entry::Vacant(entry) => {
+                entry.insert(Arc::new(Mutex::new(player)));
+                Ok(())
+            }
+        }
+    }
+
+    /// Saves the player and takes them out of the world. The player stays in the world if they
+    /// could not be saved, so that they are not lost
+    pub async fn unload_player(&self, player_id: Uuid) -> Result<(), Box<dyn Error + Send + Sync>> {
+        log::info!("Saying goodbye to player {}", player_id);
+        let player = self.players.lock().await.get(&player_id).cloned();
+        let player = match player {
+            Some(player) => player,
+            None => return Err("Player not found".into()),
+        };
+
+        let data = player.lock().await.data();
+        self.storage
+            .store_player(&player_id, data.encode()?)
+            .await?;
+        self.players.lock().await.remove(&player_id);
+        Ok(())
+    }
+
+    async fn save_players(&self) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let players = self
+            .players
+            .lock()
+            .await
+            .iter()
+            .map(|(id, player)| (*id, Arc::clone(player)))
+            .collect::<Vec<_>>();
+        for (player_id, player) in players {
+            let data = player.lock().await.data();
+            self.storage
+                .store_player(&player_id, data.encode()?)
+                .await?;
+        }
+        Ok(())
+    }
+}
+
+#[async_trait::async_trait]
+impl CollisionWorld for World {
+    async fn solid_blocks(
+        &self,
+        dimension: DimensionId,
+        region: &BoundingBox,
+    ) -> Result<SolidBlocks, Box<dyn Error + Send + Sync>> {
+        let mut solid_blocks = SolidBlocks::new(region);
+        for chunk_position in solid_blocks.chunk_positions::<CHUNK_SIZE>(dimension) {
+            self.chunk_cache
+                .borrow_chunk(&chunk_position, |chunk| {
+                    solid_blocks.insert_chunk::<CHUNK_SIZE, _>(&chunk_position, |offset| {
+                        let block_id = chunk.get(offset);
+                        if *block_id == BlockId::AIR {
+                            &[]
+                        } else {
+                            // Blocks no mod knows about are as solid as they look, a full block
+                            self.blocks
+                                .get(block_id)
+                                .map_or(BlockShape::Cube.collision_boxes(), |block| {
+                                    block.shape().collision_boxes()
+                                })
+                        }
+                    });
+                    async {}
+                })
+                .await?;
+        }
+        Ok(solid_blocks)
+    }
+
+    async fn friction(
+        &self,
+        dimension: DimensionId,
+        position: [i64; 3],
+    ) -> Result<f32, Box<dyn Error + Send + Sync>> {
+        let position = BlockPosition::<CHUNK_SIZE>::from_absolute(
+            position[0],
+            position[1],
+            position[2],
+            dimension,
         );
+        let block_id = self.get_block(&position).await?;
+        Ok(self
+            .blocks
+            .get(&block_id)
+            .map_or(1.0, |block| block.friction()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::entity::Player;
+    use crate::event::WorldEvent;
+    use crate::storage::{FileStorage, MemoryStorage, Storage};
+    use crate::test_util::{EmptyModPack, TempDirectory};
+    use crate::world::World;
+    use crate::CHUNK_SIZE;
+    use block_chunk::ChunkStorage;
+    use cgmath::Deg;
+    use pollster::FutureExt;
+    use std::error::Error;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+    use tokio::sync::Mutex;
+    use uuid::Uuid;
+    use voxelcraft_core::block::BlockPosition;
+    use voxelcraft_core::chunk::ChunkPosition;
+    use voxelcraft_core::entity::EntityPosition;
+    use voxelcraft_id::{BlockId, DimensionId, ModId};
+    use voxelcraft_mod::{Entity, Mod, ModPack};
+
+    #[derive(Debug)]
+    struct TestMod;
+
+    impl Mod for TestMod {
+        fn id(&self) -> &'static ModId {
+            static ID: ModId = ModId::from_u128(1);
+            &ID
+        }
+
+        fn name(&self) -> &str {
+            "Test"
+        }
+
+        fn version(&self) -> &str {
+            "1.0.0"
+        }
+    }
+
+    #[derive(Debug)]
+    struct TestModPack {
+        mods: Vec<Arc<dyn Mod>>,
+    }
+
+    impl ModPack for TestModPack {
+        fn name(&self) -> &str {
+            "Test"
+        }
+
+        fn mods(&self) -> &[Arc<dyn Mod>] {
+            &self.mods
+        }
+
+        fn default_dimension(&self) -> &'static DimensionId {
+            static DIMENSION: DimensionId = DimensionId::from_u128(0);
+            &DIMENSION
+        }
+    }
+
+    /// Fails to store players and to load chunks while `is_failing` is set
+    #[derive(Debug)]
+    struct FailingStorage {
+        storage: MemoryStorage,
+        is_failing: Arc<AtomicBool>,
+    }
+
+    #[async_trait::async_trait]
+    impl ChunkStorage<ChunkPosition> for FailingStorage {
+        async fn store(
+            &self,
+            position: &ChunkPosition,
+            bytes: Vec<u8>,
+        ) -> Result<(), Box<dyn Error + Send + Sync>> {
+            self.storage.store(position, bytes).await
+        }
+
+        async fn load(
+            &self,
+            position: &ChunkPosition,
+        ) -> Result<Option<Vec<u8>>, Box<dyn Error + Send + Sync>> {
+            if self.is_failing.load(Ordering::Relaxed) {
+                return Err("The storage is failing".into());
+            }
+            self.storage.load(position).await
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl Storage for FailingStorage {
+        async fn store_metadata(&self, bytes: Vec<u8>) -> Result<(), Box<dyn Error + Send + Sync>> {
+            self.storage.store_metadata(bytes).await
+        }
+
+        async fn load_metadata(&self) -> Result<Option<Vec<u8>>, Box<dyn Error + Send + Sync>> {
+            self.storage.load_metadata().await
+        }
+
+        async fn store_player(
+            &self,
+            player_id: &Uuid,
+            bytes: Vec<u8>,
+        ) -> Result<(), Box<dyn Error + Send + Sync>> {
+            if self.is_failing.load(Ordering::Relaxed) {
+                return Err("The storage is failing".into());
+            }
+            self.storage.store_player(player_id, bytes).await
+        }
+
+        async fn load_player(
+            &self,
+            player_id: &Uuid,
+        ) -> Result<Option<Vec<u8>>, Box<dyn Error + Send + Sync>> {
+            self.storage.load_player(player_id).await
+        }
+    }
+
+    fn world() -> World {
+        World::create(MemoryStorage::new(), Arc::new(EmptyModPack), "Test", 0)
+            .block_on()
+            .unwrap()
+    }
+
+    fn position(x: i64, y: i64, z: i64) -> BlockPosition<CHUNK_SIZE> {
+        BlockPosition::from_absolute(x, y, z, DimensionId::default())
+    }
+
+    #[test]
+    fn it_should_read_back_a_block_that_was_set() {
+        let world = world();
+        let mut receiver = world.get_event_receiver();
+        let block = BlockId::from_u128(7);
+
+        let previous = world
+            .set_block(&position(-1, 2, 40), block)
+            .block_on()
+            .unwrap();
+
+        assert_eq!(previous, BlockId::AIR);
+        assert_eq!(
+            world.get_block(&position(-1, 2, 40)).block_on().unwrap(),
+            block
+        );
+        assert!(matches!(
+            receiver.try_recv().unwrap(),
+            WorldEvent::BlockChanged(changed, id) if changed == position(-1, 2, 40) && id == block
+        ));
+    }
+
+    #[test]
+    fn it_should_not_broadcast_blocks_that_did_not_change() {
+        let world = world();
+        let mut receiver = world.get_event_receiver();
+
+        world
+            .set_block(&position(0, 0, 0), BlockId::AIR)
+            .block_on()
+            .unwrap();
+
+        assert!(receiver.try_recv().is_err());
+    }
+
+    #[test]
+    fn it_should_set_blocks_in_several_chunks() {
+        let world = world();
+        let mut receiver = world.get_event_receiver();
+        let block = BlockId::from_u128(3);
+        let positions = [position(0, 0, 0), position(-1, 0, 0), position(1, 0, 0)];
+
+        world
+            .set_blocks(positions.iter().map(|p| (p.clone(), block)).collect())
+            .block_on()
+            .unwrap();
+
+        for position in &positions {
+            assert_eq!(world.get_block(position).block_on().unwrap(), block);
+        }
+        let mut changed = 0;
+        while let Ok(WorldEvent::BlockChanged(..)) = receiver.try_recv() {
+            changed += 1;
+        }
+        assert_eq!(changed, positions.len());
+    }
+
+    #[test]
+    fn it_should_read_back_blocks_after_reopening_the_world() {
+        let directory = TempDirectory::new();
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        let blocks = [
+            (position(0, 0, 0), BlockId::from_u128(1)),
+            (position(-1, 40, 7), BlockId::from_u128(2)),
+            (position(1000, -3, 0), BlockId::from_u128(3)),
+        ];
+
+        runtime.block_on(async {
+            let storage = FileStorage::new(directory.path());
+            let world = World::create(storage, Arc::new(EmptyModPack), "Test", 7)
+                .await
+                .unwrap();
+            world.set_blocks(blocks.to_vec()).await.unwrap();
+            world.update(0.0).await.unwrap();
+            world.save().await.unwrap();
+        });
+
+        runtime.block_on(async {
+            let world = World::open(directory.path(), Arc::new(EmptyModPack))
+                .await
+                .unwrap();
+            for (position, block_id) in &blocks {
+                assert_eq!(world.get_block(position).await.unwrap(), *block_id);
+            }
+            assert_eq!(world.name(), "Test");
+            assert_eq!(world.seed(), 7);
+            assert_eq!(world.game_time(), 1);
+        });
+    }
+
+    #[test]
+    fn it_should_refuse_to_open_worlds_with_missing_mods() {
+        let directory = TempDirectory::new();
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        let mod_pack = TestModPack {
+            mods: vec![Arc::new(TestMod)],
+        };
+
+        runtime.block_on(async {
+            World::create(
+                FileStorage::new(directory.path()),
+                Arc::new(mod_pack),
+                "Test",
+                0,
+            )
+            .await
+            .unwrap();
+
+            assert!(World::open(directory.path(), Arc::new(EmptyModPack))
+                .await
+                .is_err());
+        });
+    }
+
+    #[test]
+    fn it_should_restore_players_where_they_left() {
+        let world = world();
+        let player_id = Uuid::new_v4();
+
+        world.load_player(player_id).block_on().unwrap();
+        let spawn = world.get_player_position(player_id).block_on().unwrap();
+        let data = {
+            let players = world.players.lock().block_on();
+            let mut player = players.get(&player_id).unwrap().lock().block_on();
+            player.set_flying(true);
+            player.set_head_rotation(Deg(-20.0), Deg(135.0));
+            player.data()
+        };
+        world.unload_player(player_id).block_on().unwrap();
+        assert!(world.get_player_position(player_id).block_on().is_none());
+
+        world.load_player(player_id).block_on().unwrap();
+
+        let players = world.players.lock().block_on();
+        let player = players.get(&player_id).unwrap().lock().block_on();
+        assert_eq!(player.position(), &spawn);
+        assert!(player.is_flying());
+        assert_eq!(player.data(), data);
+    }
+
+    #[test]
+    fn it_should_keep_players_that_could_not_be_saved() {
+        let is_failing = Arc::new(AtomicBool::new(false));
+        let storage = FailingStorage {
+            storage: MemoryStorage::new(),
+            is_failing: Arc::clone(&is_failing),
+        };
+        let world = World::create(storage, Arc::new(EmptyModPack), "Test", 0)
+            .block_on()
+            .unwrap();
+        let player_id = Uuid::new_v4();
+        world.load_player(player_id).block_on().unwrap();
+
+        is_failing.store(true, Ordering::Relaxed);
+        assert!(world.unload_player(player_id).block_on().is_err());
+        assert!(world.get_player_position(player_id).block_on().is_some());
+
+        is_failing.store(false, Ordering::Relaxed);
+        world.unload_player(player_id).block_on().unwrap();
+        assert!(world.get_player_position(player_id).block_on().is_none());
+    }
+
+    #[test]
+    fn it_should_keep_ticking_when_a_player_fails_to_update() {
+        let is_failing = Arc::new(AtomicBool::new(false));
+        let storage = FailingStorage {
+            storage: MemoryStorage::new(),
+            is_failing: Arc::clone(&is_failing),
+        };
+        let world = World::create(storage, Arc::new(EmptyModPack), "Test", 0)
+            .block_on()
+            .unwrap();
+        let player_id = Uuid::new_v4();
+        world.load_player(player_id).block_on().unwrap();
+        // Loads the chunks around the spawn while the storage still works
+        world.update(0.1).block_on().unwrap();
+        let spawn = world.get_player_position(player_id).block_on().unwrap();
+
+        // A player far away from the spawn needs chunks that can not be loaded any more
+        let stranded_id = Uuid::new_v4();
+        let stranded = Player::new(
+            stranded_id,
+            EntityPosition::from_absolute::<CHUNK_SIZE>(10_000.0, 0.0, 0.0, DimensionId::default()),
+            world.outgoing_events_sender.clone(),
+        );
+        world
+            .players
+            .lock()
+            .block_on()
+            .insert(stranded_id, Arc::new(Mutex::new(stranded)));
+        is_failing.store(true, Ordering::Relaxed);
+
+        world.update(0.1).block_on().unwrap();
+
+        assert_eq!(world.game_time(), 2);
+        assert_ne!(
+            world.get_player_position(player_id).block_on().unwrap(),
+            spawn
+        );
+    }
+
+    #[test]
+    fn it_should_not_load_a_player_that_is_in_the_world_already() {
+        let world = world();
+        let player_id = Uuid::new_v4();
+        world.load_player(player_id).block_on().unwrap();
+
+        assert!(world.load_player(player_id).block_on().is_err());
+    }
+
+    #[test]
+    fn it_should_not_open_a_directory_without_a_world() {
+        let directory = TempDirectory::new();
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+
+        let world = runtime.block_on(World::open(directory.path(), Arc::new(EmptyModPack)));
+
+        assert!(world.is_err());
     }
 }
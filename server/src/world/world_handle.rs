@@ -0,0 +1,211 @@
+use crate::world::World;
+use std::error::Error;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+use tokio::time::{sleep_until, timeout, Instant};
+
+/// The most ticks that are run back to back to catch up after the world fell behind
+const MAX_CATCH_UP_TICKS: u32 = 10;
+
+/// Keeps a world running, and stops it again with [`WorldHandle::shutdown`].
+///
+/// The world is ticked on its own task, and saved periodically on another one so that writing to
+/// storage never holds up a tick
+#[derive(Debug)]
+pub struct WorldHandle {
+    world: Arc<World>,
+    stop_sender: watch::Sender<bool>,
+    update_loop: JoinHandle<()>,
+    autosave_loop: Option<JoinHandle<()>>,
+}
+
+impl WorldHandle {
+    /// Starts ticking the world, and saves it every `autosave_interval` if one is given
+    pub fn start(world: Arc<World>, autosave_interval: Option<Duration>) -> Self {
+        let (stop_sender, stop_receiver) = watch::channel(false);
+        let update_loop =
+            tokio::spawn(Self::update_loop(Arc::clone(&world), stop_receiver.clone()));
+        let autosave_loop = autosave_interval.map(|autosave_interval| {
+            tokio::spawn(Self::autosave_loop(
+                Arc::clone(&world),
+                autosave_interval,
+                stop_receiver,
+            ))
+        });
+
+        Self {
+            world,
+            stop_sender,
+            update_loop,
+            autosave_loop,
+        }
+    }
+
+    pub fn world(&self) -> &Arc<World> {
+        &self.world
+    }
+
+    /// Stops the world from ticking and saves it.
+    ///
+    /// The world is saved even if the update loop did not stop cleanly, in which case that is
+    /// reported as the error
+    pub async fn shutdown(self) -> Result<(), Box<dyn Error + Send + Sync>> {
+        log::info!("Shutting down the world");
+        // The loops also stop when the sender is dropped, so there is nothing to do on failure
+        let _ = self.stop_sender.send(true);
+
+        let update_result = self.update_loop.await;
+        if let Some(autosave_loop) = self.autosave_loop {
+            if let Err(error) = autosave_loop.await {
+                log::error!("The autosave loop did not stop cleanly: {}", error);
+            }
+        }
+
+        let save_result = self.world.save().await;
+        update_result?;
+        save_result
+    }
+
+    /// Updates the world in fixed steps of [`World::tick_duration`]. When the world falls behind
+    /// it catches up by running several ticks in a row, up to [`MAX_CATCH_UP_TICKS`], after which
+    /// the remaining ticks are skipped
+    async fn update_loop(world: Arc<World>, stop_receiver: watch::Receiver<bool>) {
+        let tick_duration = World::tick_duration();
+        let delta = tick_duration.as_secs_f64();
+        let mut next_tick = Instant::now() + tick_duration;
+        loop {
+            sleep_until(next_tick).await;
+            if *stop_receiver.borrow() {
+                return;
+            }
+
+            let mut ticks = 0;
+            while next_tick <= Instant::now() && ticks < MAX_CATCH_UP_TICKS {
+                if let Err(error) = world.update(delta).await {
+                    log::error!("Failed to update the world: {}", error);
+                }
+                next_tick += tick_duration;
+                ticks += 1;
+            }
+
+            let now = Instant::now();
+            if next_tick <= now {
+                let skipped = (now - next_tick).as_nanos() / tick_duration.as_nanos() + 1;
+                log::warn!("The world can not keep up, skipping {} ticks", skipped);
+                next_tick = now + tick_duration;
+            }
+        }
+    }
+
+    async fn autosave_loop(
+        world: Arc<World>,
+        autosave_interval: Duration,
+        mut stop_receiver: watch::Receiver<bool>,
+    ) {
+        loop {
+            match timeout(autosave_interval, stop_receiver.changed()).await {
+                // Either asked to stop, or the handle is gone
+                Ok(_) => return,
+                Err(_) => {
+                    log::info!("Saving the world");
+                    if let Err(error) = world.save().await {
+                        log::error!("Failed to save the world: {}", error);
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::storage::FileStorage;
+    use crate::test_util::{EmptyModPack, TempDirectory};
+    use crate::world::{World, WorldHandle};
+    use crate::CHUNK_SIZE;
+    use std::sync::Arc;
+    use std::time::Duration;
+    use voxelcraft_core::block::BlockPosition;
+    use voxelcraft_id::{BlockId, DimensionId};
+
+    fn runtime() -> tokio::runtime::Runtime {
+        tokio::runtime::Builder::new_current_thread()
+            .enable_time()
+            .build()
+            .unwrap()
+    }
+
+    fn position() -> BlockPosition<CHUNK_SIZE> {
+        BlockPosition::from_absolute(3, -5, 40, DimensionId::default())
+    }
+
+    async fn create_world(directory: &std::path::Path) -> Arc<World> {
+        let world = World::create(
+            FileStorage::new(directory),
+            Arc::new(EmptyModPack),
+            "Test",
+            0,
+        )
+        .await
+        .unwrap();
+        Arc::new(world)
+    }
+
+    #[test]
+    fn it_should_save_the_world_on_shutdown() {
+        let directory = TempDirectory::new();
+
+        runtime().block_on(async {
+            let handle = WorldHandle::start(create_world(directory.path()).await, None);
+            handle
+                .world()
+                .set_block(&position(), BlockId::from_u128(2))
+                .await
+                .unwrap();
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            let game_time = handle.world().game_time();
+            assert!(handle.world().tick_statistics().ticks_per_second > 0.0);
+            handle.shutdown().await.unwrap();
+
+            let world = World::open(directory.path(), Arc::new(EmptyModPack))
+                .await
+                .unwrap();
+            assert_eq!(
+                world.get_block(&position()).await.unwrap(),
+                BlockId::from_u128(2)
+            );
+            assert!(world.game_time() >= game_time);
+        });
+    }
+
+    #[test]
+    fn it_should_save_the_world_periodically() {
+        let directory = TempDirectory::new();
+
+        runtime().block_on(async {
+            let handle = WorldHandle::start(
+                create_world(directory.path()).await,
+                Some(Duration::from_millis(20)),
+            );
+            handle
+                .world()
+                .set_block(&position(), BlockId::from_u128(2))
+                .await
+                .unwrap();
+            tokio::time::sleep(Duration::from_millis(100)).await;
+
+            // Opened next to the running world, as if the game had crashed
+            let world = World::open(directory.path(), Arc::new(EmptyModPack))
+                .await
+                .unwrap();
+            assert_eq!(
+                world.get_block(&position()).await.unwrap(),
+                BlockId::from_u128(2)
+            );
+
+            handle.shutdown().await.unwrap();
+        });
+    }
+}
@@ -0,0 +1,37 @@
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::runtime::Runtime;
+use voxelcraft_server::remote::Server;
+use voxelcraft_server::storage::MemoryStorage;
+use voxelcraft_server::world::{World, WorldHandle};
+
+#[allow(dead_code)]
+#[path = "../../src/test_util.rs"]
+mod test_util;
+
+pub use self::test_util::EmptyModPack;
+
+/// How long a test waits for a message before giving up
+pub const TIMEOUT: Duration = Duration::from_secs(5);
+
+pub fn runtime() -> Runtime {
+    tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .unwrap()
+}
+
+/// Starts a ticking world with a server in front of it
+pub async fn start_server() -> (WorldHandle, SocketAddr) {
+    let world = World::create(MemoryStorage::new(), Arc::new(EmptyModPack), "Test", 0)
+        .await
+        .unwrap();
+    let handle = Arc::new(world).start_update_loop(None);
+    let server = Server::bind("127.0.0.1:0", Arc::clone(handle.world()))
+        .await
+        .unwrap();
+    let address = server.local_address().unwrap();
+    tokio::spawn(server.run());
+    (handle, address)
+}
@@ -0,0 +1,399 @@
+mod common;
+
+use crate::common::{runtime, start_server, EmptyModPack, TIMEOUT};
+use std::collections::HashSet;
+use std::net::SocketAddr;
+use std::time::Duration;
+use tokio::net::TcpStream;
+use uuid::Uuid;
+use voxelcraft_core::block::BlockPosition;
+use voxelcraft_core::chunk::ChunkPosition;
+use voxelcraft_core::entity::EntityPosition;
+use voxelcraft_id::{BlockId, DimensionId};
+use voxelcraft_server::remote::{
+    read_message, write_message, ClientMessage, ManifestMismatch, ModManifest, ServerMessage,
+    MAX_BLOCKS_PER_MESSAGE, PROTOCOL_VERSION,
+};
+use voxelcraft_server::{Chunk, CHUNK_SIZE};
+
+struct TestClient {
+    stream: TcpStream,
+}
+
+impl TestClient {
+    async fn connect(address: SocketAddr) -> Self {
+        Self {
+            stream: TcpStream::connect(address).await.unwrap(),
+        }
+    }
+
+    /// Connects, and does the handshake with the same mods as the server
+    async fn handshake(address: SocketAddr) -> Self {
+        let mut client = Self::connect(address).await;
+        client
+            .send(ClientMessage::Handshake {
+                protocol_version: PROTOCOL_VERSION,
+            })
+            .await;
+        assert_eq!(
+            client.receive().await,
+            Some(ServerMessage::HandshakeAccepted)
+        );
+        let manifest = ModManifest::new(&EmptyModPack).await;
+        client.send(ClientMessage::Manifest(manifest.clone())).await;
+        assert_eq!(
+            client.receive().await,
+            Some(ServerMessage::ManifestAccepted(manifest))
+        );
+        client
+    }
+
+    /// Connects, does the handshake and logs in
+    async fn join(address: SocketAddr, player_id: Uuid) -> (Self, EntityPosition) {
+        let mut client = Self::handshake(address).await;
+        client
+            .send(ClientMessage::Login {
+                player_id: player_id.as_u128(),
+            })
+            .await;
+        match client.receive().await {
+            Some(ServerMessage::LoggedIn { position }) => (client, position),
+            message => panic!("Expected to be logged in, got {:?}", message),
+        }
+    }
+
+    async fn send(&mut self, message: ClientMessage) {
+        write_message(&mut self.stream, &message).await.unwrap();
+    }
+
+    async fn receive(&mut self) -> Option<ServerMessage> {
+        tokio::time::timeout(TIMEOUT, read_message(&mut self.stream))
+            .await
+            .expect("Timed out waiting for a message")
+            .unwrap()
+    }
+
+    /// Skips messages until one matches
+    async fn receive_matching<F: Fn(&ServerMessage) -> bool>(
+        &mut self,
+        matches: F,
+    ) -> ServerMessage {
+        loop {
+            match self.receive().await {
+                Some(message) if matches(&message) => return message,
+                Some(_) => {}
+                None => panic!("The server closed the connection"),
+            }
+        }
+    }
+}
+
+#[test]
+fn it_should_refuse_clients_with_another_protocol_version() {
+    runtime().block_on(async {
+        let (_handle, address) = start_server().await;
+        let mut client = TestClient::connect(address).await;
+
+        client
+            .send(ClientMessage::Handshake {
+                protocol_version: PROTOCOL_VERSION + 1,
+            })
+            .await;
+
+        assert!(matches!(
+            client.receive().await,
+            Some(ServerMessage::Disconnect { .. })
+        ));
+        assert_eq!(client.receive().await, None);
+    });
+}
+
+#[test]
+fn it_should_refuse_players_that_are_already_playing() {
+    runtime().block_on(async {
+        let (_handle, address) = start_server().await;
+        let player_id = Uuid::new_v4();
+        let (_first, _) = TestClient::join(address, player_id).await;
+        let mut second = TestClient::handshake(address).await;
+
+        second
+            .send(ClientMessage::Login {
+                player_id: player_id.as_u128(),
+            })
+            .await;
+
+        assert!(matches!(
+            second.receive().await,
+            Some(ServerMessage::Disconnect { .. })
+        ));
+    });
+}
+
+#[test]
+fn it_should_refuse_clients_with_other_mods() {
+    runtime().block_on(async {
+        let (_handle, address) = start_server().await;
+        let mut client = TestClient::connect(address).await;
+        client
+            .send(ClientMessage::Handshake {
+                protocol_version: PROTOCOL_VERSION,
+            })
+            .await;
+        client.receive().await;
+
+        client
+            .send(ClientMessage::Manifest(ModManifest {
+                mods: vec![],
+                blocks: vec![BlockId::from_u128(8)],
+                dimensions: vec![],
+            }))
+            .await;
+
+        assert_eq!(
+            client.receive().await,
+            Some(ServerMessage::ModsMismatched(vec![
+                ManifestMismatch::UnexpectedBlock(BlockId::from_u128(8))
+            ]))
+        );
+        assert_eq!(client.receive().await, None);
+    });
+}
+
+#[test]
+fn it_should_send_requested_chunks() {
+    runtime().block_on(async {
+        let (handle, address) = start_server().await;
+        let block = BlockPosition::from_absolute(1, 2, 3, DimensionId::default());
+        handle
+            .world()
+            .set_block(&block, BlockId::from_u128(5))
+            .await
+            .unwrap();
+        let (mut client, _) = TestClient::join(address, Uuid::new_v4()).await;
+
+        client
+            .send(ClientMessage::RequestChunk(block.chunk_position))
+            .await;
+
+        // The chunks around the player are streamed as well
+        let message = client
+            .receive_matching(|message| {
+                matches!(
+                    message,
+                    ServerMessage::ChunkData { position, .. }
+                        if *position == block.chunk_position
+                )
+            })
+            .await;
+        let (position, bytes) = match message {
+            ServerMessage::ChunkData { position, bytes } => (position, bytes),
+            _ => unreachable!(),
+        };
+        assert_eq!(position, block.chunk_position);
+        let chunk = Chunk::from_compressed(&bytes).unwrap();
+        assert_eq!(*chunk.get(&block.offset), BlockId::from_u128(5));
+    });
+}
+
+#[test]
+fn it_should_not_send_requested_chunks_out_of_view() {
+    runtime().block_on(async {
+        let (_handle, address) = start_server().await;
+        let (mut client, position) = TestClient::join(address, Uuid::new_v4()).await;
+        let far = ChunkPosition {
+            x: position.chunk_position.x + 1000,
+            ..position.chunk_position
+        };
+
+        client.send(ClientMessage::RequestChunk(far)).await;
+
+        let message = client
+            .receive_matching(|message| match message {
+                ServerMessage::ChunkUnavailable(position) => *position == far,
+                ServerMessage::ChunkData { position, .. } => *position == far,
+                _ => false,
+            })
+            .await;
+        assert_eq!(message, ServerMessage::ChunkUnavailable(far));
+    });
+}
+
+#[test]
+fn it_should_stream_the_chunks_around_the_player_nearest_first() {
+    runtime().block_on(async {
+        let (_handle, address) = start_server().await;
+
+        let (mut client, position) = TestClient::join(address, Uuid::new_v4()).await;
+
+        let message = client
+            .receive_matching(|message| matches!(message, ServerMessage::ChunkData { .. }))
+            .await;
+        assert!(matches!(
+            message,
+            ServerMessage::ChunkData { position: chunk_position, .. }
+                if chunk_position == position.chunk_position
+        ));
+    });
+}
+
+#[test]
+fn it_should_unload_chunks_that_went_out_of_view() {
+    runtime().block_on(async {
+        let (_handle, address) = start_server().await;
+        let (mut client, position) = TestClient::join(address, Uuid::new_v4()).await;
+        // Flying keeps the player from falling out of the chunk
+        client.send(ClientMessage::SetFlying(true)).await;
+
+        client.send(ClientMessage::SetViewDistance(0)).await;
+
+        let mut chunks = HashSet::new();
+        let mut has_unloaded = false;
+        while !has_unloaded || chunks.len() != 1 {
+            match client.receive().await {
+                Some(ServerMessage::ChunkData { position, .. }) => {
+                    chunks.insert(position);
+                }
+                Some(ServerMessage::UnloadChunk(position)) => {
+                    assert!(chunks.remove(&position), "{} was never loaded", position);
+                    has_unloaded = true;
+                }
+                Some(_) => {}
+                None => panic!("The server closed the connection"),
+            }
+        }
+        assert!(chunks.contains(&position.chunk_position));
+    });
+}
+
+#[test]
+fn it_should_broadcast_world_events_to_every_client() {
+    runtime().block_on(async {
+        let (handle, address) = start_server().await;
+        let first_id = Uuid::new_v4();
+        let (mut first, _) = TestClient::join(address, first_id).await;
+        let (mut second, _) = TestClient::join(address, Uuid::new_v4()).await;
+
+        // Nothing is solid in the empty mod pack, so the players are falling
+        second
+            .receive_matching(|message| {
+                matches!(
+                    message,
+                    ServerMessage::EntityPositionChanged { entity_id, .. }
+                        if *entity_id == first_id.as_u128()
+                )
+            })
+            .await;
+
+        let block = BlockPosition::from_absolute(-4, 0, 9, DimensionId::default());
+        handle
+            .world()
+            .set_block(&block, BlockId::from_u128(3))
+            .await
+            .unwrap();
+        for client in [&mut first, &mut second] {
+            let expected = ServerMessage::BlockChanged {
+                position: block.clone(),
+                block_id: BlockId::from_u128(3),
+            };
+            client
+                .receive_matching(|message| *message == expected)
+                .await;
+        }
+    });
+}
+
+#[test]
+fn it_should_refuse_blocks_out_of_view_without_disconnecting() {
+    runtime().block_on(async {
+        let (handle, address) = start_server().await;
+        let (mut client, position) = TestClient::join(address, Uuid::new_v4()).await;
+        client.send(ClientMessage::SetFlying(true)).await;
+        let near = position.block_position::<CHUNK_SIZE>();
+        let far = BlockPosition::from_absolute(100_000, 0, 0, position.chunk_position.dimension);
+
+        client
+            .send(ClientMessage::SetBlocks(vec![
+                (near.clone(), BlockId::from_u128(2)),
+                (far.clone(), BlockId::from_u128(2)),
+            ]))
+            .await;
+        client
+            .receive_matching(|message| matches!(message, ServerMessage::BlocksRefused { .. }))
+            .await;
+        client
+            .send(ClientMessage::SetBlocks(vec![
+                (
+                    near.clone(),
+                    BlockId::from_u128(2)
+                );
+                MAX_BLOCKS_PER_MESSAGE + 1
+            ]))
+            .await;
+        client
+            .receive_matching(|message| matches!(message, ServerMessage::BlocksRefused { .. }))
+            .await;
+        assert_eq!(handle.world().get_block(&near).await.unwrap(), BlockId::AIR);
+
+        client
+            .send(ClientMessage::SetBlocks(vec![(
+                near.clone(),
+                BlockId::from_u128(2),
+            )]))
+            .await;
+        let expected = ServerMessage::BlockChanged {
+            position: near,
+            block_id: BlockId::from_u128(2),
+        };
+        client
+            .receive_matching(|message| *message == expected)
+            .await;
+    });
+}
+
+#[test]
+fn it_should_unload_players_that_leave() {
+    runtime().block_on(async {
+        let (handle, address) = start_server().await;
+        let leaving_id = Uuid::new_v4();
+        let disconnecting_id = Uuid::new_v4();
+        let (mut leaving, _) = TestClient::join(address, leaving_id).await;
+        let (disconnecting, _) = TestClient::join(address, disconnecting_id).await;
+
+        leaving.send(ClientMessage::Disconnect).await;
+        drop(disconnecting);
+
+        for player_id in [leaving_id, disconnecting_id] {
+            let unloaded = tokio::time::timeout(TIMEOUT, async {
+                while handle
+                    .world()
+                    .get_player_position(player_id)
+                    .await
+                    .is_some()
+                {
+                    tokio::time::sleep(Duration::from_millis(10)).await;
+                }
+            })
+            .await;
+            assert!(unloaded.is_ok(), "Player {} was not unloaded", player_id);
+        }
+    });
+}
+
+#[test]
+fn it_should_log_new_players_in_at_the_spawn() {
+    runtime().block_on(async {
+        let (_handle, address) = start_server().await;
+
+        let (_client, position) = TestClient::join(address, Uuid::new_v4()).await;
+
+        assert_eq!(
+            position.chunk_position,
+            ChunkPosition {
+                x: 0,
+                y: 0,
+                z: 0,
+                dimension: DimensionId::default()
+            }
+        );
+    });
+}
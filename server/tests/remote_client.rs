@@ -0,0 +1,219 @@
+mod common;
+
+use crate::common::{runtime, start_server, EmptyModPack, TIMEOUT};
+use std::sync::Arc;
+use std::time::Duration;
+use uuid::Uuid;
+use voxelcraft_core::block::BlockPosition;
+use voxelcraft_id::{BlockId, DimensionId, ModId};
+use voxelcraft_mod::{Mod, ModPack};
+use voxelcraft_server::client::Client;
+use voxelcraft_server::event::WorldEvent;
+use voxelcraft_server::remote::{IncompatibleModsError, ManifestMismatch, RemoteClient};
+use voxelcraft_server::CHUNK_SIZE;
+
+#[derive(Debug)]
+struct ExtraMod;
+
+impl Mod for ExtraMod {
+    fn id(&self) -> &'static ModId {
+        static ID: ModId = ModId::from_u128(42);
+        &ID
+    }
+
+    fn name(&self) -> &str {
+        "Extra"
+    }
+
+    fn version(&self) -> &str {
+        "1.2.3"
+    }
+}
+
+#[derive(Debug)]
+struct ExtraModPack {
+    mods: Vec<Arc<dyn Mod>>,
+}
+
+impl ModPack for ExtraModPack {
+    fn name(&self) -> &str {
+        "Extra"
+    }
+
+    fn mods(&self) -> &[Arc<dyn Mod>] {
+        &self.mods
+    }
+
+    fn default_dimension(&self) -> &'static DimensionId {
+        EmptyModPack.default_dimension()
+    }
+}
+
+fn block(x: i64, y: i64, z: i64) -> BlockPosition<CHUNK_SIZE> {
+    BlockPosition::from_absolute(x, y, z, DimensionId::default())
+}
+
+#[test]
+fn it_should_read_chunks_from_the_server() {
+    runtime().block_on(async {
+        let (handle, address) = start_server().await;
+        let position = block(40, -3, 7);
+        handle
+            .world()
+            .set_block(&position, BlockId::from_u128(9))
+            .await
+            .unwrap();
+        let client = RemoteClient::connect(address, Uuid::new_v4(), &EmptyModPack)
+            .await
+            .unwrap();
+        client.begin_joining_world().await.unwrap();
+
+        let block_id = client
+            .get_chunk(position.chunk_position, |chunk| async move {
+                *chunk.get(&position.offset)
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(block_id, BlockId::from_u128(9));
+        assert_eq!(
+            client.position().await,
+            handle
+                .world()
+                .get_player_position(client.player_id())
+                .await
+                .unwrap()
+        );
+    });
+}
+
+#[test]
+fn it_should_share_block_changes_between_clients() {
+    runtime().block_on(async {
+        let (handle, address) = start_server().await;
+        let first = RemoteClient::connect(address, Uuid::new_v4(), &EmptyModPack)
+            .await
+            .unwrap();
+        let second = RemoteClient::connect(address, Uuid::new_v4(), &EmptyModPack)
+            .await
+            .unwrap();
+        first.begin_joining_world().await.unwrap();
+        second.begin_joining_world().await.unwrap();
+        let position = block(1, 2, 3);
+        // Both have the chunk before it changes
+        assert_eq!(second.get_block(&position).await.unwrap(), BlockId::AIR);
+        let mut events = second.get_world_event_receiver().await;
+
+        let previous = first
+            .set_block(&position, BlockId::from_u128(4))
+            .await
+            .unwrap();
+
+        assert_eq!(previous, BlockId::AIR);
+        assert_eq!(
+            first.get_block(&position).await.unwrap(),
+            BlockId::from_u128(4)
+        );
+        tokio::time::timeout(TIMEOUT, async {
+            loop {
+                if let WorldEvent::BlockChanged(changed, block_id) = events.recv().await.unwrap() {
+                    assert_eq!(changed, position);
+                    assert_eq!(block_id, BlockId::from_u128(4));
+                    return;
+                }
+            }
+        })
+        .await
+        .unwrap();
+        assert_eq!(
+            second.get_block(&position).await.unwrap(),
+            BlockId::from_u128(4)
+        );
+        assert_eq!(
+            handle.world().get_block(&position).await.unwrap(),
+            BlockId::from_u128(4)
+        );
+    });
+}
+
+#[test]
+fn it_should_follow_the_position_of_the_player() {
+    runtime().block_on(async {
+        let (_handle, address) = start_server().await;
+        let client = RemoteClient::connect(address, Uuid::new_v4(), &EmptyModPack)
+            .await
+            .unwrap();
+        client.begin_joining_world().await.unwrap();
+        let spawn = client.position().await;
+
+        // Nothing is solid in the empty mod pack, so the player falls
+        tokio::time::timeout(TIMEOUT, async {
+            while client.position().await == spawn {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .unwrap();
+    });
+}
+
+#[test]
+fn it_should_save_the_player_when_leaving() {
+    runtime().block_on(async {
+        let (handle, address) = start_server().await;
+        let client = RemoteClient::connect(address, Uuid::new_v4(), &EmptyModPack)
+            .await
+            .unwrap();
+        client.begin_joining_world().await.unwrap();
+
+        client.leave_world().await.unwrap();
+
+        assert!(!client.is_connected());
+        assert!(handle
+            .world()
+            .get_player_position(client.player_id())
+            .await
+            .is_none());
+    });
+}
+
+#[test]
+fn it_should_fail_to_join_as_a_player_that_is_already_playing() {
+    runtime().block_on(async {
+        let (_handle, address) = start_server().await;
+        let player_id = Uuid::new_v4();
+        let first = RemoteClient::connect(address, player_id, &EmptyModPack)
+            .await
+            .unwrap();
+        first.begin_joining_world().await.unwrap();
+        let second = RemoteClient::connect(address, player_id, &EmptyModPack)
+            .await
+            .unwrap();
+
+        assert!(second.begin_joining_world().await.is_err());
+    });
+}
+
+#[test]
+fn it_should_fail_to_connect_with_other_mods() {
+    runtime().block_on(async {
+        let (_handle, address) = start_server().await;
+        let mod_pack = ExtraModPack {
+            mods: vec![Arc::new(ExtraMod)],
+        };
+
+        let error = RemoteClient::connect(address, Uuid::new_v4(), &mod_pack)
+            .await
+            .unwrap_err();
+
+        let error = error.downcast::<IncompatibleModsError>().unwrap();
+        assert_eq!(
+            error.mismatches,
+            vec![ManifestMismatch::UnexpectedMod {
+                id: ModId::from_u128(42),
+                name: "Extra".to_string(),
+                version: "1.2.3".to_string(),
+            }]
+        );
+    });
+}
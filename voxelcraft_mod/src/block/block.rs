@@ -1,6 +1,6 @@
 use std::fmt::Debug;
 
-use block_chunk::mesh::FaceDirection;
+use block_chunk::mesh::{BlockShape, FaceDirection};
 use mipmap::Mipmap;
 use voxelcraft_id::{BlockId, FaceId, ModId};
 
@@ -15,10 +15,15 @@ pub trait Block: Debug + Send + Sync {
     fn get_face_for_side(&self, face_direction: &FaceDirection) -> Option<&'static FaceId> {
         None
     }
-    fn is_standard_square(&self) -> bool {
-        true
+    fn shape(&self) -> BlockShape {
+        BlockShape::Cube
     }
     fn is_transparent(&self) -> bool {
         false
     }
+    /// How much grip entities walking on the block have. Most blocks have a friction of 1.0, while
+    /// slippery blocks such as ice have less
+    fn friction(&self) -> f32 {
+        1.0
+    }
 }
@@ -0,0 +1,22 @@
+use std::error::Error;
+use voxelcraft_core::collision::{BoundingBox, SolidBlocks};
+use voxelcraft_id::DimensionId;
+
+/// The part of the world that entities collide with
+#[async_trait::async_trait]
+pub trait CollisionWorld: Send + Sync {
+    /// Finds the solid blocks within the region
+    async fn solid_blocks(
+        &self,
+        dimension: DimensionId,
+        region: &BoundingBox,
+    ) -> Result<SolidBlocks, Box<dyn Error + Send + Sync>>;
+
+    /// The friction of the block at the given absolute block coordinates, see
+    /// [`crate::Block::friction`]
+    async fn friction(
+        &self,
+        dimension: DimensionId,
+        position: [i64; 3],
+    ) -> Result<f32, Box<dyn Error + Send + Sync>>;
+}
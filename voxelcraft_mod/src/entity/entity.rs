@@ -1,3 +1,5 @@
+use crate::CollisionWorld;
+use std::error::Error;
 use uuid::Uuid;
 use voxelcraft_core::entity::EntityPosition;
 
@@ -6,5 +8,9 @@ use voxelcraft_core::entity::EntityPosition;
 pub trait Entity {
     fn id(&self) -> Uuid;
     fn position(&self) -> &EntityPosition;
-    async fn update_position(&mut self, delta: f64);
+    async fn update_position(
+        &mut self,
+        delta: f64,
+        world: &dyn CollisionWorld,
+    ) -> Result<(), Box<dyn Error + Send + Sync>>;
 }
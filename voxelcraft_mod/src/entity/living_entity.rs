@@ -1,3 +1,8 @@
 use crate::Entity;
+use voxelcraft_core::collision::BoundingBox;
 
-pub trait LivingEntity: Entity {}
+pub trait LivingEntity: Entity {
+    /// The space taken up by the entity at its current position, which it should not move in to
+    /// solid blocks with
+    fn bounding_box(&self) -> BoundingBox;
+}
@@ -1,7 +1,9 @@
+mod collision_world;
 mod entity;
 mod item;
 mod living_entity;
 
+pub use self::collision_world::CollisionWorld;
 pub use self::entity::Entity;
 pub use self::item::Item;
 pub use self::living_entity::LivingEntity;
@@ -10,6 +10,7 @@ pub use self::entity::*;
 pub use self::mod_pack::ModPack;
 pub use self::module::Mod;
 pub use block::Block;
+pub use block_chunk::mesh::{BlockShape, ShapeBox};
 pub use block_chunk::ChunkFactory;
 pub use block_chunk::ChunkStorage;
 pub use world_generator::WorldGenerator;
@@ -8,6 +8,9 @@ use voxelcraft_id::ModId;
 pub trait Mod: Send + Sync + Debug {
     fn id(&self) -> &'static ModId;
     fn name(&self) -> &str;
+    /// Clients can only play on a server that has the exact same version of every mod, usually
+    /// `env!("CARGO_PKG_VERSION")`
+    fn version(&self) -> &str;
     async fn register_blocks(&self) -> Vec<Arc<dyn Block>> {
         vec![]
     }